@@ -1,19 +1,35 @@
 use ski_analyzer_lib::config::{set_config, Config};
+use ski_analyzer_lib::dem::{Dem, ElevationCorrection};
 use ski_analyzer_lib::error::{Error, ErrorType, Result};
-use ski_analyzer_lib::gpx_analyzer::analyze_route;
+use ski_analyzer_lib::geojson_export::{route_to_geojson, ski_area_to_geojson};
+use ski_analyzer_lib::gpx_analyzer::{
+    analyze_routes, export_gpx, AnalysisMode, AnalyzedRoute, MoveProfile,
+    NoiseFilter, Smoothing, TripStatistics, DEFAULT_PROFILE,
+};
+use ski_analyzer_lib::graph::{
+    export_graph, plan_route, reachable_from_lift, way_to_valley, CostModel,
+    Graph, GraphFormat,
+};
+use ski_analyzer_lib::osm_file::load_ski_area_details_by_name;
 use ski_analyzer_lib::osm_query::{
     query_ski_area_details_by_id, query_ski_areas_by_name,
 };
 use ski_analyzer_lib::osm_reader::Document;
-use ski_analyzer_lib::ski_area::{SkiArea, SkiAreaMetadata};
+use ski_analyzer_lib::ski_area::{Difficulty, SkiArea, SkiAreaMetadata};
 use ski_analyzer_lib::utils::cancel::CancellationToken;
-use ski_analyzer_lib::utils::gpx::load_from_file as load_gpx;
+use ski_analyzer_lib::utils::gpx::save_to_file as save_gpx;
 use ski_analyzer_lib::utils::json::{
     load_from_file, save_to_file, save_to_file_pretty,
 };
+use ski_analyzer_lib::utils::track::load_from_file as load_track;
 
 use clap::{Args, Parser, Subcommand};
+use geo::Point;
 use serde::Serialize;
+use time::UtcOffset;
+
+use std::fs;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 struct ArgParser {
@@ -46,6 +62,80 @@ impl SerializedOutput {
     }
 }
 
+#[derive(Clone, Args)]
+struct NoiseFilterArgs {
+    /// Remove single points that would require a higher speed (m/s) to reach
+    /// and leave, e.g. 60
+    #[arg(long)]
+    max_speed: Option<f64>,
+    /// Keep only the first of consecutive points with the same time
+    #[arg(long)]
+    collapse_duplicates: bool,
+    /// Smoothing of the waypoint positions
+    #[arg(long, value_enum, default_value_t = Smoothing::None)]
+    smoothing: Smoothing,
+}
+
+impl NoiseFilterArgs {
+    fn to_filter(&self) -> NoiseFilter {
+        NoiseFilter {
+            max_speed: self.max_speed,
+            collapse_duplicates: self.collapse_duplicates,
+            smoothing: self.smoothing,
+        }
+    }
+}
+
+fn open_dem(path: &Option<PathBuf>) -> Result<Option<Dem>> {
+    path.as_ref().map(Dem::open).transpose()
+}
+
+/// Parses a point given as "longitude,latitude".
+fn parse_point(s: &str) -> std::result::Result<Point, String> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| "expected longitude,latitude".to_string())?;
+    let parse = |v: &str| v.trim().parse::<f64>().map_err(|e| e.to_string());
+    Ok(Point::new(parse(x)?, parse(y)?))
+}
+
+fn load_cost_model(path: Option<String>) -> Result<CostModel> {
+    match path {
+        Some(path) => load_from_file(path),
+        None => Ok(CostModel::default()),
+    }
+}
+
+/// Parses an offset given as "[+-]HH:MM" or "[+-]HH".
+fn parse_utc_offset(s: &str) -> std::result::Result<UtcOffset, String> {
+    let (sign, rest) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let parse = |v: &str| v.parse::<i8>().map_err(|e| e.to_string());
+    let (hours, minutes) = (parse(hours)?, parse(minutes)?);
+    if hours < 0 || !(0..60).contains(&minutes) {
+        return Err(format!("invalid UTC offset: {}", s));
+    }
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0)
+        .map_err(|e| e.to_string())
+}
+
+fn get_single_id(metadatas: Vec<SkiAreaMetadata>) -> Result<u64> {
+    match metadatas.len() {
+        1 => Ok(metadatas.into_iter().next().unwrap().id),
+        0 => Err(Error::new_s(
+            ErrorType::InputError,
+            "ski area entity not found",
+        )),
+        _ => Err(Error::new(
+            ErrorType::InputError,
+            format!("ambiguous ski area: {:?}", metadatas),
+        )),
+    }
+}
+
 #[derive(Clone, Subcommand)]
 enum Command {
     /// Query ski area from OSM
@@ -58,14 +148,177 @@ enum Command {
         /// Remove line parts from inside areas of the same piste.
         #[arg(short, long)]
         clip: bool,
+        /// Elevation model (SRTM .hgt or GeoTIFF file, or a directory of
+        /// them) for lift stations without elevation
+        #[arg(long)]
+        dem: Option<PathBuf>,
+    },
+    /// Parse ski area from an OSM XML or PBF file
+    ParseOsm {
+        /// OSM file name (.osm or .osm.pbf)
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Name of the ski area (case insensitive)
+        #[arg(short, long)]
+        name: String,
+        #[command(flatten)]
+        output: SerializedOutput,
+        /// Remove line parts from inside areas of the same piste.
+        #[arg(short, long)]
+        clip: bool,
+        /// Elevation model (SRTM .hgt or GeoTIFF file, or a directory of
+        /// them) for lift stations without elevation
+        #[arg(long)]
+        dem: Option<PathBuf>,
     },
     Gpx {
-        /// GPX file name
+        /// Track file names (GPX, FIT, TCX, KML, KMZ or CSV). Multiple files
+        /// are merged into one route.
+        #[arg(short, long, num_args = 1.., required = true)]
+        input: Vec<String>,
+        /// Ski area to use (previously output from ParseOsm)
+        #[arg(short, long)]
+        area: String,
+        /// Kind of activity to analyze the route for
+        #[arg(long, value_enum, default_value_t = AnalysisMode::Alpine)]
+        mode: AnalysisMode,
+        /// Move classifier profile: the name of a built-in profile
+        /// (alpine_ski, snowboard, ski_touring, walking, mountain_bike) or a
+        /// JSON file
+        #[arg(long, default_value = DEFAULT_PROFILE)]
+        profile: String,
+        #[command(flatten)]
+        filter: NoiseFilterArgs,
+        /// Elevation model (SRTM .hgt or GeoTIFF file, or a directory of
+        /// them) to correct waypoint elevations
+        #[arg(long)]
+        dem: Option<PathBuf>,
+        /// How to correct waypoint elevations when --dem is given
+        #[arg(long, value_enum, default_value_t = ElevationCorrection::Fill)]
+        elevation_correction: ElevationCorrection,
+        #[command(flatten)]
+        output: SerializedOutput,
+    },
+    /// Calculate statistics of an analyzed route
+    Stats {
+        /// Analyzed route (previously output from Gpx)
         #[arg(short, long)]
         input: String,
         /// Ski area to use (previously output from ParseOsm)
         #[arg(short, long)]
         area: String,
+        /// UTC offset as [+-]HH:MM or [+-]HH, used to split the route into
+        /// days
+        #[arg(
+            short,
+            long,
+            default_value = "+00:00",
+            value_parser = parse_utc_offset,
+            allow_hyphen_values = true
+        )]
+        utc_offset: UtcOffset,
+        #[command(flatten)]
+        output: SerializedOutput,
+    },
+    /// Export an analyzed route to GPX, with one track per activity
+    ExportGpx {
+        /// Analyzed route (previously output from Gpx)
+        #[arg(short, long)]
+        input: String,
+        /// Ski area to use (previously output from ParseOsm)
+        #[arg(short, long)]
+        area: String,
+        /// GPX file name to save result
+        #[arg(short, long)]
+        output: String,
+    },
+    /// Export a ski area and optionally a route to GeoJSON
+    ExportGeoJson {
+        /// Ski area to export (previously output from ParseOsm)
+        #[arg(short, long)]
+        area: String,
+        /// Analyzed route to export (previously output from Gpx)
+        #[arg(short, long)]
+        route: Option<String>,
+        #[command(flatten)]
+        output: SerializedOutput,
+    },
+    /// Export the graph of lifts and pistes connected to each other
+    ExportGraph {
+        /// Ski area to export (previously output from ParseOsm)
+        #[arg(short, long)]
+        area: String,
+        /// Elevation model (SRTM .hgt or GeoTIFF file, or a directory of
+        /// them) to find the downhill direction of pistes
+        #[arg(long)]
+        dem: Option<PathBuf>,
+        #[arg(short, long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// File name to save result
+        #[arg(short, long)]
+        output: String,
+    },
+    /// Plan the fastest route between two places using lifts and pistes
+    PlanRoute {
+        /// Ski area to use (previously output from ParseOsm)
+        #[arg(short, long)]
+        area: String,
+        /// Elevation model (SRTM .hgt or GeoTIFF file, or a directory of
+        /// them) to find the downhill direction of pistes
+        #[arg(long)]
+        dem: Option<PathBuf>,
+        /// Starting point as longitude,latitude
+        #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+        from: Point,
+        /// Destination as longitude,latitude
+        #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+        to: Point,
+        /// Allowed piste difficulty. Can be given multiple times. All
+        /// difficulties are allowed if not given.
+        #[arg(short, long = "difficulty")]
+        difficulties: Vec<Difficulty>,
+        /// Cost model parameters (JSON file)
+        #[arg(long)]
+        cost_model: Option<String>,
+        #[command(flatten)]
+        output: SerializedOutput,
+    },
+    /// List the pistes that can be reached from a lift up to a difficulty
+    Reachable {
+        /// Ski area to use (previously output from ParseOsm)
+        #[arg(short, long)]
+        area: String,
+        /// Elevation model (SRTM .hgt or GeoTIFF file, or a directory of
+        /// them) to find the downhill direction of pistes
+        #[arg(long)]
+        dem: Option<PathBuf>,
+        /// ID of the lift to start from
+        #[arg(short, long)]
+        lift: String,
+        /// Hardest piste difficulty allowed
+        #[arg(short, long, default_value = "easy")]
+        max_difficulty: Difficulty,
+        #[command(flatten)]
+        output: SerializedOutput,
+    },
+    /// Find the easiest way from a position back to the valley
+    WayDown {
+        /// Ski area to use (previously output from ParseOsm)
+        #[arg(short, long)]
+        area: String,
+        /// Elevation model (SRTM .hgt or GeoTIFF file, or a directory of
+        /// them) to find the downhill direction of pistes
+        #[arg(long)]
+        dem: Option<PathBuf>,
+        /// Current position as longitude,latitude
+        #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+        position: Point,
+        /// Maximum distance of the position from a lift or piste in meters
+        #[arg(long, default_value_t = 100.0)]
+        max_distance: f64,
+        /// Cost model parameters (JSON file)
+        #[arg(long)]
+        cost_model: Option<String>,
         #[command(flatten)]
         output: SerializedOutput,
     },
@@ -76,31 +329,39 @@ async fn main() -> Result<()> {
     let args = ArgParser::parse();
     set_config(args.config.clone())?;
     match args.command {
-        Command::QueryOsm { name, output, clip } => {
+        Command::QueryOsm {
+            name,
+            output,
+            clip,
+            dem,
+        } => {
+            let dem = open_dem(&dem)?;
             let json1 = query_ski_areas_by_name(name.as_str()).await?;
             let doc1 = Document::parse(&json1)?;
-            let metadatas = SkiAreaMetadata::find(&doc1)?;
-            let id = match metadatas.len() {
-                1 => metadatas.into_iter().next().unwrap().id,
-                0 => {
-                    return Err(Error::new_s(
-                        ErrorType::InputError,
-                        "ski area entity not found",
-                    ));
-                }
-                _ => {
-                    return Err(Error::new(
-                        ErrorType::InputError,
-                        format!("ambiguous ski area: {:?}", metadatas),
-                    ));
-                }
-            };
+            let id = get_single_id(SkiAreaMetadata::find(&doc1)?)?;
 
             let json2 = query_ski_area_details_by_id(id).await?;
             let doc2 = Document::parse(&json2)?;
 
             let mut ski_area =
-                SkiArea::parse(&CancellationToken::new(), &doc2)?;
+                SkiArea::parse(&CancellationToken::new(), &doc2, dem.as_ref())?;
+            if clip {
+                ski_area.clip_piste_lines();
+            }
+
+            output.write_to_file(&ski_area)?;
+        }
+        Command::ParseOsm {
+            input,
+            name,
+            output,
+            clip,
+            dem,
+        } => {
+            let dem = open_dem(&dem)?;
+            let cancel = CancellationToken::new();
+            let doc = load_ski_area_details_by_name(&cancel, &input, &name)?;
+            let mut ski_area = SkiArea::parse(&cancel, &doc, dem.as_ref())?;
             if clip {
                 ski_area.clip_piste_lines();
             }
@@ -110,18 +371,163 @@ async fn main() -> Result<()> {
         Command::Gpx {
             input,
             area,
+            mode,
+            profile,
+            filter,
+            dem,
+            elevation_correction,
             output,
         } => {
-            let gpx = load_gpx(input)?;
+            let mut tracks: Vec<_> =
+                input.iter().map(load_track).collect::<Result<_>>()?;
+            if let Some(dem) = open_dem(&dem)? {
+                for track in &mut tracks {
+                    dem.correct_elevations(
+                        &mut track.gpx,
+                        elevation_correction,
+                    );
+                }
+            }
 
-            // println!("{:#?}", gpx);
+            // println!("{:#?}", tracks);
 
             let ski_area: SkiArea = load_from_file(area)?;
+            let profile = MoveProfile::find(&profile)?;
 
+            let result = analyze_routes(
+                &CancellationToken::new(),
+                &ski_area,
+                mode,
+                &profile,
+                &filter.to_filter(),
+                tracks,
+            )?;
+            output.write_to_file(&result)?;
+        }
+        Command::Stats {
+            input,
+            area,
+            utc_offset,
+            output,
+        } => {
+            let route: AnalyzedRoute = load_from_file(input)?;
+            let ski_area: SkiArea = load_from_file(area)?;
             let result =
-                analyze_route(&CancellationToken::new(), &ski_area, gpx)?;
+                TripStatistics::calculate(&ski_area, &route, utc_offset);
+            output.write_to_file(&result)?;
+        }
+        Command::ExportGpx {
+            input,
+            area,
+            output,
+        } => {
+            let route: AnalyzedRoute = load_from_file(input)?;
+            let ski_area: SkiArea = load_from_file(area)?;
+            save_gpx(&export_gpx(&ski_area, &route), output)?;
+        }
+        Command::ExportGeoJson {
+            area,
+            route,
+            output,
+        } => {
+            let ski_area: SkiArea = load_from_file(area)?;
+            let mut result = ski_area_to_geojson(&ski_area);
+            if let Some(route) = route {
+                let route: AnalyzedRoute = load_from_file(route)?;
+                result.features.extend(route_to_geojson(&route).features);
+            }
             output.write_to_file(&result)?;
         }
+        Command::ExportGraph {
+            area,
+            dem,
+            format,
+            output,
+        } => {
+            let dem = open_dem(&dem)?;
+            let ski_area: SkiArea = load_from_file(area)?;
+            let graph =
+                Graph::new(&CancellationToken::new(), &ski_area, dem.as_ref())?;
+            fs::write(output, export_graph(&ski_area, &graph, format)?)?;
+        }
+        Command::PlanRoute {
+            area,
+            dem,
+            from,
+            to,
+            difficulties,
+            cost_model,
+            output,
+        } => {
+            let dem = open_dem(&dem)?;
+            let ski_area: SkiArea = load_from_file(area)?;
+            let cost_model = load_cost_model(cost_model)?;
+            let graph =
+                Graph::new(&CancellationToken::new(), &ski_area, dem.as_ref())?;
+            let route = plan_route(
+                &ski_area,
+                &graph,
+                from,
+                to,
+                &difficulties,
+                &cost_model,
+            )
+            .ok_or_else(|| {
+                Error::new_s(ErrorType::InputError, "no route found")
+            })?;
+            output.write_to_file(&route)?;
+        }
+        Command::Reachable {
+            area,
+            dem,
+            lift,
+            max_difficulty,
+            output,
+        } => {
+            let dem = open_dem(&dem)?;
+            let ski_area: SkiArea = load_from_file(area)?;
+            let graph =
+                Graph::new(&CancellationToken::new(), &ski_area, dem.as_ref())?;
+            let result = reachable_from_lift(
+                &ski_area,
+                &graph,
+                &lift,
+                max_difficulty,
+                &CostModel::default(),
+            )
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorType::InputError,
+                    format!("lift not found: {}", lift),
+                )
+            })?;
+            output.write_to_file(&result)?;
+        }
+        Command::WayDown {
+            area,
+            dem,
+            position,
+            max_distance,
+            cost_model,
+            output,
+        } => {
+            let dem = open_dem(&dem)?;
+            let ski_area: SkiArea = load_from_file(area)?;
+            let cost_model = load_cost_model(cost_model)?;
+            let graph =
+                Graph::new(&CancellationToken::new(), &ski_area, dem.as_ref())?;
+            let route = way_to_valley(
+                &ski_area,
+                &graph,
+                position,
+                max_distance,
+                &cost_model,
+            )
+            .ok_or_else(|| {
+                Error::new_s(ErrorType::InputError, "no way down found")
+            })?;
+            output.write_to_file(&route)?;
+        }
     };
 
     Ok(())
@@ -1,11 +1,19 @@
 use clap::Parser;
 use ski_analyzer_lib::gpx_analyzer::{
-    Activity, ActivityType, AnalyzedRoute, DerivedData, MoveType, Moving,
-    UseLift, WaypointDef,
+    Activity, ActivityType, AnalysisMode, AnalyzedRoute, DayStatistics,
+    DerivedData, DifficultyStatistics, HeartRate, LiftRideStatistics,
+    MotionStatistics, MoveStatistics, MoveType, Moving, NoiseFilter,
+    RemovalReason, RemovedPoint, RouteStatistics, RunStatistics, Smoothing,
+    Terrain, TerrainStatistics, TrailStatistics, TripStatistics, UseLift,
+    WaypointDef,
+};
+use ski_analyzer_lib::graph::{
+    CostModel, EdgeKind, PlannedRoute, Reachable, ReachablePiste, RouteStep,
 };
 use ski_analyzer_lib::ski_area::{
-    Difficulty, Lift, Piste, PisteData, PisteMetadata, PointWithElevation,
-    SkiArea, SkiAreaMetadata,
+    Difficulty, Grooming, Lift, LiftAttributes, Piste, PisteAttributes,
+    PisteData, PisteMetadata, PisteStatus, PisteType, PointWithElevation,
+    SkiArea, SkiAreaMetadata, Trail,
 };
 use ski_analyzer_lib::typescript_gen::geo::{
     LineStringDef, MultiLineStringDef, MultiPolygonDef, PointDef, PolygonDef,
@@ -37,20 +45,49 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .register::<BoundedGeometry<PolygonDef>>()
         .register::<PointWithElevation>()
         .register::<SkiAreaMetadata>()
+        .register::<LiftAttributes>()
         .register::<Lift>()
         .register::<Difficulty>()
         .register::<PisteMetadata>()
+        .register::<PisteType>()
+        .register::<Grooming>()
+        .register::<PisteStatus>()
+        .register::<PisteAttributes>()
         .register::<PisteData>()
         .register::<Piste>()
+        .register::<Trail>()
         .register::<SkiArea>()
         .register::<WaypointDef>()
         .register::<UseLift>()
         .register::<MoveType>()
         .register::<Moving>()
+        .register::<Terrain>()
         .register::<ActivityType>()
         .register::<Activity>()
+        .register::<AnalysisMode>()
+        .register::<Smoothing>()
+        .register::<NoiseFilter>()
+        .register::<RemovalReason>()
+        .register::<RemovedPoint>()
+        .register::<HeartRate>()
         .register::<AnalyzedRoute>()
-        .register::<DerivedData>();
+        .register::<DerivedData>()
+        .register::<MotionStatistics>()
+        .register::<MoveStatistics>()
+        .register::<RunStatistics>()
+        .register::<DifficultyStatistics>()
+        .register::<TerrainStatistics>()
+        .register::<LiftRideStatistics>()
+        .register::<TrailStatistics>()
+        .register::<RouteStatistics>()
+        .register::<DayStatistics>()
+        .register::<TripStatistics>()
+        .register::<EdgeKind>()
+        .register::<CostModel>()
+        .register::<RouteStep>()
+        .register::<PlannedRoute>()
+        .register::<ReachablePiste>()
+        .register::<Reachable>();
 
     let output = Typescript::default()
         .bigint(BigIntExportBehavior::Number)
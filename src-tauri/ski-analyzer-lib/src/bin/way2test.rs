@@ -20,7 +20,7 @@ async fn run(id: u64) -> Result<()> {
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     let id: u64 = args[1].parse().unwrap();
-    set_config(Config { verbose: 0 }).unwrap();
+    set_config(Config::default()).unwrap();
 
     let runtime = Builder::new_current_thread().enable_all().build().unwrap();
     runtime.block_on(run(id)).unwrap();
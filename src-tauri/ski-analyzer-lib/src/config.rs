@@ -4,6 +4,12 @@ use clap::Args;
 
 use std::sync::OnceLock;
 
+pub const DEFAULT_OVERPASS_ENDPOINT: &str =
+    "https://overpass-api.de/api/interpreter";
+pub const DEFAULT_OVERPASS_TIMEOUT: u64 = 180;
+pub const DEFAULT_USER_AGENT: &str =
+    concat!("ski-analyzer/", env!("CARGO_PKG_VERSION"));
+
 #[derive(Clone, Args)]
 pub struct Config {
     /// Print more information.
@@ -11,6 +17,16 @@ pub struct Config {
     /// -vv: anomalies.
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
+    /// Overpass API endpoint. A file:// URL or a plain path reads a canned
+    /// Overpass JSON response from disk instead of querying the network.
+    #[arg(long, default_value = DEFAULT_OVERPASS_ENDPOINT)]
+    pub overpass_endpoint: String,
+    /// Timeout of Overpass queries in seconds.
+    #[arg(long, default_value_t = DEFAULT_OVERPASS_TIMEOUT)]
+    pub overpass_timeout: u64,
+    /// User agent to send to the Overpass API.
+    #[arg(long, default_value = DEFAULT_USER_AGENT)]
+    pub user_agent: String,
 }
 
 impl Config {
@@ -23,6 +39,17 @@ impl Config {
     }
 }
 
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            verbose: 0,
+            overpass_endpoint: DEFAULT_OVERPASS_ENDPOINT.to_string(),
+            overpass_timeout: DEFAULT_OVERPASS_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+}
+
 static CONFIG: OnceLock<Config> = OnceLock::new();
 
 pub fn get_config() -> &'static Config {
@@ -0,0 +1,286 @@
+use geo::{coord, Intersects, Point, Rect};
+use gpx::Gpx;
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::{Error, ErrorType, Result};
+
+mod geotiff;
+mod hgt;
+
+/// How the elevation of waypoints is changed using the elevation model.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+pub enum ElevationCorrection {
+    /// Only set the elevation of waypoints that have none.
+    #[default]
+    Fill,
+    /// Replace every elevation, e.g. for noisy GPS elevations of phones.
+    Replace,
+}
+
+/// The position of a raster. Coordinates are the centers of the samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GridInfo {
+    west: f64,
+    north: f64,
+    dx: f64,
+    dy: f64,
+    width: usize,
+    height: usize,
+}
+
+impl GridInfo {
+    /// The area covered by the raster, including half a sample around the
+    /// outermost sample centers.
+    fn rect(&self) -> Rect {
+        Rect::new(
+            coord! {
+                x: self.west - self.dx / 2.0,
+                y: self.north - (self.height as f64 - 0.5) * self.dy,
+            },
+            coord! {
+                x: self.west + (self.width as f64 - 0.5) * self.dx,
+                y: self.north + self.dy / 2.0,
+            },
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Grid {
+    info: GridInfo,
+    /// Row by row, starting from the north.
+    data: Vec<f32>,
+    no_data: Option<f32>,
+}
+
+impl Grid {
+    fn new(
+        info: GridInfo,
+        data: Vec<f32>,
+        no_data: Option<f32>,
+    ) -> Result<Self> {
+        if data.len() != info.width * info.height {
+            return Err(Error::new(
+                ErrorType::FormatError,
+                format!(
+                    "elevation data has {} samples instead of {}x{}",
+                    data.len(),
+                    info.width,
+                    info.height
+                ),
+            ));
+        }
+        Ok(Grid {
+            info,
+            data,
+            no_data,
+        })
+    }
+
+    fn get(&self, col: usize, row: usize) -> Option<f64> {
+        let value = self.data[row * self.info.width + col];
+        if value.is_nan() || Some(value) == self.no_data {
+            None
+        } else {
+            Some(value as f64)
+        }
+    }
+
+    /// Bilinear interpolation of the samples around the point. Samples
+    /// without data are left out.
+    fn elevation(&self, point: Point) -> Option<f64> {
+        let info = &self.info;
+        if !info.rect().intersects(&point) {
+            return None;
+        }
+        let col = ((point.x() - info.west) / info.dx)
+            .clamp(0.0, (info.width - 1) as f64);
+        let row = ((info.north - point.y()) / info.dy)
+            .clamp(0.0, (info.height - 1) as f64);
+        let col0 = (col.floor() as usize).min(info.width.saturating_sub(2));
+        let row0 = (row.floor() as usize).min(info.height.saturating_sub(2));
+        let fx = col - col0 as f64;
+        let fy = row - row0 as f64;
+        let col1 = (col0 + 1).min(info.width - 1);
+        let row1 = (row0 + 1).min(info.height - 1);
+
+        let mut sum = 0.0;
+        let mut weight_sum = 0.0;
+        for (c, r, weight) in [
+            (col0, row0, (1.0 - fx) * (1.0 - fy)),
+            (col1, row0, fx * (1.0 - fy)),
+            (col0, row1, (1.0 - fx) * fy),
+            (col1, row1, fx * fy),
+        ] {
+            if weight <= 0.0 {
+                continue;
+            }
+            if let Some(value) = self.get(c, r) {
+                sum += value * weight;
+                weight_sum += weight;
+            }
+        }
+        if weight_sum > 0.0 {
+            Some(sum / weight_sum)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TileFormat {
+    Hgt,
+    GeoTiff,
+}
+
+#[derive(Debug)]
+struct Tile {
+    path: PathBuf,
+    format: TileFormat,
+    rect: Rect,
+    /// Loaded when first used. None if loading failed.
+    grid: OnceLock<Option<Grid>>,
+}
+
+impl Tile {
+    fn open(path: PathBuf) -> Result<Option<Self>> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        let (format, info) = match extension.as_deref() {
+            Some("hgt") => (TileFormat::Hgt, hgt::read_info(&path)?),
+            Some("tif" | "tiff") => {
+                (TileFormat::GeoTiff, geotiff::read_info(&path)?)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Tile {
+            path,
+            format,
+            rect: info.rect(),
+            grid: OnceLock::new(),
+        }))
+    }
+
+    fn load(&self) -> Result<Grid> {
+        match self.format {
+            TileFormat::Hgt => hgt::read(&self.path),
+            TileFormat::GeoTiff => geotiff::read(&self.path),
+        }
+    }
+
+    fn grid(&self) -> Option<&Grid> {
+        self.grid
+            .get_or_init(|| match self.load() {
+                Ok(grid) => Some(grid),
+                Err(err) => {
+                    eprintln!(
+                        "Failed to load elevation tile {}: {}",
+                        self.path.display(),
+                        err
+                    );
+                    None
+                }
+            })
+            .as_ref()
+    }
+}
+
+/// A digital elevation model made of SRTM (.hgt) or GeoTIFF tiles. GeoTIFF
+/// tiles must use geographic (longitude, latitude) coordinates. Tiles are
+/// only read when an elevation inside them is requested.
+#[derive(Debug)]
+pub struct Dem {
+    tiles: Vec<Tile>,
+}
+
+impl Dem {
+    /// Opens a single tile or every tile in a directory. Tiles that can't be
+    /// opened are skipped with a warning. Fails if no tile can be opened.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut paths = if path.is_dir() {
+            fs::read_dir(path)?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            vec![path.to_path_buf()]
+        };
+        // Make the lookup order of overlapping tiles deterministic.
+        paths.sort();
+
+        let mut tiles = Vec::new();
+        let mut last_error = None;
+        for path in paths {
+            match Tile::open(path.clone()) {
+                Ok(Some(tile)) => tiles.push(tile),
+                Ok(None) => (),
+                Err(err) => {
+                    eprintln!(
+                        "Skipping elevation tile {}: {}",
+                        path.display(),
+                        err
+                    );
+                    last_error = Some(err);
+                }
+            }
+        }
+        if tiles.is_empty() {
+            return Err(last_error.unwrap_or_else(|| {
+                Error::new(
+                    ErrorType::InputError,
+                    format!("no elevation tiles found in {}", path.display()),
+                )
+            }));
+        }
+        Ok(Dem { tiles })
+    }
+
+    /// Returns the elevation at the point in meters, or None if no tile
+    /// covers it.
+    pub fn elevation(&self, point: Point) -> Option<f64> {
+        self.tiles
+            .iter()
+            .filter(|t| t.rect.intersects(&point))
+            .find_map(|t| t.grid()?.elevation(point))
+    }
+
+    /// Changes the elevation of every track point in the GPX.
+    pub fn correct_elevations(
+        &self,
+        gpx: &mut Gpx,
+        correction: ElevationCorrection,
+    ) {
+        let points = gpx
+            .tracks
+            .iter_mut()
+            .flat_map(|t| t.segments.iter_mut())
+            .flat_map(|s| s.points.iter_mut());
+        for wp in points {
+            if correction == ElevationCorrection::Fill && wp.elevation.is_some()
+            {
+                continue;
+            }
+            if let Some(elevation) = self.elevation(wp.point()) {
+                wp.elevation = Some(elevation);
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use tiff::decoder::{Decoder, DecodingResult, Limits};
+use tiff::tags::Tag;
+use tiff::ColorType;
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use super::{Grid, GridInfo};
+use crate::error::{Error, ErrorType, Result};
+
+const MODEL_TYPE_KEY: u16 = 1024;
+const RASTER_TYPE_KEY: u16 = 1025;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+fn format_error(path: &Path, message: &str) -> Error {
+    Error::new(
+        ErrorType::FormatError,
+        format!("{}: {}", path.display(), message),
+    )
+}
+
+/// Returns the value of a GeoKey that is stored directly in the directory.
+fn get_geo_key(directory: &[u16], key: u16) -> Option<u16> {
+    // The header has the same size as an entry: version, revision, minor
+    // revision and the number of keys.
+    directory
+        .chunks_exact(4)
+        .skip(1)
+        .find(|entry| entry[0] == key && entry[1] == 0)
+        .map(|entry| entry[3])
+}
+
+fn get_info<R: Read + Seek>(
+    path: &Path,
+    decoder: &mut Decoder<R>,
+) -> Result<GridInfo> {
+    match decoder.colortype()? {
+        ColorType::Gray(_) => (),
+        _ => return Err(format_error(path, "more than one sample per pixel")),
+    }
+    let (width, height) = decoder.dimensions()?;
+
+    let directory = decoder
+        .find_tag(Tag::GeoKeyDirectoryTag)?
+        .map(|v| v.into_u16_vec())
+        .transpose()?
+        .unwrap_or_default();
+    if let Some(model_type) = get_geo_key(&directory, MODEL_TYPE_KEY) {
+        if model_type != MODEL_TYPE_GEOGRAPHIC {
+            return Err(format_error(
+                path,
+                "only geographic coordinates are supported",
+            ));
+        }
+    }
+
+    let scale = decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag)?;
+    let tiepoint = decoder.get_tag_f64_vec(Tag::ModelTiepointTag)?;
+    if scale.len() < 2 || tiepoint.len() < 6 {
+        return Err(format_error(path, "invalid georeference"));
+    }
+    let (dx, dy) = (scale[0], scale[1]);
+    let (i, j, x, y) = (tiepoint[0], tiepoint[1], tiepoint[3], tiepoint[4]);
+
+    // By default the tie point refers to the corner of a pixel. The sample
+    // is at the center of the pixel.
+    let offset = match get_geo_key(&directory, RASTER_TYPE_KEY) {
+        Some(RASTER_PIXEL_IS_POINT) => 0.0,
+        _ => 0.5,
+    };
+    Ok(GridInfo {
+        west: x + (offset - i) * dx,
+        north: y - (offset - j) * dy,
+        dx,
+        dy,
+        width: width as usize,
+        height: height as usize,
+    })
+}
+
+fn open(path: &Path) -> Result<Decoder<BufReader<File>>> {
+    Ok(Decoder::new(BufReader::new(File::open(path)?))?)
+}
+
+pub fn read_info(path: &Path) -> Result<GridInfo> {
+    get_info(path, &mut open(path)?)
+}
+
+pub fn read(path: &Path) -> Result<Grid> {
+    let mut decoder = open(path)?.with_limits(Limits::unlimited());
+    let info = get_info(path, &mut decoder)?;
+    let no_data = decoder
+        .find_tag(Tag::GdalNodata)?
+        .map(|v| v.into_string())
+        .transpose()?
+        .and_then(|s| s.trim_matches(char::from(0)).trim().parse().ok());
+    let data = match decoder.read_image()? {
+        DecodingResult::U8(v) => v.into_iter().map(|x| x as f32).collect(),
+        DecodingResult::U16(v) => v.into_iter().map(|x| x as f32).collect(),
+        DecodingResult::U32(v) => v.into_iter().map(|x| x as f32).collect(),
+        DecodingResult::U64(v) => v.into_iter().map(|x| x as f32).collect(),
+        DecodingResult::I8(v) => v.into_iter().map(|x| x as f32).collect(),
+        DecodingResult::I16(v) => v.into_iter().map(|x| x as f32).collect(),
+        DecodingResult::I32(v) => v.into_iter().map(|x| x as f32).collect(),
+        DecodingResult::I64(v) => v.into_iter().map(|x| x as f32).collect(),
+        DecodingResult::F32(v) => v,
+        DecodingResult::F64(v) => v.into_iter().map(|x| x as f32).collect(),
+    };
+    Grid::new(info, data, no_data)
+}
@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::Path;
+
+use super::{Grid, GridInfo};
+use crate::error::{Error, ErrorType, Result};
+
+const NO_DATA: i16 = -32768;
+
+fn format_error(path: &Path, message: &str) -> Error {
+    Error::new(
+        ErrorType::FormatError,
+        format!("{}: {}", path.display(), message),
+    )
+}
+
+/// Parses the south west corner of the tile from a file name such as
+/// N45E006.hgt.
+fn parse_name(name: &str) -> Option<(f64, f64)> {
+    let name = name.get(..7)?.to_uppercase();
+    let lat: f64 = name.get(1..3)?.parse().ok()?;
+    let lon: f64 = name.get(4..7)?.parse().ok()?;
+    let south = match &name[0..1] {
+        "N" => lat,
+        "S" => -lat,
+        _ => return None,
+    };
+    let west = match &name[3..4] {
+        "E" => lon,
+        "W" => -lon,
+        _ => return None,
+    };
+    Some((west, south))
+}
+
+/// SRTM tiles cover one degree and contain a square of big endian 16 bit
+/// samples, 1201 (3 arc seconds) or 3601 (1 arc second) on each side. The
+/// outermost samples are on the edges of the tile.
+fn get_info(path: &Path, len: u64) -> Result<GridInfo> {
+    let (west, south) = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(parse_name)
+        .ok_or_else(|| format_error(path, "invalid SRTM tile name"))?;
+    let size = ((len / 2) as f64).sqrt() as usize;
+    if size < 2 || (size * size * 2) as u64 != len {
+        return Err(format_error(path, "invalid SRTM tile size"));
+    }
+    let step = 1.0 / (size - 1) as f64;
+    Ok(GridInfo {
+        west,
+        north: south + 1.0,
+        dx: step,
+        dy: step,
+        width: size,
+        height: size,
+    })
+}
+
+pub fn read_info(path: &Path) -> Result<GridInfo> {
+    get_info(path, fs::metadata(path)?.len())
+}
+
+pub fn read(path: &Path) -> Result<Grid> {
+    let data = fs::read(path)?;
+    let info = get_info(path, data.len() as u64)?;
+    let samples = data
+        .chunks_exact(2)
+        .map(|b| i16::from_be_bytes([b[0], b[1]]) as f32)
+        .collect();
+    Grid::new(info, samples, Some(NO_DATA as f32))
+}
@@ -0,0 +1,160 @@
+use crate::dem::{Dem, ElevationCorrection};
+use crate::error::ErrorType;
+use crate::utils::test_util::{init, make_gpx, save_hgt, segment, Init};
+
+use geo::{point, Point};
+use rstest::rstest;
+use tiff::encoder::{colortype, TiffEncoder};
+use tiff::tags::Tag;
+
+use std::fs::{self, File};
+
+const VOID: i16 = -32768;
+
+fn test_dir(name: &str) -> String {
+    let dir = format!("test_output/dem_test/{}", name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A tile with 0.5 degree resolution.
+fn save_tile(dir: &str) {
+    save_hgt(
+        &[
+            &[1000, 1100, 1200],
+            &[1300, 1400, VOID],
+            &[1600, 1700, 1800],
+        ],
+        &format!("{}/N45E006.hgt", dir),
+    );
+}
+
+/// A 2x2 tile with 0.125 degree pixels, the north west corner at (10, 47).
+fn save_geotiff(path: &str, model_type: u16, data: &[f32]) {
+    let mut encoder = TiffEncoder::new(File::create(path).unwrap()).unwrap();
+    let mut image = encoder.new_image::<colortype::Gray32Float>(2, 2).unwrap();
+    let tags = image.encoder();
+    tags.write_tag(Tag::ModelPixelScaleTag, &[0.125f64, 0.125, 0.0][..])
+        .unwrap();
+    tags.write_tag(
+        Tag::ModelTiepointTag,
+        &[0.0f64, 0.0, 0.0, 10.0, 47.0, 0.0][..],
+    )
+    .unwrap();
+    tags.write_tag(
+        Tag::GeoKeyDirectoryTag,
+        &[1u16, 1, 0, 1, 1024, 0, 1, model_type][..],
+    )
+    .unwrap();
+    tags.write_tag(Tag::GdalNodata, "-9999").unwrap();
+    image.write_data(data).unwrap();
+}
+
+fn assert_elevation(dem: &Dem, point: Point, expected: Option<f64>) {
+    let actual = dem.elevation(point);
+    match (actual, expected) {
+        (Some(a), Some(e)) => assert!(
+            (a - e).abs() < 1e-6,
+            "{:?}: actual={} expected={}",
+            point,
+            a,
+            e
+        ),
+        _ => assert_eq!(actual, expected, "{:?}", point),
+    }
+}
+
+#[rstest]
+#[case::sample(point! { x: 6.5, y: 45.5 }, Some(1400.0))]
+#[case::corner(point! { x: 6.0, y: 46.0 }, Some(1000.0))]
+#[case::between_two(point! { x: 6.5, y: 45.25 }, Some(1550.0))]
+#[case::between_four(point! { x: 6.25, y: 45.75 }, Some(1200.0))]
+#[case::next_to_void(point! { x: 6.75, y: 45.75 }, Some(3700.0 / 3.0))]
+#[case::void(point! { x: 7.0, y: 45.5 }, None)]
+#[case::outside(point! { x: 7.5, y: 45.5 }, None)]
+fn hgt(_init: Init, #[case] point: Point, #[case] expected: Option<f64>) {
+    let dir = test_dir(&format!("hgt_{}_{}", point.x(), point.y()));
+    save_tile(&dir);
+    let dem = Dem::open(&dir).unwrap();
+    assert_elevation(&dem, point, expected);
+}
+
+#[rstest]
+fn hgt_south_west(_init: Init) {
+    let dir = test_dir("hgt_south_west");
+    let path = format!("{}/s01w002.hgt", dir);
+    save_hgt(&[&[10, 20], &[30, 40]], &path);
+    let dem = Dem::open(&path).unwrap();
+    assert_elevation(&dem, point! { x: -2.0, y: 0.0 }, Some(10.0));
+    assert_elevation(&dem, point! { x: -1.0, y: -1.0 }, Some(40.0));
+}
+
+#[rstest]
+fn geotiff(_init: Init) {
+    let dir = test_dir("geotiff");
+    save_geotiff(
+        &format!("{}/dem.tif", dir),
+        2,
+        &[100.0, 200.0, 300.0, -9999.0],
+    );
+    // Files that are not elevation models are ignored.
+    fs::write(format!("{}/README", dir), "hello").unwrap();
+    let dem = Dem::open(&dir).unwrap();
+    assert_elevation(&dem, point! { x: 10.0625, y: 46.9375 }, Some(100.0));
+    assert_elevation(&dem, point! { x: 10.125, y: 46.9375 }, Some(150.0));
+    assert_elevation(&dem, point! { x: 10.1875, y: 46.8125 }, None);
+    assert_elevation(&dem, point! { x: 10.3, y: 46.9 }, None);
+}
+
+#[rstest]
+fn geotiff_projected(_init: Init) {
+    let dir = test_dir("geotiff_projected");
+    let path = format!("{}/dem.tif", dir);
+    save_geotiff(&path, 1, &[0.0; 4]);
+    let err = Dem::open(&path).unwrap_err();
+    assert_eq!(err.get_type(), ErrorType::FormatError);
+}
+
+#[rstest]
+fn bad_tile_skipped(_init: Init) {
+    let dir = test_dir("bad_tile_skipped");
+    save_tile(&dir);
+    save_geotiff(&format!("{}/projected.tif", dir), 1, &[0.0; 4]);
+    fs::write(format!("{}/N46E006.hgt", dir), b"not a tile").unwrap();
+    let dem = Dem::open(&dir).unwrap();
+    assert_elevation(&dem, point! { x: 6.5, y: 45.5 }, Some(1400.0));
+}
+
+#[rstest]
+fn no_tiles(_init: Init) {
+    let dir = test_dir("no_tiles");
+    let err = Dem::open(&dir).unwrap_err();
+    assert_eq!(err.get_type(), ErrorType::InputError);
+}
+
+#[rstest]
+#[case::fill(ElevationCorrection::Fill, [Some(500.0), Some(1400.0), None])]
+#[case::replace(
+    ElevationCorrection::Replace,
+    [Some(1000.0), Some(1400.0), None]
+)]
+fn correct_elevations(
+    _init: Init,
+    #[case] correction: ElevationCorrection,
+    #[case] expected: [Option<f64>; 3],
+) {
+    let dir = test_dir(&format!("correct_{:?}", correction));
+    save_tile(&dir);
+    let dem = Dem::open(&dir).unwrap();
+    let mut gpx =
+        make_gpx(vec![segment(&[(6.0, 46.0), (6.5, 45.5), (8.0, 45.5)])]);
+    gpx.tracks[0].segments[0].points[0].elevation = Some(500.0);
+    dem.correct_elevations(&mut gpx, correction);
+    let actual: Vec<_> = gpx.tracks[0].segments[0]
+        .points
+        .iter()
+        .map(|wp| wp.elevation)
+        .collect();
+    assert_eq!(actual, expected);
+}
@@ -79,6 +79,30 @@ impl From<gpx::errors::GpxError> for Error {
     }
 }
 
+impl From<quick_xml::Error> for Error {
+    fn from(value: quick_xml::Error) -> Self {
+        Error::new(ErrorType::FormatError, value.to_string())
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(value: csv::Error) -> Self {
+        Error::new(ErrorType::FormatError, value.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(value: zip::result::ZipError) -> Self {
+        Error::new(ErrorType::FormatError, value.to_string())
+    }
+}
+
+impl From<tiff::TiffError> for Error {
+    fn from(value: tiff::TiffError) -> Self {
+        Error::new(ErrorType::FormatError, value.to_string())
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(value: reqwest::Error) -> Self {
         Error::new(ErrorType::NetworkError, value.to_string())
@@ -0,0 +1,228 @@
+use geo::{Geometry, MultiLineString};
+use geojson::feature::Id;
+use geojson::{Feature, FeatureCollection, JsonObject, JsonValue};
+use serde_json::json;
+use time::format_description::well_known::Iso8601;
+use time::OffsetDateTime;
+
+use crate::gpx_analyzer::{Activity, ActivityType, AnalyzedRoute};
+use crate::ski_area::{Lift, Piste, SkiArea, Trail};
+
+use std::collections::HashMap;
+
+fn to_object(value: JsonValue) -> JsonObject {
+    match value {
+        JsonValue::Object(object) => object,
+        _ => unreachable!(),
+    }
+}
+
+fn create_feature(
+    geometry: &Geometry,
+    id: Option<String>,
+    properties: JsonValue,
+) -> Feature {
+    Feature {
+        bbox: None,
+        geometry: Some(geojson::Geometry::from(geometry)),
+        id: id.map(Id::String),
+        properties: Some(to_object(properties)),
+        foreign_members: None,
+    }
+}
+
+fn format_time(time: Option<OffsetDateTime>) -> JsonValue {
+    time.and_then(|t| t.format(&Iso8601::DEFAULT).ok())
+        .map_or(JsonValue::Null, JsonValue::String)
+}
+
+fn lift_to_feature(id: &str, lift: &Lift) -> Feature {
+    let stations: Vec<_> = lift
+        .stations
+        .iter()
+        .map(|s| match s.elevation {
+            Some(e) => json!([s.point.x(), s.point.y(), e]),
+            None => json!([s.point.x(), s.point.y()]),
+        })
+        .collect();
+    create_feature(
+        &Geometry::LineString(lift.line.item.clone()),
+        Some(id.to_string()),
+        json!({
+            "kind": "lift",
+            "ref": lift.ref_,
+            "name": lift.name,
+            "type": lift.type_,
+            "stations": stations,
+            "can_go_reverse": lift.can_go_reverse,
+            "can_disembark": lift.can_disembark,
+            "underground": lift.underground,
+        }),
+    )
+}
+
+fn piste_to_feature(id: &str, piste: &Piste) -> Feature {
+    let lines = &piste.data.lines;
+    let areas = &piste.data.areas;
+    let geometry = if areas.0.is_empty() {
+        Geometry::MultiLineString(lines.clone())
+    } else if lines.0.is_empty() {
+        Geometry::MultiPolygon(areas.clone())
+    } else {
+        Geometry::GeometryCollection(geo::GeometryCollection::new_from(vec![
+            Geometry::MultiLineString(lines.clone()),
+            Geometry::MultiPolygon(areas.clone()),
+        ]))
+    };
+    create_feature(
+        &geometry,
+        Some(id.to_string()),
+        json!({
+            "kind": "piste",
+            "ref": piste.metadata.ref_,
+            "name": piste.metadata.name,
+            "difficulty": piste.metadata.difficulty,
+        }),
+    )
+}
+
+fn trail_to_feature(kind: &str, id: &str, trail: &Trail) -> Feature {
+    create_feature(
+        &Geometry::MultiLineString(trail.line.item.clone()),
+        Some(id.to_string()),
+        json!({
+            "kind": kind,
+            "ref": trail.metadata.ref_,
+            "name": trail.metadata.name,
+            "difficulty": trail.metadata.difficulty,
+            "length": trail.length,
+            "is_loop": trail.is_loop,
+        }),
+    )
+}
+
+fn get_activity_properties(index: usize, activity: &Activity) -> JsonValue {
+    let mut properties = json!({
+        "kind": "activity",
+        "index": index,
+        "begin_time": format_time(activity.begin_time),
+        "end_time": format_time(activity.end_time),
+        "length": activity.length,
+    });
+    let extra = match &activity.type_ {
+        ActivityType::Unknown(()) => json!({ "type": "Unknown" }),
+        ActivityType::UseLift(use_lift) => json!({
+            "type": "UseLift",
+            "lift_id": use_lift.lift_id,
+            "begin_station": use_lift.begin_station,
+            "end_station": use_lift.end_station,
+            "is_reverse": use_lift.is_reverse,
+        }),
+        ActivityType::EnterLift(lift_id) => {
+            json!({ "type": "EnterLift", "lift_id": lift_id })
+        }
+        ActivityType::ExitLift(lift_id) => {
+            json!({ "type": "ExitLift", "lift_id": lift_id })
+        }
+        ActivityType::Moving(moving) => json!({
+            "type": "Moving",
+            "move_type": moving.move_type,
+            "piste_id": moving.piste_id,
+            "terrain": moving.terrain,
+        }),
+        ActivityType::Trail(trail_id) => {
+            json!({ "type": "Trail", "trail_id": trail_id })
+        }
+    };
+    properties.as_object_mut().unwrap().extend(to_object(extra));
+    properties
+}
+
+fn activity_to_feature(index: usize, activity: &Activity) -> Feature {
+    let line = MultiLineString::new(
+        activity
+            .route
+            .0
+            .iter()
+            .map(|s| s.iter().map(|wp| wp.point()).collect())
+            .collect(),
+    );
+    create_feature(
+        &Geometry::MultiLineString(line),
+        None,
+        get_activity_properties(index, activity),
+    )
+}
+
+fn sorted<T>(items: &HashMap<String, T>) -> Vec<(&String, &T)> {
+    let mut result: Vec<_> = items.iter().collect();
+    result.sort_by_key(|(id, _)| *id);
+    result
+}
+
+/// Converts the ski area to a feature collection containing the outline, the
+/// lifts, the pistes, the nordic trails, the sled runs and the ski touring
+/// routes. Features are distinguished by the `kind` property.
+pub fn ski_area_to_geojson(ski_area: &SkiArea) -> FeatureCollection {
+    let outline = create_feature(
+        &Geometry::Polygon(ski_area.metadata.outline.item.clone()),
+        Some(ski_area.metadata.id.to_string()),
+        json!({
+            "kind": "ski_area",
+            "name": ski_area.metadata.name,
+        }),
+    );
+    let features =
+        [outline]
+            .into_iter()
+            .chain(
+                sorted(ski_area.lifts())
+                    .into_iter()
+                    .map(|(id, lift)| lift_to_feature(id, lift)),
+            )
+            .chain(
+                sorted(ski_area.pistes())
+                    .into_iter()
+                    .map(|(id, piste)| piste_to_feature(id, piste)),
+            )
+            .chain(
+                sorted(ski_area.nordic_trails()).into_iter().map(
+                    |(id, trail)| trail_to_feature("nordic_trail", id, trail),
+                ),
+            )
+            .chain(
+                sorted(ski_area.sled_runs())
+                    .into_iter()
+                    .map(|(id, trail)| trail_to_feature("sled_run", id, trail)),
+            )
+            .chain(sorted(ski_area.skitour_routes()).into_iter().map(
+                |(id, trail)| trail_to_feature("skitour_route", id, trail),
+            ))
+            .chain(
+                sorted(ski_area.bike_trails()).into_iter().map(
+                    |(id, trail)| trail_to_feature("bike_trail", id, trail),
+                ),
+            )
+            .collect();
+
+    FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    }
+}
+
+/// Converts the route to a feature collection with one feature per activity.
+pub fn route_to_geojson(route: &AnalyzedRoute) -> FeatureCollection {
+    FeatureCollection {
+        bbox: None,
+        features: route
+            .route
+            .item
+            .iter()
+            .enumerate()
+            .map(|(i, activity)| activity_to_feature(i, activity))
+            .collect(),
+        foreign_members: None,
+    }
+}
@@ -0,0 +1,221 @@
+use crate::geojson_export::{route_to_geojson, ski_area_to_geojson};
+use crate::gpx_analyzer::{
+    Activity, ActivityType, AnalyzedRoute, MoveType, Moving, Segments, Terrain,
+};
+use crate::ski_area::{
+    Difficulty, Lift, LiftAttributes, PointWithElevation, SkiArea,
+};
+use crate::utils::bounded_geometry::BoundedGeometry;
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, line, piste, polygon, Init,
+};
+
+use geo::{coord, point, Rect};
+use geojson::feature::Id;
+use geojson::{GeoJson, Value};
+use gpx::Waypoint;
+use rstest::{fixture, rstest};
+use serde_json::json;
+use time::OffsetDateTime;
+
+use std::collections::HashMap;
+
+#[fixture]
+fn ski_area() -> SkiArea {
+    let line_ = line(&[(6.0, 45.0), (6.0, 45.01)]);
+    let lift = Lift {
+        ref_: "A".to_string(),
+        name: "Lift A".to_string(),
+        type_: "chair_lift".to_string(),
+        stations: vec![
+            PointWithElevation::new(point! { x: 6.0, y: 45.0 }, Some(1000.0)),
+            PointWithElevation::new(point! { x: 6.0, y: 45.01 }, Some(1800.0)),
+        ],
+        line: BoundedGeometry::new(line_).unwrap(),
+        lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
+        can_go_reverse: false,
+        can_disembark: true,
+    };
+    let mut piste1 =
+        piste("Red", vec![line(&[(6.0, 45.01), (6.0, 45.0)])], vec![]);
+    piste1.metadata.ref_ = "3".to_string();
+    piste1.metadata.difficulty = Difficulty::Advanced;
+    let piste2 = piste(
+        "Mixed",
+        vec![line(&[(6.1, 45.01), (6.1, 45.0)])],
+        vec![polygon(&[
+            (6.2, 45.0),
+            (6.21, 45.0),
+            (6.21, 45.01),
+            (6.2, 45.01),
+            (6.2, 45.0),
+        ])],
+    );
+    let piste3 = piste(
+        "Area",
+        vec![],
+        vec![polygon(&[
+            (6.3, 45.0),
+            (6.31, 45.0),
+            (6.31, 45.01),
+            (6.3, 45.0),
+        ])],
+    );
+    SkiArea::new(
+        create_ski_area_metadata("Ski area".to_string()),
+        HashMap::from([("l1".to_string(), lift)]),
+        HashMap::from([
+            ("p1".to_string(), piste1),
+            ("p2".to_string(), piste2),
+            ("p3".to_string(), piste3),
+        ]),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+fn get_kind(value: &Option<Value>) -> &'static str {
+    match value {
+        Some(Value::Polygon(_)) => "Polygon",
+        Some(Value::LineString(_)) => "LineString",
+        Some(Value::MultiLineString(_)) => "MultiLineString",
+        Some(Value::MultiPolygon(_)) => "MultiPolygon",
+        Some(Value::GeometryCollection(_)) => "GeometryCollection",
+        _ => "other",
+    }
+}
+
+#[rstest]
+fn ski_area_features(_init: Init, ski_area: SkiArea) {
+    let collection = ski_area_to_geojson(&ski_area);
+
+    let summary: Vec<_> = collection
+        .features
+        .iter()
+        .map(|f| {
+            (
+                f.id.clone(),
+                get_kind(&f.geometry.as_ref().map(|g| g.value.clone())),
+                f.property("kind").unwrap().clone(),
+                f.property("name").unwrap().clone(),
+            )
+        })
+        .collect();
+    let id = |s: &str| Some(Id::String(s.to_string()));
+    assert_eq!(
+        summary,
+        vec![
+            (
+                id(&ski_area.metadata.id.to_string()),
+                "Polygon",
+                json!("ski_area"),
+                json!("Ski area")
+            ),
+            (id("l1"), "LineString", json!("lift"), json!("Lift A")),
+            (id("p1"), "MultiLineString", json!("piste"), json!("Red")),
+            (
+                id("p2"),
+                "GeometryCollection",
+                json!("piste"),
+                json!("Mixed")
+            ),
+            (id("p3"), "MultiPolygon", json!("piste"), json!("Area")),
+        ]
+    );
+
+    let lift = &collection.features[1];
+    assert_eq!(lift.property("ref"), Some(&json!("A")));
+    assert_eq!(lift.property("type"), Some(&json!("chair_lift")));
+    assert_eq!(
+        lift.property("stations"),
+        Some(&json!([[6.0, 45.0, 1000.0], [6.0, 45.01, 1800.0]]))
+    );
+    assert_eq!(lift.property("can_disembark"), Some(&json!(true)));
+
+    let piste = &collection.features[2];
+    assert_eq!(piste.property("ref"), Some(&json!("3")));
+    assert_eq!(piste.property("difficulty"), Some(&json!("Advanced")));
+
+    let text = GeoJson::from(collection).to_string();
+    assert!(text.parse::<GeoJson>().is_ok());
+}
+
+#[rstest]
+fn route_features(_init: Init) {
+    let waypoints: Vec<_> = [(6.0, 45.0), (6.0, 45.001), (6.0, 45.002)]
+        .iter()
+        .map(|(x, y)| Waypoint::new(point! { x: *x, y: *y }))
+        .collect();
+    let activity1 = Activity {
+        type_: ActivityType::EnterLift("l1".to_string()),
+        route: Segments::new(vec![waypoints[0..2].to_vec()]),
+        ..Default::default()
+    };
+    let activity2 = Activity {
+        type_: ActivityType::Moving(Moving {
+            move_type: MoveType::Ski,
+            piste_id: "p1".to_string(),
+            terrain: Terrain::Piste,
+        }),
+        route: Segments::new(vec![
+            waypoints[0..2].to_vec(),
+            waypoints[1..3].to_vec(),
+        ]),
+        begin_time: Some(OffsetDateTime::UNIX_EPOCH),
+        end_time: None,
+        length: 100.0,
+    };
+    let route = AnalyzedRoute {
+        route: BoundedGeometry {
+            item: vec![activity1, activity2],
+            bounding_rect: Rect::new(
+                coord! { x: 6.0, y: 45.0 },
+                coord! { x: 6.0, y: 45.002 },
+            ),
+        },
+        removed_points: Vec::new(),
+        heart_rate: Vec::new(),
+    };
+
+    let collection = route_to_geojson(&route);
+    assert_eq!(collection.features.len(), 2);
+
+    let feature1 = &collection.features[0];
+    assert_eq!(
+        feature1.properties.as_ref().unwrap(),
+        json!({
+            "kind": "activity",
+            "index": 0,
+            "type": "EnterLift",
+            "lift_id": "l1",
+            "begin_time": null,
+            "end_time": null,
+            "length": 0.0,
+        })
+        .as_object()
+        .unwrap()
+    );
+
+    let feature2 = &collection.features[1];
+    assert_eq!(feature2.property("type"), Some(&json!("Moving")));
+    assert_eq!(feature2.property("move_type"), Some(&json!("Ski")));
+    assert_eq!(feature2.property("piste_id"), Some(&json!("p1")));
+    assert_eq!(
+        feature2.property("begin_time"),
+        Some(&json!("1970-01-01T00:00:00.000000000Z"))
+    );
+    match &feature2.geometry.as_ref().unwrap().value {
+        Value::MultiLineString(lines) => {
+            assert_eq!(
+                *lines,
+                vec![
+                    vec![vec![6.0, 45.0], vec![6.0, 45.001]],
+                    vec![vec![6.0, 45.001], vec![6.0, 45.002]],
+                ]
+            )
+        }
+        other => panic!("unexpected geometry: {:?}", other),
+    }
+}
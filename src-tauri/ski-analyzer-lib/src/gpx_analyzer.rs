@@ -1,37 +1,66 @@
 use geo::{Distance, Haversine, Length, Line, MultiLineString};
 use gpx::{Gpx, Time, Waypoint};
-use gpx_parser::parse_gpx;
-use moving::find_moves;
+use gpx_parser::{parse_gpx, parse_gpxs};
+use incremental::find_moves_in;
+use nordic::find_trails;
 use serde::{Deserialize, Serialize};
 use std::mem::take;
 use time::format_description::well_known::Iso8601;
 use time::{Duration, OffsetDateTime};
+use use_lift::find_lift_usage;
 
 use crate::error::Result;
 use crate::ski_area::SkiArea;
 use crate::utils::bounded_geometry::BoundedGeometry;
 use crate::utils::cancel::CancellationToken;
 use crate::utils::option_time_ser;
-use use_lift::find_lift_usage;
+use crate::utils::time_ser;
+use crate::utils::track::RecordedTrack;
 
+mod gpx_export;
 mod gpx_parser;
+mod incremental;
 mod moving;
+mod noise_filter;
+mod nordic;
 mod segments;
+mod statistics;
 mod use_lift;
 mod waypoint_ser;
 
+#[cfg(test)]
+mod gpx_export_test;
 #[cfg(test)]
 mod gpx_parser_test;
 #[cfg(test)]
+mod incremental_test;
+#[cfg(test)]
+mod noise_filter_test;
+#[cfg(test)]
+mod nordic_test;
+#[cfg(test)]
 mod segments_test;
 #[cfg(test)]
+mod statistics_test;
+#[cfg(test)]
 mod test_util;
 #[cfg(test)]
 mod use_lift_test;
 
+pub use gpx_export::export_gpx;
+pub use incremental::IncrementalAnalyzer;
 pub use moving::move_type::MoveType;
-pub use moving::Moving;
+pub use moving::{
+    Constraint, ConstraintLimit, ConstraintType, MoveProfile, Moving, Terrain,
+    DEFAULT_PROFILE,
+};
+pub use noise_filter::{NoiseFilter, RemovalReason, RemovedPoint, Smoothing};
 pub use segments::{Segment, SegmentCoordinate, Segments};
+pub use statistics::{
+    DayStatistics, DifficultyStatistics, LiftRideStatistics, MotionStatistics,
+    MoveStatistics, RouteStatistics, RunStatistics, TerrainStatistics,
+    TrailStatistics, TripStatistics,
+};
 pub use use_lift::{LiftEnd, UseLift};
 pub use waypoint_ser::WaypointDef;
 
@@ -44,6 +73,8 @@ pub enum ActivityType {
     EnterLift(String),
     ExitLift(String),
     Moving(Moving),
+    /// Moving along a nordic trail. The ID is empty when not on any trail.
+    Trail(String),
 }
 
 impl Default for ActivityType {
@@ -116,45 +147,155 @@ fn format_time_option(time: Option<OffsetDateTime>) -> String {
     }
 }
 
+/// The kind of activity that the route is analyzed for.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+pub enum AnalysisMode {
+    /// Lift rides and moves on downhill pistes.
+    #[default]
+    Alpine,
+    /// Cross-country skiing on nordic trails.
+    Nordic,
+    /// Mountain biking on bike trails, using the lifts that run in summer.
+    Summer,
+}
+
+/// A heart rate measurement of the recorded track.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct HeartRate {
+    #[serde(with = "time_ser")]
+    pub time: OffsetDateTime,
+    /// In beats per minute.
+    pub value: u8,
+}
+
 #[cfg_attr(feature = "specta", derive(specta::Type))]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AnalyzedRoute {
     pub route: BoundedGeometry<Vec<Activity>>,
+    /// Waypoints of the input that are not part of the route.
+    #[serde(default)]
+    pub removed_points: Vec<RemovedPoint>,
+    /// Heart rate measurements of the input, ordered by time.
+    #[serde(default)]
+    pub heart_rate: Vec<HeartRate>,
 }
 
 pub fn analyze_route(
     cancel: &CancellationToken,
     ski_area: &SkiArea,
-    gpx: Gpx,
+    mode: AnalysisMode,
+    profile: &MoveProfile,
+    filter: &NoiseFilter,
+    track: RecordedTrack,
 ) -> Result<AnalyzedRoute> {
-    let mut segments = parse_gpx(gpx)?;
-    let result0 = find_lift_usage(cancel, ski_area, take(&mut segments.item))?;
-
-    let mut result = Vec::new();
-    result.reserve(result0.len());
-    for activity in result0 {
-        let is_unknown = match activity.type_ {
-            ActivityType::Unknown(_) => true,
-            _ => false,
-        };
-        if !is_unknown {
-            result.push(activity);
-            continue;
-        }
+    let mut removed_points = Vec::new();
+    let mut heart_rate = Vec::new();
+    let segments =
+        parse_gpx(track, filter, &mut removed_points, &mut heart_rate)?;
+    analyze_segments(
+        cancel,
+        ski_area,
+        mode,
+        profile,
+        segments,
+        removed_points,
+        heart_rate,
+    )
+}
 
-        for a in find_moves(cancel, ski_area, activity.route)? {
-            result.push(a);
+/// Analyzes multiple recordings, e.g. one per day or from multiple devices,
+/// as a single route.
+pub fn analyze_routes(
+    cancel: &CancellationToken,
+    ski_area: &SkiArea,
+    mode: AnalysisMode,
+    profile: &MoveProfile,
+    filter: &NoiseFilter,
+    tracks: Vec<RecordedTrack>,
+) -> Result<AnalyzedRoute> {
+    let mut removed_points = Vec::new();
+    let mut heart_rate = Vec::new();
+    let segments =
+        parse_gpxs(tracks, filter, &mut removed_points, &mut heart_rate)?;
+    analyze_segments(
+        cancel,
+        ski_area,
+        mode,
+        profile,
+        segments,
+        removed_points,
+        heart_rate,
+    )
+}
+
+fn analyze_segments(
+    cancel: &CancellationToken,
+    ski_area: &SkiArea,
+    mode: AnalysisMode,
+    profile: &MoveProfile,
+    mut segments: BoundedGeometry<Segments>,
+    removed_points: Vec<RemovedPoint>,
+    heart_rate: Vec<HeartRate>,
+) -> Result<AnalyzedRoute> {
+    let item = take(&mut segments.item);
+    let item = match mode {
+        AnalysisMode::Alpine => {
+            analyze_lifts_and_moves(cancel, ski_area, profile, item)?
         }
-    }
+        AnalysisMode::Nordic => find_trails(cancel, ski_area, item)?,
+        AnalysisMode::Summer => {
+            let mut result = analyze_lifts_and_moves(
+                cancel,
+                &ski_area.bike_park()?,
+                profile,
+                item,
+            )?;
+            // The pistes of the bike park are the bike trails.
+            for activity in &mut result {
+                if let ActivityType::Moving(moving) = &mut activity.type_ {
+                    if moving.terrain == Terrain::Piste
+                        && !moving.piste_id.is_empty()
+                    {
+                        moving.terrain = Terrain::BikeTrail;
+                    }
+                }
+            }
+            result
+        }
+    };
 
     Ok(AnalyzedRoute {
         route: BoundedGeometry {
-            item: result,
+            item,
             bounding_rect: segments.bounding_rect,
         },
+        removed_points,
+        heart_rate,
     })
 }
 
+fn analyze_lifts_and_moves(
+    cancel: &CancellationToken,
+    ski_area: &SkiArea,
+    profile: &MoveProfile,
+    segments: Segments,
+) -> Result<Vec<Activity>> {
+    let activities = find_lift_usage(cancel, ski_area, segments)?;
+    find_moves_in(cancel, ski_area, profile, activities)
+}
+
 fn get_time_diff(wp1: &Waypoint, wp2: &Waypoint) -> Option<Duration> {
     let t1 = OffsetDateTime::from(wp1.time?);
     let t2 = OffsetDateTime::from(wp2.time?);
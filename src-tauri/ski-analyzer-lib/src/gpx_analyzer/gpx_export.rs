@@ -0,0 +1,157 @@
+use gpx::{Gpx, GpxVersion, Metadata, Track, TrackSegment};
+
+use super::{Activity, ActivityType, AnalyzedRoute, MoveType};
+use crate::ski_area::SkiArea;
+
+const CREATOR: &str = "ski-analyzer";
+
+fn get_display_name(name: &str, ref_: &str, id: &str) -> String {
+    if !name.is_empty() {
+        name.to_string()
+    } else if !ref_.is_empty() {
+        ref_.to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+fn get_lift_name(ski_area: &SkiArea, lift_id: &str) -> String {
+    ski_area
+        .lifts()
+        .get(lift_id)
+        .map_or(lift_id.to_string(), |l| {
+            get_display_name(&l.name, &l.ref_, lift_id)
+        })
+}
+
+fn get_move_type_name(move_type: MoveType) -> &'static str {
+    match move_type {
+        MoveType::Ski => "ski",
+        MoveType::Wait => "wait",
+        MoveType::Climb => "climb",
+        MoveType::Traverse => "traverse",
+        MoveType::Unknown => "unknown",
+    }
+}
+
+/// Returns the name, type and description of the track.
+fn describe_activity(
+    ski_area: &SkiArea,
+    activity: &Activity,
+) -> (String, &'static str, Option<String>) {
+    match &activity.type_ {
+        ActivityType::Unknown(()) => ("Unknown".to_string(), "unknown", None),
+        ActivityType::UseLift(use_lift) => {
+            let lift = ski_area.lifts().get(&use_lift.lift_id);
+            (
+                format!("Lift: {}", get_lift_name(ski_area, &use_lift.lift_id)),
+                "lift",
+                lift.map(|l| l.type_.clone()),
+            )
+        }
+        ActivityType::EnterLift(lift_id) => (
+            format!("Enter lift: {}", get_lift_name(ski_area, lift_id)),
+            "enter_lift",
+            None,
+        ),
+        ActivityType::ExitLift(lift_id) => (
+            format!("Exit lift: {}", get_lift_name(ski_area, lift_id)),
+            "exit_lift",
+            None,
+        ),
+        ActivityType::Moving(moving) => {
+            let type_ = get_move_type_name(moving.move_type);
+            let piste = moving.piste(ski_area).map(|p| &p.metadata);
+            let trail = moving.trail(ski_area).map(|t| &t.metadata);
+            match piste.or(trail) {
+                Some(metadata) => (
+                    format!(
+                        "{}: {}",
+                        type_,
+                        get_display_name(
+                            &metadata.name,
+                            &metadata.ref_,
+                            &moving.piste_id
+                        )
+                    ),
+                    type_,
+                    Some(match piste {
+                        Some(_) => format!("{:?}", metadata.difficulty),
+                        None => format!("{:?}", moving.terrain),
+                    }),
+                ),
+                None => (
+                    type_.to_string(),
+                    type_,
+                    Some(format!("{:?}", moving.terrain)),
+                ),
+            }
+        }
+        ActivityType::Trail(trail_id) => {
+            match ski_area.nordic_trails().get(trail_id) {
+                Some(trail) => (
+                    format!(
+                        "Trail: {}",
+                        get_display_name(
+                            &trail.metadata.name,
+                            &trail.metadata.ref_,
+                            trail_id
+                        )
+                    ),
+                    "trail",
+                    Some(format!("{:?}", trail.metadata.difficulty)),
+                ),
+                None => ("Off trail".to_string(), "off_trail", None),
+            }
+        }
+    }
+}
+
+/// Converts the analyzed route to GPX, with one track per activity. The
+/// track names contain the lift or piste name, and the track types contain
+/// the activity or move type.
+pub fn export_gpx(ski_area: &SkiArea, route: &AnalyzedRoute) -> Gpx {
+    let tracks = route
+        .route
+        .item
+        .iter()
+        .enumerate()
+        .map(|(i, activity)| {
+            let (name, type_, description) =
+                describe_activity(ski_area, activity);
+            let mut track = Track::new();
+            track.name = Some(name);
+            track.type_ = Some(type_.to_string());
+            track.description = description;
+            track.number = Some(i as u32 + 1);
+            track.segments = activity
+                .route
+                .0
+                .iter()
+                .map(|s| {
+                    let mut segment = TrackSegment::new();
+                    segment.points = s.clone();
+                    segment
+                })
+                .collect();
+            track
+        })
+        .collect();
+
+    let mut metadata = Metadata::default();
+    metadata.name = Some(ski_area.metadata.name.clone());
+    metadata.time = route
+        .route
+        .item
+        .iter()
+        .find_map(|a| a.begin_time)
+        .map(|t| t.into());
+
+    Gpx {
+        version: GpxVersion::Gpx11,
+        creator: Some(CREATOR.to_string()),
+        metadata: Some(metadata),
+        tracks,
+        ..Default::default()
+    }
+}
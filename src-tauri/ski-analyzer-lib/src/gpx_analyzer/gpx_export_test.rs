@@ -0,0 +1,157 @@
+use super::test_util::{activity, time};
+use super::{
+    export_gpx, ActivityType, AnalyzedRoute, MoveType, Moving, Terrain, UseLift,
+};
+use crate::ski_area::{
+    Difficulty, Lift, LiftAttributes, PointWithElevation, SkiArea,
+};
+use crate::utils::bounded_geometry::BoundedGeometry;
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, line, piste, Init,
+};
+
+use gpx::{GpxVersion, Time};
+use rstest::{fixture, rstest};
+use time::OffsetDateTime;
+
+use std::collections::HashMap;
+
+#[fixture]
+fn ski_area() -> SkiArea {
+    let line_ = line(&[(6.0, 45.0), (6.0, 45.01)]);
+    let lift = Lift {
+        ref_: "A".to_string(),
+        name: String::new(),
+        type_: "chair_lift".to_string(),
+        stations: line_
+            .points()
+            .map(|p| PointWithElevation {
+                point: p,
+                elevation: None,
+            })
+            .collect(),
+        line: BoundedGeometry::new(line_).unwrap(),
+        lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
+        can_go_reverse: false,
+        can_disembark: false,
+    };
+    let mut piste1 =
+        piste("Blue run", vec![line(&[(6.0, 45.01), (6.0, 45.0)])], vec![]);
+    piste1.metadata.difficulty = Difficulty::Intermediate;
+    SkiArea::new(
+        create_ski_area_metadata("Ski area".to_string()),
+        HashMap::from([("l1".to_string(), lift)]),
+        HashMap::from([("p1".to_string(), piste1)]),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+fn moving(move_type: MoveType, piste_id: &str) -> ActivityType {
+    ActivityType::Moving(Moving {
+        move_type,
+        piste_id: piste_id.to_string(),
+        terrain: if piste_id.is_empty() {
+            Terrain::OffPiste
+        } else {
+            Terrain::Piste
+        },
+    })
+}
+
+#[rstest]
+fn export(_init: Init, ski_area: SkiArea) {
+    let activities = vec![
+        activity(
+            ActivityType::Unknown(()),
+            &[(6.0, 44.999, 1000.0, 0.0), (6.0, 45.0, 1000.0, 60.0)],
+        ),
+        activity(
+            ActivityType::EnterLift("l1".to_string()),
+            &[(6.0, 45.0, 1000.0, 60.0), (6.0, 45.0, 1000.0, 90.0)],
+        ),
+        activity(
+            ActivityType::UseLift(UseLift {
+                lift_id: "l1".to_string(),
+                begin_station: Some(0),
+                end_station: Some(1),
+                is_reverse: false,
+            }),
+            &[
+                (6.0, 45.0, 1000.0, 90.0),
+                (6.0, 45.005, 1400.0, 300.0),
+                (6.0, 45.01, 1800.0, 500.0),
+            ],
+        ),
+        activity(
+            ActivityType::ExitLift("l1".to_string()),
+            &[(6.0, 45.01, 1800.0, 500.0), (6.0, 45.0101, 1800.0, 510.0)],
+        ),
+        activity(
+            moving(MoveType::Ski, "p1"),
+            &[(6.0, 45.0101, 1800.0, 510.0), (6.0, 45.0, 1000.0, 700.0)],
+        ),
+        activity(
+            moving(MoveType::Wait, ""),
+            &[(6.0, 45.0, 1000.0, 700.0), (6.0, 45.0, 1000.0, 760.0)],
+        ),
+    ];
+    let route = AnalyzedRoute {
+        route: BoundedGeometry {
+            item: activities,
+            bounding_rect: ski_area.bounding_rect,
+        },
+        removed_points: Vec::new(),
+        heart_rate: Vec::new(),
+    };
+
+    let gpx = export_gpx(&ski_area, &route);
+    assert_eq!(gpx.version, GpxVersion::Gpx11);
+    let metadata = gpx.metadata.as_ref().unwrap();
+    assert_eq!(metadata.name.as_deref(), Some("Ski area"));
+    assert_eq!(metadata.time, Some(Time::from(time(0.0))));
+
+    let tracks: Vec<_> = gpx
+        .tracks
+        .iter()
+        .map(|t| {
+            (
+                t.name.clone().unwrap(),
+                t.type_.clone().unwrap(),
+                t.description.clone(),
+                t.number,
+            )
+        })
+        .collect();
+    let s = |s: &str| s.to_string();
+    assert_eq!(
+        tracks,
+        vec![
+            (s("Unknown"), s("unknown"), None, Some(1)),
+            (s("Enter lift: A"), s("enter_lift"), None, Some(2)),
+            (s("Lift: A"), s("lift"), Some(s("chair_lift")), Some(3)),
+            (s("Exit lift: A"), s("exit_lift"), None, Some(4)),
+            (
+                s("ski: Blue run"),
+                s("ski"),
+                Some(s("Intermediate")),
+                Some(5)
+            ),
+            (s("wait"), s("wait"), Some(s("OffPiste")), Some(6)),
+        ]
+    );
+
+    for (track, activity) in gpx.tracks.iter().zip(route.route.item.iter()) {
+        assert_eq!(track.segments.len(), 1);
+        assert_eq!(track.segments[0].points, activity.route.0[0]);
+    }
+
+    let mut buffer = Vec::new();
+    gpx::write(&gpx, &mut buffer).unwrap();
+    let read_back = gpx::read(buffer.as_slice()).unwrap();
+    assert_eq!(read_back.tracks.len(), 6);
+    assert_eq!(read_back.tracks[2].name.as_deref(), Some("Lift: A"));
+    assert_eq!(read_back.tracks[2].segments[0].points.len(), 3);
+}
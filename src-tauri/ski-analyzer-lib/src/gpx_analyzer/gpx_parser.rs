@@ -1,18 +1,34 @@
-use super::Segments;
 use super::{format_time_option, to_odt};
+use super::{
+    HeartRate, NoiseFilter, RemovalReason, RemovedPoint, Segment, Segments,
+};
 use crate::config::get_config;
 use crate::error::{Error, ErrorType, Result};
 use crate::utils::bounded_geometry::BoundedGeometry;
 use crate::utils::rect::union_rects_if;
+use crate::utils::track::{RecordedTrack, SensorData};
 
 use geo::{Coord, Rect};
-use gpx::{Gpx, Time, Waypoint};
+use gpx::{Time, Waypoint};
+use time::OffsetDateTime;
 
 use std::mem;
 
+/// The largest acceptable horizontal dilution of precision.
 const PRECISION_LIMIT: f64 = 10.0;
+/// The largest acceptable GPS accuracy reported by the device, in meters.
+const ACCURACY_LIMIT: f64 = 50.0;
 
-pub fn parse_gpx(gpx: Gpx) -> Result<BoundedGeometry<Segments>> {
+/// Splits the route into segments at points with bad precision or accuracy
+/// and filters each segment. The points that are left out are added to
+/// `removed`. The heart rate of every point with a time is added to
+/// `heart_rate`, even if the position of the point is left out.
+pub fn parse_gpx(
+    recorded: RecordedTrack,
+    filter: &NoiseFilter,
+    removed: &mut Vec<RemovedPoint>,
+    heart_rate: &mut Vec<HeartRate>,
+) -> Result<BoundedGeometry<Segments>> {
     let mut result = Vec::new();
     let mut bounding_rect: Option<Rect> = None;
     let config = get_config();
@@ -24,14 +40,30 @@ pub fn parse_gpx(gpx: Gpx) -> Result<BoundedGeometry<Segments>> {
         max_precision: f64,
     }
 
-    for track in gpx.tracks {
+    let mut sensor_data = recorded.sensor_data.into_iter();
+    for track in recorded.gpx.tracks {
         for segment in track.segments {
-            let mut add = |current: &mut Vec<Waypoint>| {
-                if !current.is_empty() {
-                    result.push(mem::take(current));
-                }
-            };
+            let mut add =
+                |current: &mut Vec<Waypoint>,
+                 current_data: &mut Vec<SensorData>,
+                 removed: &mut Vec<RemovedPoint>| {
+                    let filtered = filter.filter(
+                        mem::take(current),
+                        mem::take(current_data),
+                        removed,
+                    );
+                    if filtered.is_empty() {
+                        return;
+                    }
+                    for waypoint in &filtered {
+                        let coord = Coord::from(waypoint.point());
+                        let r0 = Rect::new(coord, coord);
+                        bounding_rect = union_rects_if(bounding_rect, Some(r0));
+                    }
+                    result.push(filtered);
+                };
             let mut current = Vec::new();
+            let mut current_data = Vec::new();
 
             let mut bad_precision_debug: Option<BadPrecisionDebug> = None;
 
@@ -40,8 +72,19 @@ pub fn parse_gpx(gpx: Gpx) -> Result<BoundedGeometry<Segments>> {
                     Some(p) => p,
                     None => 0.0,
                 };
-                if precision > PRECISION_LIMIT {
-                    add(&mut current);
+                let data = sensor_data.next().unwrap_or_default();
+                if let (Some(time), Some(value)) =
+                    (to_odt(waypoint.time), data.heart_rate)
+                {
+                    heart_rate.push(HeartRate { time, value });
+                }
+                let accuracy = data.accuracy.unwrap_or(0.0);
+                if precision > PRECISION_LIMIT || accuracy > ACCURACY_LIMIT {
+                    add(&mut current, &mut current_data, removed);
+                    removed.push(RemovedPoint::new(
+                        &waypoint,
+                        RemovalReason::BadPrecision,
+                    ));
                     if config.is_vv() {
                         if let Some(bpd) = bad_precision_debug.as_mut() {
                             bpd.min_precision =
@@ -71,13 +114,11 @@ pub fn parse_gpx(gpx: Gpx) -> Result<BoundedGeometry<Segments>> {
                         }
                         bad_precision_debug = None;
                     }
-                    let coord = Coord::from(waypoint.point());
-                    let r0 = Rect::new(coord, coord);
-                    bounding_rect = union_rects_if(bounding_rect, Some(r0));
                     current.push(waypoint);
+                    current_data.push(data);
                 }
             }
-            add(&mut current);
+            add(&mut current, &mut current_data, removed);
         }
     }
 
@@ -87,3 +128,88 @@ pub fn parse_gpx(gpx: Gpx) -> Result<BoundedGeometry<Segments>> {
             .ok_or(Error::new_s(ErrorType::InputError, "Empty route"))?,
     })
 }
+
+fn get_time(wp: &Waypoint) -> Option<OffsetDateTime> {
+    to_odt(wp.time)
+}
+
+/// Orders segments by their start time and removes waypoints that overlap
+/// with an earlier segment, such as when the same trip is recorded by
+/// multiple devices. Segments without time information are put at the end.
+fn merge_segments(
+    mut segments: Vec<Segment>,
+    removed: &mut Vec<RemovedPoint>,
+) -> Vec<Segment> {
+    segments.sort_by_key(|s| {
+        let begin = s.iter().find_map(get_time);
+        (begin.is_none(), begin)
+    });
+
+    let config = get_config();
+    let mut result = Vec::new();
+    let mut last_time: Option<OffsetDateTime> = None;
+    for mut segment in segments {
+        if let Some(last) = last_time {
+            let original_len = segment.len();
+            segment.retain(|wp| {
+                let keep = get_time(wp).map_or(true, |t| t > last);
+                if !keep {
+                    removed.push(RemovedPoint::new(
+                        wp,
+                        RemovalReason::Overlapping,
+                    ));
+                }
+                keep
+            });
+            if config.is_vv() && segment.len() != original_len {
+                eprintln!(
+                    "Removed {} overlapping waypoints before {}",
+                    original_len - segment.len(),
+                    format_time_option(Some(last)),
+                );
+            }
+        }
+        if let Some(end) = segment.iter().rev().find_map(get_time) {
+            last_time = Some(last_time.map_or(end, |t| t.max(end)));
+        }
+        if !segment.is_empty() {
+            result.push(segment);
+        }
+    }
+    result
+}
+
+/// Parses multiple GPX inputs into a single chronologically ordered route.
+pub fn parse_gpxs(
+    tracks: Vec<RecordedTrack>,
+    filter: &NoiseFilter,
+    removed: &mut Vec<RemovedPoint>,
+    heart_rate: &mut Vec<HeartRate>,
+) -> Result<BoundedGeometry<Segments>> {
+    let mut segments = Vec::new();
+    let mut bounding_rect: Option<Rect> = None;
+    for track in tracks {
+        match parse_gpx(track, filter, removed, heart_rate) {
+            Ok(parsed) => {
+                bounding_rect =
+                    union_rects_if(bounding_rect, Some(parsed.bounding_rect));
+                segments.extend(parsed.item.0);
+            }
+            Err(err) if err.get_type() == ErrorType::InputError => {
+                if get_config().is_v() {
+                    eprintln!("Skipping input: {}", err);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    // Keep the first measurement if multiple devices recorded the heart rate.
+    heart_rate.sort_by_key(|h| h.time);
+    heart_rate.dedup_by_key(|h| h.time);
+
+    Ok(BoundedGeometry {
+        item: Segments::new(merge_segments(segments, removed)),
+        bounding_rect: bounding_rect
+            .ok_or(Error::new_s(ErrorType::InputError, "Empty route"))?,
+    })
+}
@@ -1,12 +1,19 @@
-use super::gpx_parser::parse_gpx;
+use super::gpx_parser::{parse_gpx, parse_gpxs};
 use super::test_util::wp;
+use super::{
+    analyze_route, AnalysisMode, HeartRate, MoveProfile, NoiseFilter,
+    RemovalReason, RemovedPoint,
+};
+use crate::utils::cancel::CancellationToken;
+use crate::utils::track::{RecordedTrack, SensorData};
 use crate::{
     assert_eq_pretty,
-    utils::test_util::{init, Init},
+    utils::test_util::{create_ski_area, init, lift, Init},
 };
 
 use gpx::{Gpx, Track, TrackSegment, Waypoint};
 use rstest::rstest;
+use time::{Duration, OffsetDateTime};
 
 fn segment(input: &[(f64, f64, Option<f64>)]) -> TrackSegment {
     let mut result = TrackSegment::new();
@@ -14,6 +21,21 @@ fn segment(input: &[(f64, f64, Option<f64>)]) -> TrackSegment {
     result
 }
 
+fn timed_segment(input: &[(f64, f64, Option<i64>)]) -> TrackSegment {
+    let mut result = TrackSegment::new();
+    result.points = input
+        .iter()
+        .map(|(x, y, t)| {
+            let mut wp = wp(*x, *y, None);
+            wp.time = t.map(|t| {
+                (OffsetDateTime::UNIX_EPOCH + Duration::seconds(t)).into()
+            });
+            wp
+        })
+        .collect();
+    result
+}
+
 fn track(input: Vec<TrackSegment>) -> Track {
     let mut result = Track::new();
     result.segments = input;
@@ -26,6 +48,10 @@ fn make_gpx(input: Vec<Track>) -> Gpx {
     result
 }
 
+fn reasons(removed: &[RemovedPoint]) -> Vec<RemovalReason> {
+    removed.iter().map(|r| r.reason).collect()
+}
+
 fn get_wp(
     gpx: &Gpx,
     track_id: usize,
@@ -56,7 +82,16 @@ fn multiple_tracks_and_segments(_init: Init) {
         ])]),
     ]);
 
-    let actual = parse_gpx(gpx.clone()).unwrap().item;
+    let mut removed = Vec::new();
+    let actual = parse_gpx(
+        gpx.clone().into(),
+        &NoiseFilter::default(),
+        &mut removed,
+        &mut Vec::new(),
+    )
+    .unwrap()
+    .item;
+    assert!(removed.is_empty());
     let expected = vec![
         vec![
             get_wp(&gpx, 0, 0, 0),
@@ -102,7 +137,17 @@ fn bad_accuracy(_init: Init) {
         ]),
     ])]);
 
-    let actual = parse_gpx(gpx.clone()).unwrap().item;
+    let mut removed = Vec::new();
+    let actual = parse_gpx(
+        gpx.clone().into(),
+        &NoiseFilter::default(),
+        &mut removed,
+        &mut Vec::new(),
+    )
+    .unwrap()
+    .item;
+    assert_eq!(reasons(&removed), vec![RemovalReason::BadPrecision; 5]);
+    assert_eq!(removed[0].point.x_y(), (2.0, 4.0));
     let expected = vec![
         vec![
             get_wp(&gpx, 0, 0, 0),
@@ -128,3 +173,196 @@ fn bad_accuracy(_init: Init) {
     ];
     assert_eq_pretty!(actual.0, expected);
 }
+
+#[rstest]
+fn bad_sensor_accuracy(_init: Init) {
+    // The accuracy is in meters, so it has a different limit than HDOP.
+    let gpx = make_gpx(vec![track(vec![segment(&[
+        (1.0, 1.0, None),
+        (1.0, 2.0, None),
+        (1.0, 3.0, None),
+        (1.0, 4.0, None),
+        (1.0, 5.0, None),
+    ])])]);
+    let recorded = RecordedTrack {
+        gpx: gpx.clone(),
+        sensor_data: [Some(5.0), Some(20.0), Some(80.0), None, Some(10.0)]
+            .into_iter()
+            .map(|accuracy| SensorData {
+                accuracy,
+                ..Default::default()
+            })
+            .collect(),
+    };
+
+    let mut removed = Vec::new();
+    let actual = parse_gpx(
+        recorded,
+        &NoiseFilter::default(),
+        &mut removed,
+        &mut Vec::new(),
+    )
+    .unwrap()
+    .item;
+    assert_eq!(reasons(&removed), vec![RemovalReason::BadPrecision]);
+    assert_eq!(removed[0].point.x_y(), (1.0, 3.0));
+    let expected = vec![
+        vec![get_wp(&gpx, 0, 0, 0), get_wp(&gpx, 0, 0, 1)],
+        vec![get_wp(&gpx, 0, 0, 3), get_wp(&gpx, 0, 0, 4)],
+    ];
+    assert_eq_pretty!(actual.0, expected);
+}
+
+#[rstest]
+fn heart_rate(_init: Init) {
+    let mut gpx = make_gpx(vec![track(vec![timed_segment(&[
+        (6.0, 45.0, Some(0)),
+        (6.0, 45.0001, Some(10)),
+        (6.0, 45.0002, Some(20)),
+        (6.0, 45.0003, None),
+    ])])]);
+    // The position is left out, but the heart rate is still valid.
+    gpx.tracks[0].segments[0].points[1].hdop = Some(20.0);
+    let recorded = RecordedTrack {
+        gpx,
+        sensor_data: [Some(100), Some(110), None, Some(130)]
+            .into_iter()
+            .map(|heart_rate| SensorData {
+                heart_rate,
+                ..Default::default()
+            })
+            .collect(),
+    };
+
+    let actual = analyze_route(
+        &CancellationToken::new(),
+        &create_ski_area(
+            vec![("l1", lift("L1", &[(6.01, 45.0), (6.01, 45.01)]))],
+            Vec::new(),
+        ),
+        AnalysisMode::Alpine,
+        &MoveProfile::default(),
+        &NoiseFilter::default(),
+        recorded,
+    )
+    .unwrap();
+    let time = |t| OffsetDateTime::UNIX_EPOCH + Duration::seconds(t);
+    assert_eq!(
+        actual.heart_rate,
+        vec![
+            HeartRate {
+                time: time(0),
+                value: 100
+            },
+            HeartRate {
+                time: time(10),
+                value: 110
+            },
+        ]
+    );
+}
+
+#[rstest]
+fn merge_ordered(_init: Init) {
+    let gpx1 = make_gpx(vec![track(vec![timed_segment(&[
+        (2.0, 1.0, Some(100)),
+        (2.0, 2.0, Some(110)),
+    ])])]);
+    let gpx2 = make_gpx(vec![track(vec![
+        timed_segment(&[(1.0, 1.0, Some(10)), (1.0, 2.0, Some(20))]),
+        timed_segment(&[(3.0, 1.0, Some(200)), (3.0, 2.0, Some(210))]),
+    ])]);
+
+    let actual = parse_gpxs(
+        vec![gpx1.clone().into(), gpx2.clone().into()],
+        &NoiseFilter::default(),
+        &mut Vec::new(),
+        &mut Vec::new(),
+    )
+    .unwrap();
+    let expected = vec![
+        vec![get_wp(&gpx2, 0, 0, 0), get_wp(&gpx2, 0, 0, 1)],
+        vec![get_wp(&gpx1, 0, 0, 0), get_wp(&gpx1, 0, 0, 1)],
+        vec![get_wp(&gpx2, 0, 1, 0), get_wp(&gpx2, 0, 1, 1)],
+    ];
+    assert_eq_pretty!(actual.item.0, expected);
+    assert_eq!(actual.bounding_rect.min().x, 1.0);
+    assert_eq!(actual.bounding_rect.max().x, 3.0);
+}
+
+#[rstest]
+fn merge_overlapping(_init: Init) {
+    let gpx1 = make_gpx(vec![track(vec![timed_segment(&[
+        (1.0, 1.0, Some(10)),
+        (1.0, 2.0, Some(20)),
+        (1.0, 3.0, Some(30)),
+    ])])]);
+    let gpx2 = make_gpx(vec![track(vec![
+        timed_segment(&[
+            (2.0, 1.0, Some(15)),
+            (2.0, 2.0, Some(25)),
+            (2.0, 3.0, Some(30)),
+            (2.0, 4.0, Some(35)),
+            (2.0, 5.0, Some(45)),
+        ]),
+        timed_segment(&[(3.0, 1.0, Some(40)), (3.0, 2.0, Some(42))]),
+    ])]);
+
+    let mut removed = Vec::new();
+    let actual = parse_gpxs(
+        vec![gpx1.clone().into(), gpx2.clone().into()],
+        &NoiseFilter::default(),
+        &mut removed,
+        &mut Vec::new(),
+    )
+    .unwrap()
+    .item;
+    assert_eq!(reasons(&removed), vec![RemovalReason::Overlapping; 5]);
+    let expected = vec![
+        vec![
+            get_wp(&gpx1, 0, 0, 0),
+            get_wp(&gpx1, 0, 0, 1),
+            get_wp(&gpx1, 0, 0, 2),
+        ],
+        vec![get_wp(&gpx2, 0, 0, 3), get_wp(&gpx2, 0, 0, 4)],
+    ];
+    assert_eq_pretty!(actual.0, expected);
+}
+
+#[rstest]
+fn merge_untimed(_init: Init) {
+    let gpx1 = make_gpx(vec![track(vec![timed_segment(&[
+        (1.0, 1.0, None),
+        (1.0, 2.0, None),
+    ])])]);
+    let gpx2 = make_gpx(vec![track(vec![timed_segment(&[
+        (2.0, 1.0, Some(10)),
+        (2.0, 2.0, Some(20)),
+    ])])]);
+    let gpx3 = make_gpx(vec![]);
+
+    let actual = parse_gpxs(
+        vec![gpx1.clone().into(), gpx2.clone().into(), gpx3.into()],
+        &NoiseFilter::default(),
+        &mut Vec::new(),
+        &mut Vec::new(),
+    )
+    .unwrap()
+    .item;
+    let expected = vec![
+        vec![get_wp(&gpx2, 0, 0, 0), get_wp(&gpx2, 0, 0, 1)],
+        vec![get_wp(&gpx1, 0, 0, 0), get_wp(&gpx1, 0, 0, 1)],
+    ];
+    assert_eq_pretty!(actual.0, expected);
+}
+
+#[rstest]
+fn merge_empty(_init: Init) {
+    assert!(parse_gpxs(
+        vec![make_gpx(vec![]).into()],
+        &NoiseFilter::default(),
+        &mut Vec::new(),
+        &mut Vec::new(),
+    )
+    .is_err());
+}
@@ -0,0 +1,98 @@
+use gpx::Waypoint;
+
+use super::moving::find_moves;
+use super::use_lift::LiftUsageFinder;
+use super::{Activity, ActivityType, MoveProfile};
+use crate::error::Result;
+use crate::ski_area::SkiArea;
+use crate::utils::cancel::CancellationToken;
+
+/// Analyzes a route while it is being recorded. Waypoints are added one at a
+/// time, and activities are returned as soon as they are final. Feeding a
+/// whole route gives the same result as analyzing it at once.
+pub struct IncrementalAnalyzer<'s> {
+    ski_area: &'s SkiArea,
+    profile: &'s MoveProfile,
+    lift_usage: LiftUsageFinder<'s>,
+    /// The result of [`Self::pending`] until the next waypoint is added.
+    pending: Option<Vec<Activity>>,
+}
+
+impl<'s> IncrementalAnalyzer<'s> {
+    pub fn new(ski_area: &'s SkiArea, profile: &'s MoveProfile) -> Self {
+        Self {
+            ski_area,
+            profile,
+            lift_usage: LiftUsageFinder::new(ski_area),
+            pending: None,
+        }
+    }
+
+    /// Adds the next waypoint and returns the activities that became final.
+    pub fn add_point(
+        &mut self,
+        cancel: &CancellationToken,
+        point: Waypoint,
+    ) -> Result<Vec<Activity>> {
+        self.pending = None;
+        let activities = self.lift_usage.add_point(point);
+        find_moves_in(cancel, self.ski_area, self.profile, activities)
+    }
+
+    /// The next waypoint starts a new track segment.
+    pub fn end_segment(&mut self) {
+        self.pending = None;
+        self.lift_usage.end_segment();
+    }
+
+    /// Provisional analysis of the part of the route that is not final yet.
+    /// Lift usage is only detected once the lift ride is over, so an ongoing
+    /// ride is returned as moves. The result is only calculated again after
+    /// new waypoints are added.
+    pub fn pending(
+        &mut self,
+        cancel: &CancellationToken,
+    ) -> Result<&[Activity]> {
+        if self.pending.is_none() {
+            let route = self.lift_usage.pending_route();
+            self.pending = Some(if route.0.is_empty() {
+                Vec::new()
+            } else {
+                find_moves(cancel, self.ski_area, self.profile, route)?
+            });
+        }
+        Ok(self.pending.as_deref().unwrap())
+    }
+
+    /// IDs of the lifts that the route may currently be using.
+    pub fn possible_lifts(&self) -> Vec<&'s str> {
+        self.lift_usage.possible_lifts()
+    }
+
+    /// Ends the route and returns the remaining activities.
+    pub fn finish(self, cancel: &CancellationToken) -> Result<Vec<Activity>> {
+        let activities = self.lift_usage.finish();
+        find_moves_in(cancel, self.ski_area, self.profile, activities)
+    }
+}
+
+pub(super) fn find_moves_in(
+    cancel: &CancellationToken,
+    ski_area: &SkiArea,
+    profile: &MoveProfile,
+    activities: Vec<Activity>,
+) -> Result<Vec<Activity>> {
+    let mut result = Vec::with_capacity(activities.len());
+    for activity in activities {
+        match activity.type_ {
+            ActivityType::Unknown(_) => result.append(&mut find_moves(
+                cancel,
+                ski_area,
+                profile,
+                activity.route,
+            )?),
+            _ => result.push(activity),
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,218 @@
+use super::test_util::time;
+use super::{
+    analyze_segments, Activity, ActivityType, AnalysisMode,
+    IncrementalAnalyzer, MoveProfile, Segments, Terrain,
+};
+use crate::ski_area::{Lift, LiftAttributes, PointWithElevation, SkiArea};
+use crate::utils::bounded_geometry::BoundedGeometry;
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, line, piste, trail, Init,
+};
+
+use geo::point;
+use gpx::Waypoint;
+use rstest::{fixture, rstest};
+use time::OffsetDateTime;
+
+use std::collections::HashMap;
+
+#[fixture]
+fn ski_area() -> SkiArea {
+    let lift_line =
+        BoundedGeometry::new(line(&[(6.0, 45.0), (6.0, 45.01)])).unwrap();
+    let stations = [lift_line.item[0], lift_line.item[1]]
+        .iter()
+        .map(|c| PointWithElevation {
+            point: (*c).into(),
+            elevation: None,
+        })
+        .collect();
+    let lift = Lift {
+        ref_: String::new(),
+        name: "lift".to_string(),
+        type_: String::new(),
+        line: lift_line,
+        stations,
+        lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
+        can_go_reverse: false,
+        can_disembark: false,
+    };
+    let piste = piste(
+        "piste",
+        vec![line(&[(6.002, 45.01), (6.002, 45.0)])],
+        vec![],
+    );
+    SkiArea::new(
+        create_ski_area_metadata("incremental".to_string()),
+        HashMap::from([("lift".to_string(), lift)]),
+        HashMap::from([("piste".to_string(), piste)]),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+fn waypoint(x: f64, y: f64, ele: f64, t: f64) -> Waypoint {
+    let mut result = Waypoint::new(point! { x: x, y: y });
+    result.elevation = Some(ele);
+    result.time = Some(time(t).into());
+    result
+}
+
+/// Two laps: ride the lift up, then ski down the piste next to it.
+fn route() -> Segments {
+    let mut result = Vec::new();
+    let mut t = 0.0;
+    for _ in 0..2 {
+        for i in 0..=50 {
+            let y = 45.0 + 0.0002 * i as f64;
+            result.push(waypoint(6.0, y, 1000.0 + 10.0 * i as f64, t));
+            t += 8.0;
+        }
+        for i in 0..=5 {
+            let x = 6.0 + 0.0004 * i as f64;
+            result.push(waypoint(x, 45.0101, 1500.0, t));
+            t += 10.0;
+        }
+        for i in 1..=50 {
+            let y = 45.01 - 0.0002 * i as f64;
+            result.push(waypoint(6.002, y, 1500.0 - 10.0 * i as f64, t));
+            t += 2.0;
+        }
+        for i in 0..=5 {
+            let x = 6.002 - 0.0004 * i as f64;
+            result.push(waypoint(x, 44.9999, 1000.0, t));
+            t += 10.0;
+        }
+    }
+    Segments::new(vec![result])
+}
+
+fn types(activities: &[Activity]) -> Vec<String> {
+    activities
+        .iter()
+        .map(|a| match &a.type_ {
+            ActivityType::Moving(m) => {
+                format!("{:?} {}", m.move_type, m.piste_id)
+            }
+            ActivityType::UseLift(u) => format!("UseLift {}", u.lift_id),
+            t => format!("{:?}", t),
+        })
+        .collect()
+}
+
+/// The time of the first waypoint of each activity, in seconds.
+fn begin_times(activities: &[Activity]) -> Vec<f64> {
+    activities
+        .iter()
+        .map(|a| {
+            let wp = &a.route.0[0][0];
+            (OffsetDateTime::from(wp.time.unwrap()) - time(0.0))
+                .as_seconds_f64()
+        })
+        .collect()
+}
+
+#[rstest]
+fn streaming(_init: Init, ski_area: SkiArea) {
+    let cancel = CancellationToken::new();
+    let profile = MoveProfile::default();
+    let mut analyzer = IncrementalAnalyzer::new(&ski_area, &profile);
+    let mut actual = Vec::new();
+    // The index of the waypoint that made each activity final.
+    let mut emitted_at = Vec::new();
+    for (i, wp) in route().0.remove(0).into_iter().enumerate() {
+        let mut activities = analyzer.add_point(&cancel, wp).unwrap();
+        emitted_at.extend(activities.iter().map(|_| i));
+        actual.append(&mut activities);
+    }
+    let mut rest = analyzer.finish(&cancel).unwrap();
+    emitted_at.extend(rest.iter().map(|_| usize::MAX));
+    actual.append(&mut rest);
+
+    assert_eq!(
+        types(&actual),
+        vec![
+            "UseLift lift",
+            "Ski ",
+            "Ski piste",
+            "UseLift lift",
+            "Ski ",
+            "Ski piste",
+        ]
+    );
+    assert_eq!(
+        begin_times(&actual),
+        vec![0.0, 408.0, 458.0, 618.0, 1036.0, 1086.0]
+    );
+    // A lift ride is final as soon as the lift is left, the moves after it
+    // only when the next lift ride is over.
+    let end = usize::MAX;
+    assert_eq!(emitted_at, vec![52, 165, 165, 165, end, end]);
+}
+
+#[rstest]
+fn live_view(_init: Init, ski_area: SkiArea) {
+    let cancel = CancellationToken::new();
+    let profile = MoveProfile::default();
+    let mut analyzer = IncrementalAnalyzer::new(&ski_area, &profile);
+    assert!(analyzer.pending(&cancel).unwrap().is_empty());
+
+    let mut committed = Vec::new();
+    for wp in route().0.remove(0).into_iter().take(30) {
+        committed.append(&mut analyzer.add_point(&cancel, wp).unwrap());
+    }
+    assert!(committed.is_empty());
+    assert_eq!(analyzer.possible_lifts(), vec!["lift"]);
+    let pending = analyzer.pending(&cancel).unwrap().as_ptr();
+    assert!(!analyzer.pending(&cancel).unwrap().is_empty());
+    // Not calculated again without new waypoints.
+    assert_eq!(analyzer.pending(&cancel).unwrap().as_ptr(), pending);
+}
+
+#[rstest]
+fn summer(_init: Init, mut ski_area: SkiArea) {
+    ski_area.bike_trails_mut().insert(
+        "bike".to_string(),
+        trail("Bike", &[(6.002, 45.01), (6.002, 45.0)]),
+    );
+    let actual = analyze_segments(
+        &CancellationToken::new(),
+        &ski_area,
+        AnalysisMode::Summer,
+        &MoveProfile::default(),
+        BoundedGeometry {
+            item: route(),
+            bounding_rect: ski_area.bounding_rect,
+        },
+        Vec::new(),
+        Vec::new(),
+    )
+    .unwrap()
+    .route
+    .item;
+
+    assert_eq!(
+        types(&actual),
+        vec![
+            "UseLift lift",
+            "Ski ",
+            "Ski bike",
+            "UseLift lift",
+            "Ski ",
+            "Ski bike",
+        ]
+    );
+    let terrains: Vec<_> = actual
+        .iter()
+        .filter_map(|a| match &a.type_ {
+            ActivityType::Moving(m) if !m.piste_id.is_empty() => {
+                Some(m.terrain)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(terrains, vec![Terrain::BikeTrail; 2]);
+}
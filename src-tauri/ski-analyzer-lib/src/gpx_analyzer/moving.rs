@@ -1,34 +1,93 @@
 use super::{Activity, ActivityType, SegmentCoordinate, Segments};
 use crate::error::Result;
-use crate::ski_area::SkiArea;
+use crate::ski_area::{Piste, SkiArea, Trail};
 use crate::utils::cancel::CancellationToken;
 
 use find_pistes::find_pistes;
-use move_type::get_move_candidates;
 use process::process_moves;
+use terrain::{find_backcountry, find_trails};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 mod find_pistes;
 pub mod move_type;
 mod process;
+mod profile;
 mod simple_candidate;
+mod terrain;
 
 #[cfg(test)]
 mod find_pistes_test;
 #[cfg(test)]
 mod process_test;
 #[cfg(test)]
+mod profile_test;
+#[cfg(test)]
 mod simple_candidate_test;
+#[cfg(test)]
+mod terrain_test;
 
 pub use move_type::MoveType;
+pub use profile::{MoveProfile, DEFAULT_PROFILE};
+pub use simple_candidate::{Constraint, ConstraintLimit, ConstraintType};
+pub use terrain::Terrain;
 
 #[cfg_attr(feature = "specta", derive(specta::Type))]
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct Moving {
     pub move_type: MoveType,
+    /// The piste, sled run, ski touring route or bike trail that the move
+    /// follows, depending on the terrain. Empty if none.
     pub piste_id: String,
+    pub terrain: Terrain,
+}
+
+/// Older versions did not store the terrain. Moves on a piste are assumed to
+/// be on the piste, other moves are off-piste.
+impl<'de> Deserialize<'de> for Moving {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Moving, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Data {
+            move_type: MoveType,
+            piste_id: String,
+            terrain: Option<Terrain>,
+        }
+
+        let data = Data::deserialize(deserializer)?;
+        let terrain = data.terrain.unwrap_or(if data.piste_id.is_empty() {
+            Terrain::OffPiste
+        } else {
+            Terrain::Piste
+        });
+        Ok(Moving {
+            move_type: data.move_type,
+            piste_id: data.piste_id,
+            terrain,
+        })
+    }
+}
+
+impl Moving {
+    pub fn piste<'s>(&self, ski_area: &'s SkiArea) -> Option<&'s Piste> {
+        match self.terrain {
+            Terrain::SledRun | Terrain::SkiTour | Terrain::BikeTrail => None,
+            _ => ski_area.pistes().get(&self.piste_id),
+        }
+    }
+
+    /// The sled run, ski touring route or bike trail that the move follows.
+    pub fn trail<'s>(&self, ski_area: &'s SkiArea) -> Option<&'s Trail> {
+        match self.terrain {
+            Terrain::SledRun => ski_area.sled_runs().get(&self.piste_id),
+            Terrain::SkiTour => ski_area.skitour_routes().get(&self.piste_id),
+            Terrain::BikeTrail => ski_area.bike_trails().get(&self.piste_id),
+            _ => None,
+        }
+    }
 }
 
 fn commit_moves(
@@ -46,12 +105,20 @@ fn commit_moves(
 pub fn find_moves<'s>(
     cancel: &CancellationToken,
     ski_area: &'s SkiArea,
+    profile: &MoveProfile,
     mut segments: Segments,
 ) -> Result<Vec<Activity>> {
-    let move_coords =
-        process_moves(cancel, &mut segments, &get_move_candidates())?;
+    let move_coords = process_moves(
+        cancel,
+        &mut segments,
+        &profile.create_candidate_factories(),
+    )?;
     let coords_with_pistes =
         find_pistes(cancel, ski_area, &segments, move_coords)?;
+    let coords_with_pistes =
+        find_trails(cancel, ski_area, &segments, coords_with_pistes)?;
+    let coords_with_pistes =
+        find_backcountry(cancel, ski_area, &segments, coords_with_pistes)?;
 
     let moves = commit_moves(&mut segments, coords_with_pistes);
     Ok(moves)
@@ -7,7 +7,7 @@ use geo::{
     Line, Point, Rect,
 };
 
-use super::{MoveType, Moving, Segments};
+use super::{MoveType, Moving, Segments, Terrain};
 use crate::error::Result;
 use crate::gpx_analyzer::SegmentCoordinate;
 use crate::ski_area::{Difficulty, Piste, SkiArea};
@@ -161,8 +161,11 @@ impl<'a> Candidates<'a> {
             ski_area,
             candidates: HashMap::new(),
             bounding_rects: ski_area
-                .pistes
+                .pistes()
                 .iter()
+                // Abandoned pistes are not maintained, so skiing there is
+                // freeride.
+                .filter(|(_, p)| !p.data.attributes.is_abandoned())
                 .map(|(id, p)| {
                     let mut r = p.data.bounding_rect;
                     expand_rect(&mut r, get_min_distance(p));
@@ -201,11 +204,12 @@ impl<'a> Candidates<'a> {
         });
 
         let mut result = Vec::new();
-        let mut push = |piste_id, begin| {
+        let mut push = |piste_id, terrain, begin| {
             result.push((
                 Moving {
                     move_type,
                     piste_id,
+                    terrain,
                 },
                 begin,
             ))
@@ -222,7 +226,7 @@ impl<'a> Candidates<'a> {
                 continue;
             }
 
-            push(piste_id, begin);
+            push(piste_id, Terrain::of_piste(candidate.piste), begin);
             self.first_empty = Some(end);
 
             let mut possible_next = Vec::new();
@@ -244,7 +248,7 @@ impl<'a> Candidates<'a> {
                 current = candidates.pop();
                 if let Some((_, c)) = current.as_ref() {
                     if c.begin_coord > end {
-                        push(String::new(), end);
+                        push(String::new(), Terrain::OffPiste, end);
                     }
                 }
                 continue;
@@ -285,11 +289,18 @@ impl<'a> Candidates<'a> {
         for candidate in self.candidates.values_mut().flatten() {
             candidate.add_point(coord, point);
         }
+        // MAX_DISTANCE_FREERIDE is the largest distance returned by
+        // get_min_distance().
         for (id, piste) in self
             .ski_area
-            .pistes
-            .iter()
-            .filter(|(id, _)| self.bounding_rects[*id].intersects(point))
+            .index()
+            .pistes_near(*point, MAX_DISTANCE_FREERIDE)
+            .filter_map(|id| self.ski_area.pistes().get_key_value(id))
+            .filter(|(id, _)| {
+                self.bounding_rects
+                    .get(*id)
+                    .is_some_and(|r| r.intersects(point))
+            })
         {
             let entry = self.candidates.entry(id.clone());
             if let Entry::Occupied(e) = &entry {
@@ -352,6 +363,7 @@ pub fn find_pistes(
                         Moving {
                             move_type,
                             piste_id: String::new(),
+                            terrain: Terrain::OffPiste,
                         },
                         c,
                     ));
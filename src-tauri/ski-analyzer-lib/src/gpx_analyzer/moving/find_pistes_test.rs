@@ -6,7 +6,7 @@ use std::fs;
 use time::OffsetDateTime;
 
 use super::find_pistes::find_pistes;
-use super::{commit_moves, MoveType, Moving};
+use super::{commit_moves, MoveType, Moving, Terrain};
 use crate::assert_eq_pretty;
 use crate::gpx_analyzer::test_util::save_analyzed_route;
 use crate::gpx_analyzer::{SegmentCoordinate, Segments};
@@ -82,6 +82,7 @@ fn move_on_piste_with_line() {
         Moving {
             piste_id: "1".to_string(),
             move_type: MoveType::Ski,
+            terrain: Terrain::Piste,
         },
         (0, 0),
     )];
@@ -104,6 +105,7 @@ fn move_on_piste_with_area() {
         Moving {
             piste_id: "1".to_string(),
             move_type: MoveType::Ski,
+            terrain: Terrain::Piste,
         },
         (0, 0),
     )];
@@ -127,6 +129,7 @@ fn enter_and_leave_piste_with_area() {
             Moving {
                 piste_id: String::new(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (0, 0),
         ),
@@ -134,6 +137,7 @@ fn enter_and_leave_piste_with_area() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 5),
         ),
@@ -141,6 +145,7 @@ fn enter_and_leave_piste_with_area() {
             Moving {
                 piste_id: String::new(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (0, 27),
         ),
@@ -148,6 +153,7 @@ fn enter_and_leave_piste_with_area() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 35),
         ),
@@ -172,6 +178,7 @@ fn enter_and_leave_piste_with_line() {
             Moving {
                 piste_id: String::new(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (0, 0),
         ),
@@ -179,6 +186,7 @@ fn enter_and_leave_piste_with_line() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 5),
         ),
@@ -186,6 +194,7 @@ fn enter_and_leave_piste_with_line() {
             Moving {
                 piste_id: String::new(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (0, 27),
         ),
@@ -193,6 +202,7 @@ fn enter_and_leave_piste_with_line() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 35),
         ),
@@ -222,6 +232,7 @@ fn enter_and_leave_another_piste_with_area() {
             Moving {
                 piste_id: String::new(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (0, 0),
         ),
@@ -229,6 +240,7 @@ fn enter_and_leave_another_piste_with_area() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 5),
         ),
@@ -236,6 +248,7 @@ fn enter_and_leave_another_piste_with_area() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 27),
         ),
@@ -243,6 +256,7 @@ fn enter_and_leave_another_piste_with_area() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 35),
         ),
@@ -274,6 +288,7 @@ fn enter_and_leave_another_piste_with_line() {
             Moving {
                 piste_id: String::new(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (0, 0),
         ),
@@ -281,6 +296,7 @@ fn enter_and_leave_another_piste_with_line() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 5),
         ),
@@ -288,6 +304,7 @@ fn enter_and_leave_another_piste_with_line() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 26),
         ),
@@ -295,6 +312,7 @@ fn enter_and_leave_another_piste_with_line() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 36),
         ),
@@ -324,6 +342,7 @@ fn enter_and_leave_another_piste_from_area_to_line() {
             Moving {
                 piste_id: String::new(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (0, 0),
         ),
@@ -331,6 +350,7 @@ fn enter_and_leave_another_piste_from_area_to_line() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 5),
         ),
@@ -338,6 +358,7 @@ fn enter_and_leave_another_piste_from_area_to_line() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 27),
         ),
@@ -345,6 +366,7 @@ fn enter_and_leave_another_piste_from_area_to_line() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 35),
         ),
@@ -374,6 +396,7 @@ fn enter_and_leave_another_piste_from_line_to_area() {
             Moving {
                 piste_id: String::new(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (0, 0),
         ),
@@ -381,6 +404,7 @@ fn enter_and_leave_another_piste_from_line_to_area() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 5),
         ),
@@ -388,6 +412,7 @@ fn enter_and_leave_another_piste_from_line_to_area() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 27),
         ),
@@ -395,6 +420,7 @@ fn enter_and_leave_another_piste_from_line_to_area() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 35),
         ),
@@ -428,6 +454,7 @@ fn stay_on_piste_when_crossing_another_with_areas() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 0),
         ),
@@ -435,6 +462,7 @@ fn stay_on_piste_when_crossing_another_with_areas() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 33),
         ),
@@ -442,6 +470,7 @@ fn stay_on_piste_when_crossing_another_with_areas() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 38),
         ),
@@ -470,6 +499,7 @@ fn stay_on_piste_when_crossing_another_with_lines() {
         Moving {
             piste_id: "2".to_string(),
             move_type: MoveType::Ski,
+            terrain: Terrain::Piste,
         },
         (0, 0),
     )];
@@ -502,6 +532,7 @@ fn stay_on_piste_when_crossing_another_from_area_to_line() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 0),
         ),
@@ -509,6 +540,7 @@ fn stay_on_piste_when_crossing_another_from_area_to_line() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 33),
         ),
@@ -516,6 +548,7 @@ fn stay_on_piste_when_crossing_another_from_area_to_line() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 38),
         ),
@@ -544,6 +577,7 @@ fn stay_on_piste_when_crossing_another_from_line_to_area() {
         Moving {
             piste_id: "2".to_string(),
             move_type: MoveType::Ski,
+            terrain: Terrain::Piste,
         },
         (0, 0),
     )];
@@ -566,6 +600,7 @@ fn stay_on_piste_when_leaving_for_a_short_time_with_areas() {
         Moving {
             piste_id: "1".to_string(),
             move_type: MoveType::Ski,
+            terrain: Terrain::Piste,
         },
         (0, 0),
     )];
@@ -588,6 +623,7 @@ fn stay_on_piste_when_leaving_for_a_short_time_with_lines() {
         Moving {
             piste_id: "1".to_string(),
             move_type: MoveType::Ski,
+            terrain: Terrain::Piste,
         },
         (0, 0),
     )];
@@ -616,6 +652,7 @@ fn multiple_segments() {
             Moving {
                 piste_id: "2".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 0),
         ),
@@ -623,6 +660,7 @@ fn multiple_segments() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (1, 0),
         ),
@@ -647,6 +685,7 @@ fn multiple_segments_to_outside_piste() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 0),
         ),
@@ -654,6 +693,7 @@ fn multiple_segments_to_outside_piste() {
             Moving {
                 piste_id: "".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::OffPiste,
             },
             (1, 0),
         ),
@@ -661,6 +701,7 @@ fn multiple_segments_to_outside_piste() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (1, 3),
         ),
@@ -685,6 +726,7 @@ fn multiple_moves() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Ski,
+                terrain: Terrain::Piste,
             },
             (0, 0),
         ),
@@ -692,6 +734,7 @@ fn multiple_moves() {
             Moving {
                 piste_id: "1".to_string(),
                 move_type: MoveType::Traverse,
+                terrain: Terrain::Piste,
             },
             (0, 3),
         ),
@@ -1,15 +1,18 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-
-use super::{
-    process::CandidateFactory,
-    simple_candidate::{
-        Constraint, ConstraintLimit, ConstraintType, SimpleCandidateFactory,
-    },
-};
 
 #[cfg_attr(feature = "specta", derive(specta::Type))]
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
 pub enum MoveType {
     Ski,
     Wait,
@@ -18,119 +21,13 @@ pub enum MoveType {
     Unknown,
 }
 
-//struct DummyCandidate {}
-//
-//impl Candidate for DummyCandidate {
-//    fn add_line(
-//        &mut self,
-//        _wp0: &gpx::Waypoint,
-//        _wp1: &gpx::Waypoint,
-//    ) -> Option<bool> {
-//        Some(true)
-//    }
-//}
-//
-//struct DummyCandidateFactory {}
-//
-//impl CandidateFactory for DummyCandidateFactory {
-//    fn create_candidate(&self) -> Box<dyn super::process::Candidate> {
-//        Box::new(DummyCandidate {})
-//    }
-//}
-
-pub fn get_move_candidates() -> HashMap<MoveType, Box<dyn CandidateFactory>> {
-    //let factory: Box<dyn CandidateFactory> = Box::new(DummyCandidateFactory {});
-    //[(MoveType::Ski, factory)]
-    [
-        (
-            MoveType::Ski,
-            SimpleCandidateFactory::new(vec![Constraint::new(
-                ConstraintType::Speed,
-                Some(1.2),
-                None,
-                ConstraintLimit::Time,
-                1.0,
-            )]),
-        ),
-        (
-            MoveType::Wait,
-            SimpleCandidateFactory::new(vec![Constraint::new(
-                ConstraintType::Speed,
-                None,
-                Some(1.2),
-                ConstraintLimit::Time,
-                1.0,
-            )]),
-        ),
-    ]
-    //[
-    //    (
-    //        MoveType::Ski,
-    //        SimpleCandidateFactory::new(vec![
-    //            Constraint::new(
-    //                ConstraintType::Speed,
-    //                Some(5.0),
-    //                None,
-    //                ConstraintLimit::Distance,
-    //                100.0,
-    //            ),
-    //            Constraint::new(
-    //                ConstraintType::Inclination,
-    //                None,
-    //                Some(-0.05),
-    //                ConstraintLimit::Distance,
-    //                200.0,
-    //            ),
-    //        ]),
-    //    ),
-    //    (
-    //        MoveType::Wait,
-    //        SimpleCandidateFactory::new(vec![Constraint::new(
-    //            ConstraintType::Speed,
-    //            None,
-    //            Some(1.0),
-    //            ConstraintLimit::Time,
-    //            20.0,
-    //        )]),
-    //    ),
-    //    (
-    //        MoveType::Climb,
-    //        SimpleCandidateFactory::new(vec![
-    //            Constraint::new(
-    //                ConstraintType::Speed,
-    //                Some(0.1),
-    //                Some(2.0),
-    //                ConstraintLimit::Time,
-    //                20.0,
-    //            ),
-    //            Constraint::new(
-    //                ConstraintType::Inclination,
-    //                Some(0.05),
-    //                None,
-    //                ConstraintLimit::Time,
-    //                20.0,
-    //            ),
-    //        ]),
-    //    ),
-    //    (
-    //        MoveType::Traverse,
-    //        SimpleCandidateFactory::new(vec![
-    //            Constraint::new(
-    //                ConstraintType::Speed,
-    //                Some(0.5),
-    //                Some(5.0),
-    //                ConstraintLimit::Time,
-    //                20.0,
-    //            ),
-    //            Constraint::new(
-    //                ConstraintType::Inclination,
-    //                Some(-0.1),
-    //                Some(0.1),
-    //                ConstraintLimit::Distance,
-    //                100.0,
-    //            ),
-    //        ]),
-    //    ),
-    //]
-    .into()
+impl MoveType {
+    /// Whether this move type is used instead of `other` where both match.
+    /// Climbing and traversing are slow, so they also match waiting.
+    pub fn overrides(self, other: MoveType) -> bool {
+        matches!(
+            (self, other),
+            (MoveType::Climb | MoveType::Traverse, MoveType::Wait)
+        )
+    }
 }
@@ -19,8 +19,15 @@ pub trait Candidate {
 
 pub trait CandidateFactory {
     fn create_candidate(&self) -> Box<dyn Candidate>;
+
+    /// Whether the move type of this factory is used instead of `other`
+    /// where both match.
+    fn overrides(&self, _other: MoveType) -> bool {
+        false
+    }
 }
 
+#[derive(Clone, Copy)]
 struct FinishedCandidate {
     move_type: MoveType,
     min: SegmentCoordinate,
@@ -131,6 +138,12 @@ impl<'a> Process<'a> {
         finished_candidates.sort_by_key(|c| std::cmp::Reverse(c.min));
 
         let config = get_config();
+        let move_types = self.move_types;
+        let overrides = |move_type: MoveType, other: MoveType| {
+            move_types
+                .get(&move_type)
+                .is_some_and(|f| f.overrides(other))
+        };
 
         let mut push = |x, coord| {
             if config.is_vv() {
@@ -143,10 +156,40 @@ impl<'a> Process<'a> {
             let first_coord = finished_candidates.last().unwrap().min;
             let idx =
                 finished_candidates.partition_point(|c| c.min != first_coord);
-            let to_commit = finished_candidates
-                .drain(idx..)
-                .max_by_key(|c| c.max)
+            let mut group: Vec<FinishedCandidate> =
+                finished_candidates.drain(idx..).collect();
+            // On a tie, prefer the move type that is declared first so that
+            // the result does not depend on hash map ordering.
+            let to_commit_idx = (0..group.len())
+                .filter(|&i| {
+                    !group
+                        .iter()
+                        .any(|c| overrides(c.move_type, group[i].move_type))
+                })
+                .max_by_key(|&i| {
+                    (group[i].max, std::cmp::Reverse(group[i].move_type))
+                })
                 .unwrap();
+            let mut to_commit = group.swap_remove(to_commit_idx);
+            // The others may still continue after a shorter overriding move.
+            finished_candidates.append(&mut group);
+            // Stop where an overriding move starts. The rest of this move is
+            // committed after it.
+            if let Some(cut) = finished_candidates
+                .iter()
+                .filter(|c| {
+                    overrides(c.move_type, to_commit.move_type)
+                        && c.min < to_commit.max
+                })
+                .map(|c| c.min)
+                .min()
+            {
+                finished_candidates.push(FinishedCandidate {
+                    min: cut,
+                    ..to_commit
+                });
+                to_commit.max = cut;
+            }
             if to_commit.min != self.last_commit {
                 push(MoveType::Unknown, self.last_commit);
             }
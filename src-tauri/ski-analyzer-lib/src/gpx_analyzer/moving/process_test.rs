@@ -1,12 +1,19 @@
 use std::collections::HashMap;
 
+use geo::{point, Destination, Haversine, Point};
 use gpx::Waypoint;
+use rstest::rstest;
+use time::OffsetDateTime;
 
 use super::process::{process_moves, Candidate, CandidateFactory};
-use super::MoveType;
-use crate::gpx_analyzer::test_util::wp;
-use crate::gpx_analyzer::Segments;
+use super::{find_moves, MoveProfile, MoveType, Moving, Terrain};
+use crate::gpx_analyzer::test_util::{time, wp};
+use crate::gpx_analyzer::{ActivityType, Segments};
+use crate::ski_area::SkiArea;
 use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, line, piste, Init,
+};
 
 #[derive(Clone, Copy)]
 struct TestCandidate {
@@ -280,3 +287,220 @@ fn multiple_segments() {
     let expected = vec![(MoveType::Ski, (0, 0)), (MoveType::Ski, (1, 0))];
     assert_eq!(actual, expected);
 }
+
+/// Deterministic pseudo-random numbers for simulating recording noise.
+struct Noise(u64);
+
+impl Noise {
+    /// Uniformly distributed in [-1, 1).
+    fn next(&mut self) -> f64 {
+        // xorshift64
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+}
+
+/// Creates a track the way a watch records it: sampled about every 2
+/// seconds, with a speed that varies by 20%, a slowly drifting GPS error of a
+/// few meters and a barometric elevation error of a few decimeters. Each part
+/// is given as (duration in seconds, mean horizontal speed in m/s, grade,
+/// heading in degrees).
+fn track(parts: &[(f64, f64, f64, f64)]) -> Segments {
+    let mut noise = Noise(0x5eed);
+    let mut position = point! { x: 6.0, y: 45.0 };
+    let (mut ele, mut t) = (2000.0, 0.0);
+    // The GPS error to the east and north, in meters.
+    let mut error = (0.0, 0.0);
+    let mut waypoints = Vec::new();
+    let mut add = |noise: &mut Noise, position: Point, ele: f64, t: f64| {
+        error = (
+            0.99 * error.0 + 0.3 * noise.next(),
+            0.99 * error.1 + 0.3 * noise.next(),
+        );
+        let p = Haversine::destination(position, 90.0, error.0);
+        let p = Haversine::destination(p, 0.0, error.1);
+        let mut wp = Waypoint::new(p);
+        wp.elevation = Some(ele + 0.3 * noise.next());
+        wp.time = Some(time(t).into());
+        waypoints.push(wp);
+    };
+    add(&mut noise, position, ele, t);
+    // The samples are not aligned to the parts. A sample interval that spans
+    // two parts is moved according to the earlier one.
+    let mut end = 0.0;
+    for (duration, speed, grade, heading) in parts {
+        end += duration;
+        while t < end {
+            let dt = 2.0 + 0.5 * noise.next();
+            let distance = speed * (1.0 + 0.1 * noise.next()) * dt;
+            position = Haversine::destination(position, *heading, distance);
+            ele += distance * grade;
+            t += dt;
+            add(&mut noise, position, ele, t);
+        }
+    }
+    Segments::new(vec![waypoints])
+}
+
+fn process_with_default_profile(
+    segments: &mut Segments,
+) -> Vec<(MoveType, OffsetDateTime)> {
+    let result = process_moves(
+        &CancellationToken::new(),
+        segments,
+        &MoveProfile::default().create_candidate_factories(),
+    )
+    .unwrap();
+    result
+        .into_iter()
+        .map(|(move_type, coord)| {
+            let t = segments.get(coord).unwrap().time.unwrap();
+            (move_type, t.into())
+        })
+        .collect()
+}
+
+/// Checks the move types, and that each move starts within a few samples of
+/// the given time in seconds.
+fn assert_moves<T: PartialEq + std::fmt::Debug>(
+    actual: &[(T, OffsetDateTime)],
+    expected: &[(T, f64)],
+) {
+    let actual_types: Vec<_> = actual.iter().map(|(t, _)| t).collect();
+    let expected_types: Vec<_> = expected.iter().map(|(t, _)| t).collect();
+    assert_eq!(actual_types, expected_types, "{:?}", actual);
+    for ((_, a), (_, e)) in actual.iter().zip(expected) {
+        let diff = (*a - time(*e)).as_seconds_f64().abs();
+        assert!(diff <= 10.0, "{:?} expected={:?}", actual, expected);
+    }
+}
+
+#[rstest]
+fn skinning(_init: Init) {
+    // Stand at the bottom, skin up a 15% slope in switchbacks at a walking
+    // pace with a short stop at each kick turn, take the skins off at the
+    // top, then ski down.
+    let mut parts = vec![(60.0, 0.0, 0.0, 0.0)];
+    for i in 0..6 {
+        let heading = if i % 2 == 0 { 45.0 } else { 315.0 };
+        parts.extend([(96.0, 0.7, 0.15, heading), (4.0, 0.0, 0.0, heading)]);
+    }
+    parts.extend([(120.0, 0.0, 0.0, 0.0), (60.0, 8.0, -0.3, 0.0)]);
+    let mut segments = track(&parts);
+    let actual = process_with_default_profile(&mut segments);
+    assert_moves(
+        &actual,
+        &[
+            (MoveType::Wait, 0.0),
+            (MoveType::Climb, 60.0),
+            (MoveType::Wait, 660.0),
+            (MoveType::Ski, 780.0),
+        ],
+    );
+}
+
+#[rstest]
+fn lift_queue(_init: Init) {
+    // Shuffle forward in the lift queue every now and then, then ski.
+    let mut parts = Vec::new();
+    for _ in 0..10 {
+        parts.extend([(16.0, 0.0, 0.0, 0.0), (4.0, 1.0, 0.0, 0.0)]);
+    }
+    parts.push((60.0, 8.0, -0.3, 0.0));
+    let mut segments = track(&parts);
+    let actual = process_with_default_profile(&mut segments);
+    assert_moves(&actual, &[(MoveType::Wait, 0.0), (MoveType::Ski, 200.0)]);
+}
+
+#[rstest]
+fn slow_downhill_is_not_traverse(_init: Init) {
+    // Going slowly on a steep slope is not traversing, but waiting like any
+    // other slow movement.
+    let segments = track(&[
+        (60.0, 8.0, -0.25, 0.0),
+        (180.0, 0.7, -0.2, 0.0),
+        (60.0, 8.0, -0.25, 0.0),
+    ]);
+    let actual = process_with_default_profile(&mut segments.clone());
+    assert_moves(
+        &actual,
+        &[
+            (MoveType::Ski, 0.0),
+            (MoveType::Wait, 60.0),
+            (MoveType::Ski, 240.0),
+        ],
+    );
+}
+
+/// Ski down, pole along an almost flat cat track, then continue skiing. The
+/// traverse candidate that includes the end of the steep part only fails at
+/// the first slow sample, so that sample is detected as waiting.
+fn cat_track() -> Segments {
+    track(&[
+        (60.0, 8.0, -0.25, 0.0),
+        (180.0, 1.0, -0.02, 0.0),
+        (60.0, 8.0, -0.25, 0.0),
+    ])
+}
+
+#[rstest]
+fn cat_track_traverse(_init: Init) {
+    let actual = process_with_default_profile(&mut cat_track());
+    assert_moves(
+        &actual,
+        &[
+            (MoveType::Ski, 0.0),
+            (MoveType::Wait, 60.0),
+            (MoveType::Traverse, 60.0),
+            (MoveType::Ski, 240.0),
+        ],
+    );
+}
+
+#[rstest]
+fn cat_track_traverse_on_piste(_init: Init) {
+    let ski_area = SkiArea::new(
+        create_ski_area_metadata("cat_track".to_string()),
+        HashMap::new(),
+        HashMap::from([(
+            "cat_track".to_string(),
+            piste(
+                "Cat track",
+                vec![line(&[(6.0, 45.0), (6.0, 45.01)])],
+                vec![],
+            ),
+        )]),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap();
+
+    let actual = find_moves(
+        &CancellationToken::new(),
+        &ski_area,
+        &MoveProfile::default(),
+        cat_track(),
+    )
+    .unwrap();
+    let actual: Vec<_> = actual
+        .into_iter()
+        .map(|a| (a.type_, a.begin_time.unwrap()))
+        .collect();
+    let moving = |move_type| {
+        ActivityType::Moving(Moving {
+            move_type,
+            piste_id: "cat_track".to_string(),
+            terrain: Terrain::Piste,
+        })
+    };
+    assert_moves(
+        &actual,
+        &[
+            (moving(MoveType::Ski), 0.0),
+            (moving(MoveType::Wait), 60.0),
+            (moving(MoveType::Traverse), 60.0),
+            (moving(MoveType::Ski), 240.0),
+        ],
+    );
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::process::CandidateFactory;
+use super::simple_candidate::{Constraint, SimpleCandidateFactory};
+use super::MoveType;
+use crate::error::{Error, ErrorType, Result};
+use crate::utils::json::load_from_file;
+
+pub const DEFAULT_PROFILE: &str = "alpine_ski";
+
+const BUILTIN_PROFILES: [(&str, &str); 5] = [
+    ("alpine_ski", include_str!("profiles/alpine_ski.json")),
+    ("snowboard", include_str!("profiles/snowboard.json")),
+    ("ski_touring", include_str!("profiles/ski_touring.json")),
+    ("walking", include_str!("profiles/walking.json")),
+    ("mountain_bike", include_str!("profiles/mountain_bike.json")),
+];
+
+/// Rules for classifying movement. Each move type is recognized when all of
+/// its constraints hold; segments that match no move type become
+/// [`MoveType::Unknown`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MoveProfile {
+    pub move_types: HashMap<MoveType, Vec<Constraint>>,
+}
+
+impl MoveProfile {
+    pub fn builtin_names() -> Vec<&'static str> {
+        BUILTIN_PROFILES.iter().map(|(name, _)| *name).collect()
+    }
+
+    pub fn builtin(name: &str) -> Result<Self> {
+        let (_, data) = BUILTIN_PROFILES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorType::InputError,
+                    format!("unknown move profile: {}", name),
+                )
+            })?;
+        let profile: MoveProfile = serde_json::from_str(data)?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let profile: MoveProfile = load_from_file(path)?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    /// Returns the built-in profile with the given name, or loads it from a
+    /// file if there is no such built-in profile.
+    pub fn find(name_or_path: &str) -> Result<Self> {
+        if BUILTIN_PROFILES.iter().any(|(n, _)| *n == name_or_path) {
+            Self::builtin(name_or_path)
+        } else {
+            Self::load_from_file(name_or_path)
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.move_types.contains_key(&MoveType::Unknown) {
+            return Err(Error::new_s(
+                ErrorType::InputError,
+                "move profile cannot contain rules for Unknown",
+            ));
+        }
+        if let Some((move_type, _)) =
+            self.move_types.iter().find(|(_, c)| c.is_empty())
+        {
+            return Err(Error::new(
+                ErrorType::InputError,
+                format!("no constraints for {:?}", move_type),
+            ));
+        }
+        for (move_type, constraints) in &self.move_types {
+            for constraint in constraints {
+                if let (Some(min), Some(max)) = (constraint.min, constraint.max)
+                {
+                    if min > max {
+                        return Err(Error::new(
+                            ErrorType::InputError,
+                            format!(
+                                "{:?}: min {} is greater than max {}",
+                                move_type, min, max
+                            ),
+                        ));
+                    }
+                }
+                if constraint.limit <= 0.0 {
+                    return Err(Error::new(
+                        ErrorType::InputError,
+                        format!(
+                            "{:?}: limit must be positive, got {}",
+                            move_type, constraint.limit
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn create_candidate_factories(
+        &self,
+    ) -> HashMap<MoveType, Box<dyn CandidateFactory>> {
+        self.move_types
+            .iter()
+            .map(|(move_type, constraints)| {
+                (
+                    *move_type,
+                    SimpleCandidateFactory::new(
+                        *move_type,
+                        constraints.clone(),
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Default for MoveProfile {
+    fn default() -> Self {
+        Self::builtin(DEFAULT_PROFILE).unwrap()
+    }
+}
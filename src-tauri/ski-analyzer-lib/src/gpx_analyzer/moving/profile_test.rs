@@ -0,0 +1,132 @@
+use super::{
+    Constraint, ConstraintLimit, ConstraintType, MoveProfile, MoveType,
+    DEFAULT_PROFILE,
+};
+use crate::error::ErrorType;
+use crate::utils::test_util::{init, Init};
+
+use rstest::rstest;
+
+use std::collections::HashMap;
+use std::fs;
+
+fn write_temp_file(name: &str, content: &str) -> String {
+    let path = std::env::temp_dir().join(format!(
+        "ski-analyzer-{}-{}",
+        std::process::id(),
+        name
+    ));
+    fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[rstest]
+fn builtin_profiles(_init: Init) {
+    for name in MoveProfile::builtin_names() {
+        let profile = MoveProfile::builtin(name).unwrap();
+        assert!(!profile.move_types.is_empty(), "{}", name);
+        assert!(!profile.move_types.contains_key(&MoveType::Unknown));
+    }
+}
+
+#[rstest]
+fn default_profile(_init: Init) {
+    let expected = MoveProfile::builtin(DEFAULT_PROFILE).unwrap();
+    assert_eq!(MoveProfile::default(), expected);
+    assert_eq!(MoveProfile::find(DEFAULT_PROFILE).unwrap(), expected);
+    for move_type in [
+        MoveType::Ski,
+        MoveType::Wait,
+        MoveType::Climb,
+        MoveType::Traverse,
+    ] {
+        assert!(
+            expected.move_types.contains_key(&move_type),
+            "{move_type:?}"
+        );
+    }
+}
+
+#[rstest]
+fn unknown_builtin(_init: Init) {
+    let err = MoveProfile::builtin("curling").unwrap_err();
+    assert_eq!(err.get_type(), ErrorType::InputError);
+}
+
+#[rstest]
+fn load_from_file(_init: Init) {
+    let path = write_temp_file(
+        "profile.json",
+        r#"{
+            "Climb": [{
+                "type": "Inclination",
+                "min": 0.05,
+                "max": null,
+                "limit_type": "Distance",
+                "limit": 20.0
+            }]
+        }"#,
+    );
+    let actual = MoveProfile::find(&path);
+    fs::remove_file(&path).unwrap();
+
+    let expected = MoveProfile {
+        move_types: HashMap::from([(
+            MoveType::Climb,
+            vec![Constraint::new(
+                ConstraintType::Inclination,
+                Some(0.05),
+                None,
+                ConstraintLimit::Distance,
+                20.0,
+            )],
+        )]),
+    };
+    assert_eq!(actual.unwrap(), expected);
+}
+
+#[rstest]
+#[case::unknown_move_type("unknown.json", r#"{ "Unknown": [] }"#)]
+#[case::no_constraints("empty.json", r#"{ "Ski": [] }"#)]
+#[case::min_greater_than_max(
+    "min_max.json",
+    r#"{
+    "Ski": [{
+        "type": "Speed",
+        "min": 2.0,
+        "max": 1.0,
+        "limit_type": "Time",
+        "limit": 10.0
+    }]
+}"#
+)]
+#[case::zero_limit(
+    "zero_limit.json",
+    r#"{
+    "Ski": [{
+        "type": "Speed",
+        "min": 1.0,
+        "max": null,
+        "limit_type": "Time",
+        "limit": 0.0
+    }]
+}"#
+)]
+#[case::negative_limit(
+    "negative_limit.json",
+    r#"{
+    "Ski": [{
+        "type": "Speed",
+        "min": null,
+        "max": 2.0,
+        "limit_type": "Time",
+        "limit": -5.0
+    }]
+}"#
+)]
+fn invalid_file(_init: Init, #[case] name: &str, #[case] content: &str) {
+    let path = write_temp_file(name, content);
+    let actual = MoveProfile::load_from_file(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(actual.unwrap_err().get_type(), ErrorType::InputError);
+}
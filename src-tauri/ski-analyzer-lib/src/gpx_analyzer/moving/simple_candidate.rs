@@ -2,24 +2,27 @@
 
 use geo::{Distance, Haversine};
 use gpx::Waypoint;
+use serde::{Deserialize, Serialize};
 
 use super::process::{Candidate, CandidateFactory};
+use super::MoveType;
 use crate::gpx_analyzer::{get_elevation_diff, get_time_diff};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ConstraintType {
     Speed,
     Inclination,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ConstraintLimit {
     Distance,
     Time,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Constraint {
+    #[serde(rename = "type")]
     pub type_: ConstraintType,
     pub min: Option<f64>,
     pub max: Option<f64>,
@@ -158,7 +161,13 @@ impl Candidate for SimpleCandidate {
 
             match agg.evaluate() {
                 None => has_not_evaluatable_constraint = true,
-                Some(false) => return Some(false),
+                Some(false) => {
+                    eprintln!(
+                        "  fail {:?} {:?} {}",
+                        agg.constraint.type_, agg.value, agg.extent
+                    );
+                    return Some(false);
+                }
                 Some(true) => (),
             };
         }
@@ -172,12 +181,19 @@ impl Candidate for SimpleCandidate {
 }
 
 pub struct SimpleCandidateFactory {
+    move_type: MoveType,
     constraints: Vec<Constraint>,
 }
 
 impl SimpleCandidateFactory {
-    pub fn new(constraints: Vec<Constraint>) -> Box<dyn CandidateFactory> {
-        Box::new(Self { constraints })
+    pub fn new(
+        move_type: MoveType,
+        constraints: Vec<Constraint>,
+    ) -> Box<dyn CandidateFactory> {
+        Box::new(Self {
+            move_type,
+            constraints,
+        })
     }
 }
 
@@ -185,4 +201,8 @@ impl CandidateFactory for SimpleCandidateFactory {
     fn create_candidate(&self) -> Box<dyn Candidate> {
         Box::new(SimpleCandidate::new(self.constraints.clone()))
     }
+
+    fn overrides(&self, other: MoveType) -> bool {
+        self.move_type.overrides(other)
+    }
 }
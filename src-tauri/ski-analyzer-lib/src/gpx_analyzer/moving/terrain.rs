@@ -0,0 +1,216 @@
+use geo::{Distance, Haversine, Intersects, Point};
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use super::{MoveType, Moving, SegmentCoordinate, Segments};
+use crate::error::Result;
+use crate::ski_area::{Difficulty, Piste, SkiArea};
+use crate::utils::cancel::CancellationToken;
+
+/// Waypoints farther than this from a sled run or ski touring route (in
+/// meters) are not on it.
+const MAX_TRAIL_DISTANCE: f64 = 30.0;
+/// The ratio of the waypoints of a move that must be on a sled run or ski
+/// touring route for the move to follow it.
+const MIN_TRAIL_RATIO: f64 = 0.8;
+/// The distance (in meters) that must be covered inside or outside the ski
+/// area before an off-piste move is split, so that GPS noise along the
+/// outline does not cause many short parts.
+const MIN_BACKCOUNTRY_DISTANCE: f64 = 50.0;
+
+/// Where a move happened relative to the pistes of the ski area.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+pub enum Terrain {
+    /// On or near a marked piste.
+    Piste,
+    /// On or near a freeride route.
+    Freeride,
+    /// Descending on a sled run. The piste ID refers to
+    /// [`SkiArea::sled_runs`].
+    SledRun,
+    /// Climbing on a ski touring route. The piste ID refers to
+    /// [`SkiArea::skitour_routes`].
+    SkiTour,
+    /// Riding a mountain bike trail in summer. The piste ID refers to
+    /// [`SkiArea::bike_trails`].
+    BikeTrail,
+    /// Away from pistes, but inside the ski area.
+    OffPiste,
+    /// Outside the ski area.
+    Backcountry,
+}
+
+impl Terrain {
+    pub fn of_piste(piste: &Piste) -> Self {
+        match piste.metadata.difficulty {
+            Difficulty::Freeride => Terrain::Freeride,
+            _ => Terrain::Piste,
+        }
+    }
+}
+
+/// Assigns off-piste descents to the sled runs and off-piste climbs to the
+/// ski touring routes that they follow.
+pub fn find_trails(
+    cancel: &CancellationToken,
+    ski_area: &SkiArea,
+    segments: &Segments,
+    mut input: Vec<(Moving, SegmentCoordinate)>,
+) -> Result<Vec<(Moving, SegmentCoordinate)>> {
+    for i in 0..input.len() {
+        let (moving, begin_coord) = &input[i];
+        let (trails, terrain) = match (moving.terrain, moving.move_type) {
+            (Terrain::OffPiste, MoveType::Ski) => {
+                (ski_area.sled_runs(), Terrain::SledRun)
+            }
+            (Terrain::OffPiste, MoveType::Climb) => {
+                (ski_area.skitour_routes(), Terrain::SkiTour)
+            }
+            _ => continue,
+        };
+        if trails.is_empty() {
+            continue;
+        }
+
+        let end_coord = input
+            .get(i + 1)
+            .map(|m| m.1)
+            .unwrap_or_else(|| segments.end_coord());
+        let mut total = 0;
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, wp) in segments.iter_between(*begin_coord, end_coord) {
+            cancel.check()?;
+            total += 1;
+            for id in trails_near(ski_area, terrain, wp.point()) {
+                *counts.entry(id).or_default() += 1;
+            }
+        }
+
+        let best = counts
+            .into_iter()
+            .max_by(|(id1, c1), (id2, c2)| {
+                c1.cmp(c2).then_with(|| id2.cmp(id1))
+            })
+            .filter(|(_, c)| *c as f64 >= total as f64 * MIN_TRAIL_RATIO);
+        if let Some((id, _)) = best {
+            let moving = &mut input[i].0;
+            moving.piste_id = id.to_string();
+            moving.terrain = terrain;
+        }
+    }
+
+    Ok(input)
+}
+
+/// The IDs of the sled runs or ski touring routes that are at most
+/// [`MAX_TRAIL_DISTANCE`] away from `p`.
+fn trails_near(ski_area: &SkiArea, terrain: Terrain, p: Point) -> Vec<&str> {
+    let index = ski_area.index();
+    let (trails, ids): (_, Vec<_>) = match terrain {
+        Terrain::SledRun => (
+            ski_area.sled_runs(),
+            index.sled_runs_near(p, MAX_TRAIL_DISTANCE).collect(),
+        ),
+        _ => (
+            ski_area.skitour_routes(),
+            index.skitour_routes_near(p, MAX_TRAIL_DISTANCE).collect(),
+        ),
+    };
+    ids.into_iter()
+        .filter_map(|id| {
+            let (id, trail) = trails.get_key_value(id)?;
+            (trail.distance(p)? <= MAX_TRAIL_DISTANCE).then_some(id.as_str())
+        })
+        .collect()
+}
+
+/// Splits off-piste moves into the parts that are inside and outside the
+/// outline of the ski area. A part only starts after at least
+/// [`MIN_BACKCOUNTRY_DISTANCE`] is covered on that side of the outline. Does
+/// nothing if the outline is unknown.
+pub fn find_backcountry(
+    cancel: &CancellationToken,
+    ski_area: &SkiArea,
+    segments: &Segments,
+    input: Vec<(Moving, SegmentCoordinate)>,
+) -> Result<Vec<(Moving, SegmentCoordinate)>> {
+    let outline = &ski_area.metadata.outline.item;
+    if outline.exterior().0.is_empty() {
+        return Ok(input);
+    }
+
+    let mut result = Vec::with_capacity(input.len());
+    for i in 0..input.len() {
+        let (moving, begin_coord) = &input[i];
+        if moving.terrain != Terrain::OffPiste {
+            result.push(input[i].clone());
+            continue;
+        }
+
+        let end_coord = input
+            .get(i + 1)
+            .map(|m| m.1)
+            .unwrap_or_else(|| segments.end_coord());
+        let mut push = |terrain, coord| {
+            result.push((
+                Moving {
+                    terrain,
+                    ..moving.clone()
+                },
+                coord,
+            ));
+        };
+        let mut current: Option<Terrain> = None;
+        // Where the waypoints started to be on the other side of the
+        // outline, and the distance covered there since.
+        let mut change: Option<(SegmentCoordinate, f64)> = None;
+        let mut prev: Option<Point> = None;
+        for (coord, wp) in segments.iter_between(*begin_coord, end_coord) {
+            cancel.check()?;
+            let point = wp.point();
+            let terrain = if outline.intersects(&point) {
+                Terrain::OffPiste
+            } else {
+                Terrain::Backcountry
+            };
+            match current {
+                None => {
+                    push(terrain, *begin_coord);
+                    current = Some(terrain);
+                }
+                Some(c) if c == terrain => change = None,
+                Some(_) => {
+                    let (change_coord, distance) = match (change, prev) {
+                        (Some((c, d)), Some(prev)) => {
+                            (c, d + Haversine::distance(prev, point))
+                        }
+                        _ => (coord, 0.0),
+                    };
+                    if distance >= MIN_BACKCOUNTRY_DISTANCE {
+                        push(terrain, change_coord);
+                        current = Some(terrain);
+                        change = None;
+                    } else {
+                        change = Some((change_coord, distance));
+                    }
+                }
+            }
+            prev = Some(point);
+        }
+    }
+
+    Ok(result)
+}
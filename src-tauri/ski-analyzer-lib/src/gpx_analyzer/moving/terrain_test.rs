@@ -0,0 +1,233 @@
+use super::terrain::{find_backcountry, find_trails};
+use super::{MoveType, Moving, Terrain};
+use crate::gpx_analyzer::test_util::wp;
+use crate::gpx_analyzer::{SegmentCoordinate, Segments};
+use crate::ski_area::{Difficulty, SkiArea};
+use crate::utils::bounded_geometry::BoundedGeometry;
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, line, piste, polygon, trail, Init,
+};
+
+use rstest::rstest;
+use time::OffsetDateTime;
+
+use std::collections::HashMap;
+
+fn ski_area(with_outline: bool) -> SkiArea {
+    let mut metadata = create_ski_area_metadata("terrain".to_string());
+    if with_outline {
+        metadata.outline = BoundedGeometry::new(polygon(&[
+            (6.0, 45.0),
+            (6.01, 45.0),
+            (6.01, 45.01),
+            (6.0, 45.01),
+            (6.0, 45.0),
+        ]))
+        .unwrap();
+    }
+    let piste = piste("p", vec![line(&[(6.0, 45.0), (6.0, 45.01)])], vec![]);
+    SkiArea::new(
+        metadata,
+        HashMap::new(),
+        HashMap::from([("p".to_string(), piste)]),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+fn moving(piste_id: &str, terrain: Terrain) -> Moving {
+    Moving {
+        move_type: MoveType::Ski,
+        piste_id: piste_id.to_string(),
+        terrain,
+    }
+}
+
+fn climb(piste_id: &str, terrain: Terrain) -> Moving {
+    Moving {
+        move_type: MoveType::Climb,
+        ..moving(piste_id, terrain)
+    }
+}
+
+/// A sled run along the first part of the segments and a ski touring route
+/// along the second part.
+fn ski_area_with_trails() -> SkiArea {
+    let mut result = ski_area(true);
+    *result.sled_runs_mut() = HashMap::from([(
+        "s".to_string(),
+        trail("Sled", &[(6.004, 45.005), (6.0125, 45.005)]),
+    )]);
+    *result.skitour_routes_mut() = HashMap::from([(
+        "t".to_string(),
+        trail("Tour", &[(6.0095, 45.006), (6.0075, 45.006)]),
+    )]);
+    result
+}
+
+fn segments() -> Segments {
+    Segments::new(vec![vec![
+        wp(6.005, 45.005, None),
+        wp(6.006, 45.005, None),
+        wp(6.009, 45.005, None),
+        wp(6.011, 45.005, None),
+        wp(6.012, 45.005, None),
+        wp(6.009, 45.006, None),
+        wp(6.008, 45.006, None),
+    ]])
+}
+
+fn run_find_trails(
+    ski_area: &SkiArea,
+    input: Vec<(Moving, SegmentCoordinate)>,
+) -> Vec<(Moving, SegmentCoordinate)> {
+    find_trails(&CancellationToken::new(), ski_area, &segments(), input)
+        .unwrap()
+}
+
+fn run(
+    ski_area: &SkiArea,
+    input: Vec<(Moving, SegmentCoordinate)>,
+) -> Vec<(Moving, SegmentCoordinate)> {
+    find_backcountry(&CancellationToken::new(), ski_area, &segments(), input)
+        .unwrap()
+}
+
+#[rstest]
+fn piste_terrain(_init: Init) {
+    let mut p = piste("p", vec![line(&[(6.0, 45.0), (6.0, 45.01)])], vec![]);
+    assert_eq!(Terrain::of_piste(&p), Terrain::Piste);
+    p.metadata.difficulty = Difficulty::Freeride;
+    assert_eq!(Terrain::of_piste(&p), Terrain::Freeride);
+}
+
+#[rstest]
+fn leave_and_return(_init: Init) {
+    let actual = run(
+        &ski_area(true),
+        vec![(moving("", Terrain::OffPiste), (0, 0))],
+    );
+    let expected = vec![
+        (moving("", Terrain::OffPiste), (0, 0)),
+        (moving("", Terrain::Backcountry), (0, 3)),
+        (moving("", Terrain::OffPiste), (0, 5)),
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+fn only_off_piste_is_split(_init: Init) {
+    let input = vec![
+        (moving("p", Terrain::Piste), (0, 0)),
+        (moving("", Terrain::OffPiste), (0, 2)),
+        (moving("f", Terrain::Freeride), (0, 5)),
+    ];
+    let actual = run(&ski_area(true), input);
+    let expected = vec![
+        (moving("p", Terrain::Piste), (0, 0)),
+        (moving("", Terrain::OffPiste), (0, 2)),
+        (moving("", Terrain::Backcountry), (0, 3)),
+        (moving("f", Terrain::Freeride), (0, 5)),
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+fn start_outside(_init: Init) {
+    let actual = run(
+        &ski_area(true),
+        vec![
+            (moving("p", Terrain::Piste), (0, 0)),
+            (moving("", Terrain::OffPiste), (0, 3)),
+        ],
+    );
+    let expected = vec![
+        (moving("p", Terrain::Piste), (0, 0)),
+        (moving("", Terrain::Backcountry), (0, 3)),
+        (moving("", Terrain::OffPiste), (0, 5)),
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+fn short_excursion_is_ignored(_init: Init) {
+    // Goes about 30 meters outside the outline, then returns.
+    let segments = Segments::new(vec![vec![
+        wp(6.005, 45.005, None),
+        wp(6.0098, 45.005, None),
+        wp(6.0102, 45.005, None),
+        wp(6.0106, 45.005, None),
+        wp(6.0098, 45.005, None),
+        wp(6.005, 45.005, None),
+    ]]);
+    let input = vec![(moving("", Terrain::OffPiste), (0, 0))];
+    let actual = find_backcountry(
+        &CancellationToken::new(),
+        &ski_area(true),
+        &segments,
+        input.clone(),
+    )
+    .unwrap();
+    assert_eq!(actual, input);
+}
+
+#[rstest]
+fn unknown_outline(_init: Init) {
+    let input = vec![(moving("", Terrain::OffPiste), (0, 0))];
+    let actual = run(&ski_area(false), input.clone());
+    assert_eq!(actual, input);
+}
+
+#[rstest]
+fn sled_run_and_skitour(_init: Init) {
+    let actual = run_find_trails(
+        &ski_area_with_trails(),
+        vec![
+            (moving("", Terrain::OffPiste), (0, 0)),
+            (climb("", Terrain::OffPiste), (0, 5)),
+        ],
+    );
+    let expected = vec![
+        (moving("s", Terrain::SledRun), (0, 0)),
+        (climb("t", Terrain::SkiTour), (0, 5)),
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+#[case::mostly_off_trail(vec![(moving("", Terrain::OffPiste), (0, 0))])]
+#[case::wrong_move_type(vec![
+    (climb("", Terrain::OffPiste), (0, 0)),
+    (moving("", Terrain::OffPiste), (0, 5)),
+])]
+#[case::on_piste(vec![(moving("p", Terrain::Piste), (0, 0))])]
+fn trail_not_followed(
+    _init: Init,
+    #[case] input: Vec<(Moving, SegmentCoordinate)>,
+) {
+    let actual = run_find_trails(&ski_area_with_trails(), input.clone());
+    assert_eq!(actual, input);
+}
+
+#[rstest]
+#[case::on_piste(
+    r#"{ "move_type": "Ski", "piste_id": "p" }"#,
+    moving("p", Terrain::Piste)
+)]
+#[case::off_piste(
+    r#"{ "move_type": "Ski", "piste_id": "" }"#,
+    moving("", Terrain::OffPiste)
+)]
+#[case::explicit(
+    r#"{ "move_type": "Climb", "piste_id": "t", "terrain": "SkiTour" }"#,
+    climb("t", Terrain::SkiTour)
+)]
+fn deserialize_terrain(
+    _init: Init,
+    #[case] json: &str,
+    #[case] expected: Moving,
+) {
+    let actual: Moving = serde_json::from_str(json).unwrap();
+    assert_eq!(actual, expected);
+}
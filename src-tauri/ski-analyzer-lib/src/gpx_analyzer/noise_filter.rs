@@ -0,0 +1,288 @@
+use geo::{Destination, Distance, Haversine, Point};
+use gpx::Waypoint;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::{get_speed, get_time_diff, to_odt, Segment};
+use crate::utils::option_time_ser;
+use crate::utils::track::SensorData;
+
+#[cfg(feature = "specta")]
+use crate::typescript_gen::geo::PointDef;
+
+/// The number of points averaged by [`Smoothing::MovingAverage`].
+const MOVING_AVERAGE_WINDOW: usize = 5;
+/// Standard deviation of the acceleration assumed by [`Smoothing::Kalman`],
+/// in m/s^2.
+const KALMAN_ACCELERATION: f64 = 3.0;
+/// Measurement error used by [`Smoothing::Kalman`] when the waypoint has no
+/// precision information, in meters.
+const KALMAN_DEFAULT_PRECISION: f64 = 5.0;
+/// User equivalent range error: the measurement error in meters that a
+/// dilution of precision of 1 corresponds to.
+const UERE: f64 = 5.0;
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+pub enum Smoothing {
+    #[default]
+    None,
+    MovingAverage,
+    Kalman,
+}
+
+/// Preprocessing of the recorded waypoints before they are analyzed. The
+/// default leaves every waypoint unchanged.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseFilter {
+    /// Remove single points that would require a speed higher than this
+    /// (in m/s) to reach and to leave.
+    pub max_speed: Option<f64>,
+    /// Keep only the first of consecutive points with the same time.
+    pub collapse_duplicates: bool,
+    pub smoothing: Smoothing,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RemovalReason {
+    /// The precision of the point is too low. The segment is split here.
+    BadPrecision,
+    Duplicate,
+    Outlier,
+    /// Another recording already covers the time of the point.
+    Overlapping,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemovedPoint {
+    #[cfg_attr(feature = "specta", specta(type = PointDef))]
+    pub point: Point,
+    #[serde(with = "option_time_ser")]
+    pub time: Option<OffsetDateTime>,
+    pub reason: RemovalReason,
+}
+
+impl RemovedPoint {
+    pub fn new(waypoint: &Waypoint, reason: RemovalReason) -> Self {
+        RemovedPoint {
+            point: waypoint.point(),
+            time: to_odt(waypoint.time),
+            reason,
+        }
+    }
+}
+
+impl NoiseFilter {
+    /// Filters a segment, adding the removed points to `removed`.
+    /// `sensor_data` belongs to the points of `segment`. It may be empty if
+    /// there is no sensor data.
+    pub fn filter(
+        &self,
+        segment: Segment,
+        sensor_data: Vec<SensorData>,
+        removed: &mut Vec<RemovedPoint>,
+    ) -> Segment {
+        let sensor_data = sensor_data
+            .into_iter()
+            .chain(std::iter::repeat(Default::default()));
+        let mut result: Vec<(Waypoint, SensorData)> =
+            segment.into_iter().zip(sensor_data).collect();
+        if self.collapse_duplicates {
+            result = collapse_duplicates(result, removed);
+        }
+        if let Some(max_speed) = self.max_speed {
+            result = remove_outliers(result, max_speed, removed);
+        }
+        match self.smoothing {
+            Smoothing::None => (),
+            Smoothing::MovingAverage => moving_average(&mut result),
+            Smoothing::Kalman => kalman(&mut result),
+        }
+        result.into_iter().map(|(wp, _)| wp).collect()
+    }
+}
+
+fn collapse_duplicates(
+    segment: Vec<(Waypoint, SensorData)>,
+    removed: &mut Vec<RemovedPoint>,
+) -> Vec<(Waypoint, SensorData)> {
+    let mut result: Vec<(Waypoint, SensorData)> =
+        Vec::with_capacity(segment.len());
+    for (wp, data) in segment {
+        let is_duplicate = result.last().is_some_and(|(last, _)| {
+            wp.time.is_some()
+                && get_time_diff(last, &wp).is_some_and(|dt| dt.is_zero())
+        });
+        if is_duplicate {
+            removed.push(RemovedPoint::new(&wp, RemovalReason::Duplicate));
+        } else {
+            result.push((wp, data));
+        }
+    }
+    result
+}
+
+/// Removes spikes: points that are too far from both the previous and the
+/// next point, while those two are close to each other.
+fn remove_outliers(
+    segment: Vec<(Waypoint, SensorData)>,
+    max_speed: f64,
+    removed: &mut Vec<RemovedPoint>,
+) -> Vec<(Waypoint, SensorData)> {
+    let too_fast = |wp1: &Waypoint, wp2: &Waypoint| {
+        get_speed(wp1, wp2).is_some_and(|s| s > max_speed)
+    };
+    let mut result: Vec<(Waypoint, SensorData)> =
+        Vec::with_capacity(segment.len());
+    let mut it = segment.into_iter().peekable();
+    while let Some((wp, data)) = it.next() {
+        let is_outlier = match (result.last(), it.peek()) {
+            (Some((prev, _)), Some((next, _))) => {
+                too_fast(prev, &wp)
+                    && too_fast(&wp, next)
+                    && !too_fast(prev, next)
+            }
+            _ => false,
+        };
+        if is_outlier {
+            removed.push(RemovedPoint::new(&wp, RemovalReason::Outlier));
+        } else {
+            result.push((wp, data));
+        }
+    }
+    result
+}
+
+/// The position of a waypoint can only be set in the constructor.
+#[allow(deprecated)]
+fn move_waypoint(wp: &mut Waypoint, point: Point) {
+    let old = std::mem::replace(wp, Waypoint::new(point));
+    wp.elevation = old.elevation;
+    wp.speed = old.speed;
+    wp.time = old.time;
+    wp.name = old.name;
+    wp.comment = old.comment;
+    wp.description = old.description;
+    wp.source = old.source;
+    wp.links = old.links;
+    wp.symbol = old.symbol;
+    wp.type_ = old.type_;
+    wp.geoidheight = old.geoidheight;
+    wp.fix = old.fix;
+    wp.sat = old.sat;
+    wp.hdop = old.hdop;
+    wp.vdop = old.vdop;
+    wp.pdop = old.pdop;
+    wp.age = old.age;
+    wp.dgps_age = old.dgps_age;
+    wp.dgpsid = old.dgpsid;
+}
+
+/// Replaces each position with the average of the positions around it.
+fn moving_average(segment: &mut [(Waypoint, SensorData)]) {
+    let half = MOVING_AVERAGE_WINDOW / 2;
+    let points: Vec<Point> = segment.iter().map(|(wp, _)| wp.point()).collect();
+    for (i, (wp, _)) in segment.iter_mut().enumerate() {
+        let window =
+            &points[i.saturating_sub(half)..(i + half + 1).min(points.len())];
+        let n = window.len() as f64;
+        let sum = window.iter().fold(Point::new(0.0, 0.0), |a, p| a + *p);
+        move_waypoint(wp, Point::new(sum.x() / n, sum.y() / n));
+    }
+}
+
+/// A constant velocity Kalman filter for one axis.
+struct Kalman1D {
+    position: f64,
+    velocity: f64,
+    // Covariance matrix.
+    p: [[f64; 2]; 2],
+}
+
+impl Kalman1D {
+    fn new(position: f64, variance: f64) -> Self {
+        Kalman1D {
+            position,
+            velocity: 0.0,
+            p: [[variance, 0.0], [0.0, variance]],
+        }
+    }
+
+    fn update(&mut self, dt: f64, measurement: f64, variance: f64) -> f64 {
+        // Predict
+        self.position += self.velocity * dt;
+        let q = KALMAN_ACCELERATION * KALMAN_ACCELERATION;
+        let [[p00, p01], [p10, p11]] = self.p;
+        let p00 = p00 + dt * (p10 + p01) + dt * dt * p11 + q * dt.powi(4) / 4.0;
+        let p01 = p01 + dt * p11 + q * dt.powi(3) / 2.0;
+        let p10 = p10 + dt * p11 + q * dt.powi(3) / 2.0;
+        let p11 = p11 + q * dt * dt;
+
+        // Correct
+        let s = p00 + variance;
+        let k0 = p00 / s;
+        let k1 = p10 / s;
+        let residual = measurement - self.position;
+        self.position += k0 * residual;
+        self.velocity += k1 * residual;
+        self.p = [
+            [(1.0 - k0) * p00, (1.0 - k0) * p01],
+            [p10 - k1 * p00, p11 - k1 * p01],
+        ];
+        self.position
+    }
+}
+
+/// The variance of the measured position in square meters. The accuracy
+/// reported by the device is used if known, otherwise the error is estimated
+/// from the dilution of precision.
+fn variance(wp: &Waypoint, data: &SensorData) -> f64 {
+    data.accuracy
+        .or_else(|| wp.hdop.map(|hdop| hdop * UERE))
+        .unwrap_or(KALMAN_DEFAULT_PRECISION)
+        .powi(2)
+}
+
+/// Smooths the positions with a Kalman filter. Positions are converted to
+/// meters relative to the first point, which is accurate enough for the size
+/// of a ski area.
+fn kalman(segment: &mut [(Waypoint, SensorData)]) {
+    let Some(origin) = segment.first().map(|(wp, _)| wp.point()) else {
+        return;
+    };
+    let to_meters = |p: Point| {
+        let x = Haversine::distance(origin, Point::new(p.x(), origin.y()));
+        let y = Haversine::distance(origin, Point::new(origin.x(), p.y()));
+        (
+            if p.x() < origin.x() { -x } else { x },
+            if p.y() < origin.y() { -y } else { y },
+        )
+    };
+    let v = variance(&segment[0].0, &segment[0].1);
+    let mut filters = (Kalman1D::new(0.0, v), Kalman1D::new(0.0, v));
+    for i in 1..segment.len() {
+        let dt = get_time_diff(&segment[i - 1].0, &segment[i].0)
+            .map_or(1.0, |dt| dt.as_seconds_f64().max(0.0));
+        let (x, y) = to_meters(segment[i].0.point());
+        let v = variance(&segment[i].0, &segment[i].1);
+        let x = filters.0.update(dt, x, v);
+        let y = filters.1.update(dt, y, v);
+        let p = Haversine::destination(origin, 90.0, x);
+        let p = Haversine::destination(p, 0.0, y);
+        move_waypoint(&mut segment[i].0, p);
+    }
+}
@@ -0,0 +1,206 @@
+use super::test_util::time;
+use super::{NoiseFilter, RemovalReason, Segment, Smoothing};
+use crate::utils::test_util::{init, Init};
+use crate::utils::track::SensorData;
+
+use geo::{point, Distance, Haversine};
+use gpx::Waypoint;
+use rstest::rstest;
+
+/// Creates waypoints from (x, y, time) tuples.
+fn segment(points: &[(f64, f64, f64)]) -> Segment {
+    points
+        .iter()
+        .map(|(x, y, t)| {
+            let mut wp = Waypoint::new(point! { x: *x, y: *y });
+            wp.time = Some(time(*t).into());
+            wp
+        })
+        .collect()
+}
+
+fn coords(segment: &Segment) -> Vec<(f64, f64)> {
+    segment.iter().map(|wp| wp.point().x_y()).collect()
+}
+
+/// A straight line to the north with 10 m/s and a zig-zag error of 5 m.
+fn jittery_line() -> Segment {
+    let step = 0.00009;
+    let jitter = 0.00006;
+    segment(
+        &(0..40)
+            .map(|i| {
+                let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+                (6.0 + sign * jitter, 45.0 + step * i as f64, i as f64)
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn outlier_filter() -> NoiseFilter {
+    NoiseFilter {
+        max_speed: Some(60.0),
+        ..NoiseFilter::default()
+    }
+}
+
+fn error_from_line(segment: &Segment) -> f64 {
+    segment
+        .iter()
+        .map(|wp| {
+            let p = wp.point();
+            Haversine::distance(p, point! { x: 6.0, y: p.y() })
+        })
+        .sum::<f64>()
+        / segment.len() as f64
+}
+
+#[rstest]
+fn collapse_duplicates(_init: Init) {
+    let filter = NoiseFilter {
+        collapse_duplicates: true,
+        ..NoiseFilter::default()
+    };
+    let mut removed = Vec::new();
+    let actual = filter.filter(
+        segment(&[
+            (6.0, 45.0, 0.0),
+            (6.0, 45.0001, 0.0),
+            (6.0, 45.0002, 1.0),
+            (6.0, 45.0003, 1.0),
+            (6.0, 45.0004, 1.0),
+            (6.0, 45.0005, 2.0),
+        ]),
+        Vec::new(),
+        &mut removed,
+    );
+    assert_eq!(
+        coords(&actual),
+        vec![(6.0, 45.0), (6.0, 45.0002), (6.0, 45.0005)]
+    );
+    assert_eq!(removed.len(), 3);
+    assert!(removed.iter().all(|r| r.reason == RemovalReason::Duplicate));
+    assert_eq!(removed[0].point.x_y(), (6.0, 45.0001));
+    assert_eq!(removed[0].time, Some(time(0.0)));
+}
+
+#[rstest]
+fn remove_spike(_init: Init) {
+    let mut removed = Vec::new();
+    let actual = outlier_filter().filter(
+        segment(&[
+            (6.0, 45.0, 0.0),
+            (6.0, 45.0001, 1.0),
+            // About 1 km away from the others.
+            (6.01, 45.0055, 2.0),
+            (6.0, 45.0002, 3.0),
+            (6.0, 45.0003, 4.0),
+        ]),
+        Vec::new(),
+        &mut removed,
+    );
+    assert_eq!(
+        coords(&actual),
+        vec![(6.0, 45.0), (6.0, 45.0001), (6.0, 45.0002), (6.0, 45.0003)]
+    );
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].reason, RemovalReason::Outlier);
+    assert_eq!(removed[0].point.x_y(), (6.01, 45.0055));
+}
+
+#[rstest]
+fn keep_sustained_jump(_init: Init) {
+    // A gap in the recording: every point after the jump is far away, so
+    // none of them is an outlier.
+    let input = segment(&[
+        (6.0, 45.0, 0.0),
+        (6.0, 45.0001, 1.0),
+        (6.01, 45.0055, 2.0),
+        (6.01, 45.0056, 3.0),
+        (6.01, 45.0057, 4.0),
+    ]);
+    let mut removed = Vec::new();
+    let actual =
+        outlier_filter().filter(input.clone(), Vec::new(), &mut removed);
+    assert_eq!(coords(&actual), coords(&input));
+    assert!(removed.is_empty());
+}
+
+#[rstest]
+fn default_keeps_all_points(_init: Init) {
+    let input = segment(&[
+        (6.0, 45.0, 0.0),
+        (6.0, 45.0001, 0.0),
+        (6.01, 45.0055, 1.0),
+        (6.0, 45.0002, 2.0),
+    ]);
+    let mut removed = Vec::new();
+    let actual =
+        NoiseFilter::default().filter(input.clone(), Vec::new(), &mut removed);
+    assert_eq!(actual, input);
+    assert!(removed.is_empty());
+}
+
+#[rstest]
+#[case::moving_average(Smoothing::MovingAverage)]
+#[case::kalman(Smoothing::Kalman)]
+fn smooth(_init: Init, #[case] smoothing: Smoothing) {
+    let input = jittery_line();
+    let filter = NoiseFilter {
+        smoothing,
+        ..NoiseFilter::default()
+    };
+    let mut removed = Vec::new();
+    let actual = filter.filter(input.clone(), Vec::new(), &mut removed);
+    assert!(removed.is_empty());
+    assert_eq!(actual.len(), input.len());
+    for (a, i) in actual.iter().zip(input.iter()) {
+        assert_eq!(a.time, i.time);
+    }
+
+    let before = error_from_line(&input);
+    let after = error_from_line(&actual);
+    assert!(after < before / 2.0, "before={} after={}", before, after);
+    // The smoothed route should still go along the line.
+    let last = actual.last().unwrap().point();
+    assert!((last.y() - input.last().unwrap().point().y()).abs() < 0.0002);
+}
+
+/// Error of the smoothed position of a point that is off the line by about
+/// 40 m, in meters.
+fn kalman_error(hdop: Option<f64>, accuracy: Option<f64>) -> f64 {
+    let mut input = segment(
+        &(0..20)
+            .map(|i| (6.0, 45.0 + 0.00009 * i as f64, i as f64))
+            .collect::<Vec<_>>(),
+    );
+    input[10] = segment(&[(6.0005, 45.0009, 10.0)]).remove(0);
+    input[10].hdop = hdop;
+    let sensor_data = (0..input.len())
+        .map(|i| SensorData {
+            accuracy: if i == 10 { accuracy } else { Some(3.0) },
+            ..Default::default()
+        })
+        .collect();
+    let filter = NoiseFilter {
+        smoothing: Smoothing::Kalman,
+        ..NoiseFilter::default()
+    };
+    let actual = filter.filter(input, sensor_data, &mut Vec::new());
+    let p = actual[10].point();
+    Haversine::distance(p, point! { x: 6.0, y: p.y() })
+}
+
+#[rstest]
+fn kalman_precision(_init: Init) {
+    let accurate = kalman_error(None, Some(3.0));
+    // The accuracy is in meters.
+    let inaccurate = kalman_error(None, Some(50.0));
+    // HDOP has no unit, 10 is about 50 m.
+    let bad_hdop = kalman_error(Some(10.0), None);
+    assert!(inaccurate < accurate / 2.0, "{} {}", accurate, inaccurate);
+    assert!(bad_hdop < accurate / 2.0, "{} {}", accurate, bad_hdop);
+    // Without accuracy, a small HDOP is a small error.
+    let good_hdop = kalman_error(Some(1.0), None);
+    assert!(good_hdop > bad_hdop * 2.0, "{} {}", good_hdop, bad_hdop);
+}
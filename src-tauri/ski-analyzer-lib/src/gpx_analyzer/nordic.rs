@@ -0,0 +1,107 @@
+use geo::Point;
+
+use std::collections::HashMap;
+
+use super::{Activity, ActivityType, SegmentCoordinate, Segments};
+use crate::error::Result;
+use crate::ski_area::SkiArea;
+use crate::utils::cancel::CancellationToken;
+
+/// Waypoints farther than this from a trail (in meters) are not on it.
+const MAX_DISTANCE: f64 = 30.0;
+
+/// Consecutive waypoints that are all close to the same trails.
+struct Run<'s> {
+    begin: SegmentCoordinate,
+    /// The sum of the distances of the waypoints from each trail. Empty when
+    /// not on a trail.
+    trails: HashMap<&'s str, f64>,
+}
+
+impl<'s> Run<'s> {
+    /// The trail that the run follows most closely. Ties are broken by the
+    /// ID to make the result deterministic.
+    fn best_trail(&self) -> &'s str {
+        self.trails
+            .iter()
+            .min_by(|(id1, d1), (id2, d2)| {
+                d1.total_cmp(d2).then_with(|| id1.cmp(id2))
+            })
+            .map_or("", |(id, _)| *id)
+    }
+}
+
+fn trails_near(ski_area: &SkiArea, p: Point) -> HashMap<&str, f64> {
+    ski_area
+        .index()
+        .nordic_trails_near(p, MAX_DISTANCE)
+        .filter_map(|id| {
+            let (id, trail) = ski_area.nordic_trails().get_key_value(id)?;
+            Some((id.as_str(), trail.distance(p)?))
+        })
+        .filter(|(_, d)| *d <= MAX_DISTANCE)
+        .collect()
+}
+
+/// Finds the trails that the route follows. Trails often share parts, so
+/// each part of the route is assigned to the trail that it stays close to
+/// for the longest time.
+fn find_runs<'s>(
+    cancel: &CancellationToken,
+    ski_area: &'s SkiArea,
+    segments: &Segments,
+) -> Result<Vec<(&'s str, SegmentCoordinate)>> {
+    let mut result: Vec<(&str, SegmentCoordinate)> = Vec::new();
+    let mut add = |run: Run<'s>| {
+        let trail = run.best_trail();
+        if !matches!(result.last(), Some((t, _)) if *t == trail) {
+            result.push((trail, run.begin));
+        }
+    };
+
+    let mut current: Option<Run> = None;
+    for (coord, wp) in segments {
+        cancel.check()?;
+        let trails = trails_near(ski_area, wp.point());
+        let continues = current.as_ref().is_some_and(|run| {
+            if run.trails.is_empty() {
+                trails.is_empty()
+            } else {
+                run.trails.keys().any(|id| trails.contains_key(id))
+            }
+        });
+        if continues {
+            let run = current.as_mut().unwrap();
+            run.trails.retain(|id, _| trails.contains_key(id));
+            for (id, distance) in run.trails.iter_mut() {
+                *distance += trails[id];
+            }
+        } else {
+            if let Some(run) = current.take() {
+                add(run);
+            }
+            current = Some(Run {
+                begin: coord,
+                trails,
+            });
+        }
+    }
+    if let Some(run) = current {
+        add(run);
+    }
+    Ok(result)
+}
+
+/// Splits the route into parts along nordic trails.
+pub fn find_trails(
+    cancel: &CancellationToken,
+    ski_area: &SkiArea,
+    mut segments: Segments,
+) -> Result<Vec<Activity>> {
+    let runs = find_runs(cancel, ski_area, &segments)?;
+    Ok(segments.commit(None, |_segments| {
+        runs.into_iter().map(|(trail, coord)| {
+            (ActivityType::Trail(trail.to_string()), coord)
+        })
+    }))
+}
@@ -0,0 +1,100 @@
+use super::{
+    analyze_route, ActivityType, AnalysisMode, MoveProfile, NoiseFilter,
+};
+use crate::ski_area::{SkiArea, Trails};
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, make_gpx, segment, trail, Coord, Init,
+};
+
+use rstest::{fixture, rstest};
+use time::OffsetDateTime;
+
+use std::collections::HashMap;
+
+/// Two trails that share their first kilometer. Trail "a" continues east,
+/// trail "b" turns north.
+#[fixture]
+fn ski_area() -> SkiArea {
+    SkiArea::with_trails(
+        create_ski_area_metadata("nordic".to_string()),
+        HashMap::new(),
+        HashMap::new(),
+        Trails {
+            nordic: HashMap::from([
+                (
+                    "a".to_string(),
+                    trail("A", &[(6.0, 45.0), (6.01, 45.0), (6.02, 45.0)]),
+                ),
+                (
+                    "b".to_string(),
+                    trail("B", &[(6.0, 45.0), (6.01, 45.0), (6.01, 45.01)]),
+                ),
+            ]),
+            ..Default::default()
+        },
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+fn get_trails(ski_area: &SkiArea, points: &[Coord]) -> Vec<(String, usize)> {
+    let route = analyze_route(
+        &CancellationToken::new(),
+        ski_area,
+        AnalysisMode::Nordic,
+        &MoveProfile::default(),
+        &NoiseFilter::default(),
+        make_gpx(vec![segment(points)]).into(),
+    )
+    .unwrap();
+    route
+        .route
+        .item
+        .into_iter()
+        .map(|a| match a.type_ {
+            ActivityType::Trail(id) => {
+                (id, a.route.0.iter().map(|s| s.len()).sum())
+            }
+            t => panic!("Unexpected activity: {:?}", t),
+        })
+        .collect()
+}
+
+#[rstest]
+fn shared_part(_init: Init, ski_area: SkiArea) {
+    let actual = get_trails(
+        &ski_area,
+        &[
+            (6.0, 45.0),
+            (6.005, 45.0),
+            (6.01, 45.0),
+            (6.01, 45.005),
+            (6.01, 45.01),
+        ],
+    );
+    assert_eq!(actual, vec![("b".to_string(), 5)]);
+}
+
+#[rstest]
+fn off_trail(_init: Init, ski_area: SkiArea) {
+    let actual = get_trails(
+        &ski_area,
+        &[
+            (6.005, 45.0),
+            (6.015, 45.0),
+            (6.02, 45.0),
+            (6.03, 45.0),
+            (6.03, 45.005),
+            (6.02, 45.0001),
+        ],
+    );
+    assert_eq!(
+        actual,
+        vec![
+            ("a".to_string(), 4),
+            (String::new(), 3),
+            ("a".to_string(), 1),
+        ]
+    );
+}
@@ -0,0 +1,495 @@
+use serde::{Deserialize, Serialize};
+use time::{Date, Duration, OffsetDateTime, UtcOffset};
+
+use super::{
+    get_elevation_diff, get_speed, Activity, ActivityType, AnalyzedRoute,
+    MoveType, Terrain,
+};
+use crate::ski_area::{Difficulty, SkiArea};
+use crate::utils::option_time_ser;
+
+/// Durations are in seconds, speeds in m/s, distances and elevations in
+/// meters.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct MotionStatistics {
+    pub distance: f64,
+    pub duration: Option<f64>,
+    pub ascent: f64,
+    pub descent: f64,
+    pub top_speed: Option<f64>,
+    pub average_speed: Option<f64>,
+}
+
+fn get_duration(activity: &Activity) -> Option<f64> {
+    Some((activity.end_time? - activity.begin_time?).as_seconds_f64())
+}
+
+impl MotionStatistics {
+    fn add(&mut self, activity: &Activity) {
+        self.distance += activity.length;
+        if let Some(duration) = get_duration(activity) {
+            *self.duration.get_or_insert(0.0) += duration;
+        }
+
+        for wps in activity.route.0.iter().flat_map(|s| s.windows(2)) {
+            if let Some(diff) = get_elevation_diff(&wps[0], &wps[1]) {
+                if diff > 0.0 {
+                    self.ascent += diff;
+                } else {
+                    self.descent -= diff;
+                }
+            }
+            if let Some(speed) = get_speed(&wps[0], &wps[1]) {
+                self.top_speed =
+                    Some(self.top_speed.map_or(speed, |s| s.max(speed)));
+            }
+        }
+
+        self.average_speed = self
+            .duration
+            .filter(|d| *d > 0.0)
+            .map(|d| self.distance / d);
+    }
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct MoveStatistics {
+    /// Index of the activity in the route.
+    pub activity: usize,
+    pub move_type: MoveType,
+    pub piste_id: String,
+    pub difficulty: Difficulty,
+    pub terrain: Terrain,
+    #[serde(flatten)]
+    pub motion: MotionStatistics,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct LiftRideStatistics {
+    /// Index of the activity in the route.
+    pub activity: usize,
+    pub lift_id: String,
+    /// The actual ride time.
+    pub duration: Option<f64>,
+    /// The ride time according to the lift data, if known.
+    pub nominal_duration: Option<f64>,
+}
+
+/// Cross-country skiing on one nordic trail or riding one bike trail, summed
+/// over every part of the route that follows it.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct TrailStatistics {
+    pub trail_id: String,
+    #[serde(flatten)]
+    pub motion: MotionStatistics,
+    /// In seconds per kilometer.
+    pub pace: Option<f64>,
+    /// The number of times the trail was entered. Following the trail without
+    /// leaving it or taking a lift counts as one lap.
+    pub laps: usize,
+}
+
+/// A run is everything between two lift rides that contains skiing.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct RunStatistics {
+    /// Index of the first activity of the run in the route.
+    pub first_activity: usize,
+    /// Index of the last activity of the run in the route.
+    pub last_activity: usize,
+    #[serde(with = "option_time_ser")]
+    pub begin_time: Option<OffsetDateTime>,
+    #[serde(with = "option_time_ser")]
+    pub end_time: Option<OffsetDateTime>,
+    #[serde(flatten)]
+    pub motion: MotionStatistics,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DifficultyStatistics {
+    pub difficulty: Difficulty,
+    pub distance: f64,
+    pub descent: f64,
+    pub duration: Option<f64>,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct TerrainStatistics {
+    pub terrain: Terrain,
+    pub distance: f64,
+    pub descent: f64,
+    pub duration: Option<f64>,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct RouteStatistics {
+    #[serde(with = "option_time_ser")]
+    pub begin_time: Option<OffsetDateTime>,
+    #[serde(with = "option_time_ser")]
+    pub end_time: Option<OffsetDateTime>,
+    /// The whole route, including lifts.
+    pub total: MotionStatistics,
+    /// Only skiing.
+    pub ski: MotionStatistics,
+    pub run_count: usize,
+    pub lift_count: usize,
+    pub lift_time: f64,
+    pub lift_distance: f64,
+    pub lift_ascent: f64,
+    /// Waiting in place and queuing for lifts.
+    pub wait_time: f64,
+    pub runs: Vec<RunStatistics>,
+    pub moves: Vec<MoveStatistics>,
+    pub lift_rides: Vec<LiftRideStatistics>,
+    /// Skiing per nordic trail or riding per bike trail, in the order they
+    /// were first used.
+    pub trails: Vec<TrailStatistics>,
+    /// Skiing per difficulty, ordered from the easiest.
+    pub difficulties: Vec<DifficultyStatistics>,
+    /// Skiing per terrain, ordered from on-piste to backcountry.
+    pub terrains: Vec<TerrainStatistics>,
+}
+
+const DIFFICULTIES: &[Difficulty] = &[
+    Difficulty::Novice,
+    Difficulty::Easy,
+    Difficulty::Intermediate,
+    Difficulty::Advanced,
+    Difficulty::Expert,
+    Difficulty::Freeride,
+    Difficulty::Unknown,
+];
+
+const TERRAINS: &[Terrain] = &[
+    Terrain::Piste,
+    Terrain::Freeride,
+    Terrain::SledRun,
+    Terrain::SkiTour,
+    Terrain::BikeTrail,
+    Terrain::OffPiste,
+    Terrain::Backcountry,
+];
+
+/// A longer pause in the recording ends the current run even without a lift
+/// ride, e.g. at the end of a day.
+const MAX_RUN_GAP: Duration = Duration::hours(1);
+
+struct RunBuilder {
+    run: RunStatistics,
+    has_ski: bool,
+}
+
+impl RunBuilder {
+    fn new(index: usize, activity: &Activity) -> Self {
+        RunBuilder {
+            run: RunStatistics {
+                first_activity: index,
+                last_activity: index,
+                begin_time: activity.begin_time,
+                end_time: activity.end_time,
+                motion: MotionStatistics::default(),
+            },
+            has_ski: false,
+        }
+    }
+
+    fn add(&mut self, index: usize, activity: &Activity) {
+        self.run.last_activity = index;
+        if activity.end_time.is_some() {
+            self.run.end_time = activity.end_time;
+        }
+        self.run.motion.add(activity);
+    }
+}
+
+impl RouteStatistics {
+    pub fn calculate(ski_area: &SkiArea, route: &AnalyzedRoute) -> Self {
+        Self::calculate_part(ski_area, &route.route.item, 0)
+    }
+
+    /// Activity indices in the result are offset by `first_index`.
+    fn calculate_part(
+        ski_area: &SkiArea,
+        activities: &[Activity],
+        first_index: usize,
+    ) -> Self {
+        let mut result = RouteStatistics {
+            begin_time: activities.iter().find_map(|a| a.begin_time),
+            end_time: activities.iter().rev().find_map(|a| a.end_time),
+            ..Default::default()
+        };
+        let mut difficulties: Vec<DifficultyStatistics> = DIFFICULTIES
+            .iter()
+            .map(|d| DifficultyStatistics {
+                difficulty: *d,
+                distance: 0.0,
+                descent: 0.0,
+                duration: None,
+            })
+            .collect();
+        let mut terrains: Vec<TerrainStatistics> = TERRAINS
+            .iter()
+            .map(|t| TerrainStatistics {
+                terrain: *t,
+                distance: 0.0,
+                descent: 0.0,
+                duration: None,
+            })
+            .collect();
+        let mut run: Option<RunBuilder> = None;
+        let mut last_trail: Option<&str> = None;
+
+        for (i, activity) in activities.iter().enumerate() {
+            let i = i + first_index;
+            result.total.add(activity);
+            match &activity.type_ {
+                ActivityType::UseLift(use_lift) => {
+                    result.lift_count += 1;
+                    let mut lift = MotionStatistics::default();
+                    lift.add(activity);
+                    result.lift_time += lift.duration.unwrap_or(0.0);
+                    result.lift_distance += lift.distance;
+                    result.lift_ascent += lift.ascent;
+                    result.lift_rides.push(LiftRideStatistics {
+                        activity: i,
+                        lift_id: use_lift.lift_id.clone(),
+                        duration: lift.duration,
+                        nominal_duration: ski_area
+                            .lifts()
+                            .get(&use_lift.lift_id)
+                            .and_then(|l| {
+                                l.nominal_duration(
+                                    use_lift.begin_station,
+                                    use_lift.end_station,
+                                )
+                            }),
+                    });
+                    result.finish_run(run.take());
+                    last_trail = None;
+                }
+                ActivityType::EnterLift(_) => {
+                    result.wait_time += get_duration(activity).unwrap_or(0.0);
+                }
+                ActivityType::Moving(moving) => {
+                    let mut motion = MotionStatistics::default();
+                    motion.add(activity);
+                    let difficulty = moving
+                        .piste(ski_area)
+                        .map(|p| &p.metadata)
+                        .or_else(|| moving.trail(ski_area).map(|t| &t.metadata))
+                        .map_or(Difficulty::Unknown, |m| m.difficulty);
+
+                    match moving.move_type {
+                        MoveType::Ski => {
+                            result.ski.add(activity);
+                            let d = difficulties
+                                .iter_mut()
+                                .find(|d| d.difficulty == difficulty)
+                                .unwrap();
+                            d.distance += motion.distance;
+                            d.descent += motion.descent;
+                            if let Some(duration) = motion.duration {
+                                *d.duration.get_or_insert(0.0) += duration;
+                            }
+                            let t = terrains
+                                .iter_mut()
+                                .find(|t| t.terrain == moving.terrain)
+                                .unwrap();
+                            t.distance += motion.distance;
+                            t.descent += motion.descent;
+                            if let Some(duration) = motion.duration {
+                                *t.duration.get_or_insert(0.0) += duration;
+                            }
+                            if moving.terrain == Terrain::BikeTrail {
+                                result.add_trail(
+                                    &moving.piste_id,
+                                    activity,
+                                    &mut last_trail,
+                                );
+                            } else {
+                                last_trail = None;
+                            }
+                        }
+                        MoveType::Wait => {
+                            result.wait_time += motion.duration.unwrap_or(0.0);
+                        }
+                        _ => (),
+                    }
+
+                    let is_gap = run
+                        .as_ref()
+                        .and_then(|r| {
+                            Some(activity.begin_time? - r.run.end_time?)
+                        })
+                        .is_some_and(|gap| gap > MAX_RUN_GAP);
+                    if is_gap {
+                        result.finish_run(run.take());
+                    }
+                    let builder =
+                        run.get_or_insert_with(|| RunBuilder::new(i, activity));
+                    builder.add(i, activity);
+                    builder.has_ski |= moving.move_type == MoveType::Ski;
+
+                    result.moves.push(MoveStatistics {
+                        activity: i,
+                        move_type: moving.move_type,
+                        piste_id: moving.piste_id.clone(),
+                        difficulty,
+                        terrain: moving.terrain,
+                        motion,
+                    });
+                }
+                ActivityType::Trail(trail_id) => {
+                    result.ski.add(activity);
+                    if trail_id.is_empty() {
+                        last_trail = None;
+                    } else {
+                        result.add_trail(trail_id, activity, &mut last_trail);
+                    }
+                }
+                ActivityType::Unknown(_) | ActivityType::ExitLift(_) => (),
+            }
+        }
+
+        result.finish_run(run);
+        result.difficulties = difficulties
+            .into_iter()
+            .filter(|d| d.distance > 0.0)
+            .collect();
+        result.terrains =
+            terrains.into_iter().filter(|t| t.distance > 0.0).collect();
+        result
+    }
+
+    /// A new lap starts unless the previous part of the route was on the same
+    /// trail.
+    fn add_trail<'a>(
+        &mut self,
+        trail_id: &'a str,
+        activity: &Activity,
+        last_trail: &mut Option<&'a str>,
+    ) {
+        let index =
+            match self.trails.iter().position(|t| t.trail_id == trail_id) {
+                Some(index) => index,
+                None => {
+                    self.trails.push(TrailStatistics {
+                        trail_id: trail_id.to_string(),
+                        motion: MotionStatistics::default(),
+                        pace: None,
+                        laps: 0,
+                    });
+                    self.trails.len() - 1
+                }
+            };
+        let trail = &mut self.trails[index];
+        if *last_trail != Some(trail_id) {
+            trail.laps += 1;
+        }
+        *last_trail = Some(trail_id);
+        trail.motion.add(activity);
+        trail.pace = trail
+            .motion
+            .duration
+            .filter(|_| trail.motion.distance > 0.0)
+            .map(|d| d / trail.motion.distance * 1000.0);
+    }
+
+    fn finish_run(&mut self, run: Option<RunBuilder>) {
+        if let Some(builder) = run.filter(|r| r.has_ski) {
+            self.run_count += 1;
+            self.runs.push(builder.run);
+        }
+    }
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct DayStatistics {
+    /// In YYYY-MM-DD format. None if the route has no time information.
+    pub date: Option<String>,
+    /// Index of the first activity of the day in the route.
+    pub first_activity: usize,
+    /// Index of the last activity of the day in the route.
+    pub last_activity: usize,
+    pub statistics: RouteStatistics,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct TripStatistics {
+    pub total: RouteStatistics,
+    pub days: Vec<DayStatistics>,
+}
+
+fn format_date(date: Date) -> String {
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day()
+    )
+}
+
+impl TripStatistics {
+    /// Days are determined in the time zone given by `offset`. Activities
+    /// without time information belong to the day before them.
+    pub fn calculate(
+        ski_area: &SkiArea,
+        route: &AnalyzedRoute,
+        offset: UtcOffset,
+    ) -> Self {
+        let activities = &route.route.item;
+        let mut days: Vec<(Option<Date>, usize)> = Vec::new();
+        for (i, activity) in activities.iter().enumerate() {
+            let date = activity.begin_time.map(|t| t.to_offset(offset).date());
+            match days.last_mut() {
+                Some((current, _)) if date.is_none() || *current == date => (),
+                Some((current @ None, _)) => *current = date,
+                _ => days.push((date, i)),
+            }
+        }
+
+        let ends = days
+            .iter()
+            .skip(1)
+            .map(|(_, first)| *first)
+            .chain([activities.len()]);
+        let days = days
+            .iter()
+            .zip(ends)
+            .map(|((date, first), end)| DayStatistics {
+                date: date.map(format_date),
+                first_activity: *first,
+                last_activity: end - 1,
+                statistics: RouteStatistics::calculate_part(
+                    ski_area,
+                    &activities[*first..end],
+                    *first,
+                ),
+            })
+            .collect();
+
+        TripStatistics {
+            total: RouteStatistics::calculate(ski_area, route),
+            days,
+        }
+    }
+}
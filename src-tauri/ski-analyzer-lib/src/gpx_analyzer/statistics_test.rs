@@ -0,0 +1,523 @@
+use super::{
+    Activity, ActivityType, AnalyzedRoute, LiftRideStatistics, MoveType,
+    Moving, RouteStatistics, Segments, Terrain, TrailStatistics,
+    TripStatistics, UseLift,
+};
+use crate::ski_area::{
+    Difficulty, Lift, LiftAttributes, PointWithElevation, SkiArea,
+};
+use crate::utils::bounded_geometry::BoundedGeometry;
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, line, piste, Init,
+};
+
+use geo::{point, Distance, Haversine};
+use gpx::Waypoint;
+use rstest::{fixture, rstest};
+use time::{Duration, OffsetDateTime, UtcOffset};
+
+use std::collections::HashMap;
+
+type Point = (f64, f64, f64, f64);
+
+fn time(seconds: f64) -> OffsetDateTime {
+    OffsetDateTime::UNIX_EPOCH + Duration::seconds_f64(seconds)
+}
+
+fn activity(type_: ActivityType, points: &[Point]) -> Activity {
+    Activity::new(
+        type_,
+        Segments::new(vec![points
+            .iter()
+            .map(|(x, y, ele, t)| {
+                let mut wp = Waypoint::new(point! { x: *x, y: *y });
+                wp.elevation = Some(*ele);
+                wp.time = Some(time(*t).into());
+                wp
+            })
+            .collect()]),
+    )
+}
+
+fn use_lift() -> ActivityType {
+    ActivityType::UseLift(UseLift {
+        lift_id: "lift".to_string(),
+        begin_station: Some(0),
+        end_station: Some(1),
+        is_reverse: false,
+    })
+}
+
+fn moving(move_type: MoveType, piste_id: &str) -> ActivityType {
+    ActivityType::Moving(Moving {
+        move_type,
+        piste_id: piste_id.to_string(),
+        terrain: if piste_id.is_empty() {
+            Terrain::OffPiste
+        } else {
+            Terrain::Piste
+        },
+    })
+}
+
+fn route(ski_area: &SkiArea, activities: Vec<Activity>) -> AnalyzedRoute {
+    AnalyzedRoute {
+        route: BoundedGeometry {
+            item: activities,
+            bounding_rect: ski_area.bounding_rect,
+        },
+        removed_points: Vec::new(),
+        heart_rate: Vec::new(),
+    }
+}
+
+#[fixture]
+fn ski_area() -> SkiArea {
+    let piste1 =
+        piste("p1", vec![line(&[(6.0, 45.01), (6.0, 45.006)])], vec![]);
+    let mut piste2 =
+        piste("p2", vec![line(&[(6.0, 45.01), (6.0, 45.0)])], vec![]);
+    piste2.metadata.difficulty = Difficulty::Advanced;
+    SkiArea::new(
+        create_ski_area_metadata("statistics".to_string()),
+        HashMap::new(),
+        HashMap::from([("p1".to_string(), piste1), ("p2".to_string(), piste2)]),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+fn approx_eq(actual: Option<f64>, expected: f64) {
+    let actual = actual.unwrap();
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "actual={} expected={}",
+        actual,
+        expected
+    );
+}
+
+#[rstest]
+fn empty(_init: Init, ski_area: SkiArea) {
+    let actual =
+        RouteStatistics::calculate(&ski_area, &route(&ski_area, vec![]));
+    assert_eq!(actual, RouteStatistics::default());
+}
+
+#[rstest]
+fn full_route(_init: Init, ski_area: SkiArea) {
+    let activities = vec![
+        activity(
+            ActivityType::Unknown(()),
+            &[(6.0, 45.0, 1000.0, 0.0), (6.0, 45.0005, 1000.0, 60.0)],
+        ),
+        activity(
+            ActivityType::EnterLift("lift".to_string()),
+            &[(6.0, 45.0005, 1000.0, 60.0), (6.0, 45.0005, 1000.0, 120.0)],
+        ),
+        activity(
+            use_lift(),
+            &[
+                (6.0, 45.0005, 1000.0, 120.0),
+                (6.0, 45.005, 1400.0, 420.0),
+                (6.0, 45.01, 1800.0, 720.0),
+            ],
+        ),
+        activity(
+            ActivityType::ExitLift("lift".to_string()),
+            &[(6.0, 45.01, 1800.0, 720.0), (6.0, 45.0101, 1800.0, 740.0)],
+        ),
+        activity(
+            moving(MoveType::Ski, "p1"),
+            &[
+                (6.0, 45.0101, 1800.0, 740.0),
+                (6.0, 45.008, 1700.0, 800.0),
+                (6.0, 45.006, 1600.0, 850.0),
+            ],
+        ),
+        activity(
+            moving(MoveType::Wait, "p1"),
+            &[(6.0, 45.006, 1600.0, 850.0), (6.0, 45.006, 1600.0, 910.0)],
+        ),
+        activity(
+            moving(MoveType::Ski, ""),
+            &[(6.0, 45.006, 1600.0, 910.0), (6.0, 45.003, 1400.0, 990.0)],
+        ),
+        activity(
+            moving(MoveType::Climb, ""),
+            &[(6.0, 45.003, 1400.0, 990.0), (6.0, 45.0031, 1410.0, 1090.0)],
+        ),
+        activity(
+            use_lift(),
+            &[(6.0, 45.0031, 1410.0, 1090.0), (6.0, 45.01, 1800.0, 1690.0)],
+        ),
+        activity(
+            moving(MoveType::Ski, "p2"),
+            &[(6.0, 45.01, 1800.0, 1690.0), (6.0, 45.0, 1000.0, 1990.0)],
+        ),
+    ];
+    let total_distance: f64 = activities.iter().map(|a| a.length).sum();
+    let ski_distance: f64 =
+        [4, 6, 9].iter().map(|i| activities[*i].length).sum();
+    let lift_distance = activities[2].length + activities[8].length;
+    let top_speed = Haversine::distance(
+        point! { x: 6.0, y: 45.008 },
+        point! { x: 6.0, y: 45.006 },
+    ) / 50.0;
+
+    let actual =
+        RouteStatistics::calculate(&ski_area, &route(&ski_area, activities));
+
+    assert_eq!(actual.begin_time, Some(time(0.0)));
+    assert_eq!(actual.end_time, Some(time(1990.0)));
+
+    approx_eq(Some(actual.total.distance), total_distance);
+    assert_eq!(actual.total.duration, Some(1990.0));
+    assert_eq!(actual.total.ascent, 1200.0);
+    assert_eq!(actual.total.descent, 1200.0);
+
+    approx_eq(Some(actual.ski.distance), ski_distance);
+    assert_eq!(actual.ski.duration, Some(490.0));
+    assert_eq!(actual.ski.ascent, 0.0);
+    assert_eq!(actual.ski.descent, 1200.0);
+    approx_eq(actual.ski.top_speed, top_speed);
+    approx_eq(actual.ski.average_speed, ski_distance / 490.0);
+
+    assert_eq!(actual.lift_count, 2);
+    assert_eq!(actual.lift_time, 1200.0);
+    approx_eq(Some(actual.lift_distance), lift_distance);
+    assert_eq!(actual.lift_ascent, 1190.0);
+    assert_eq!(actual.wait_time, 120.0);
+
+    assert_eq!(actual.run_count, 2);
+    let runs: Vec<_> = actual
+        .runs
+        .iter()
+        .map(|r| (r.first_activity, r.last_activity, r.begin_time, r.end_time))
+        .collect();
+    assert_eq!(
+        runs,
+        vec![
+            (4, 7, Some(time(740.0)), Some(time(1090.0))),
+            (9, 9, Some(time(1690.0)), Some(time(1990.0))),
+        ]
+    );
+    assert_eq!(actual.runs[0].motion.descent, 400.0);
+    assert_eq!(actual.runs[0].motion.ascent, 10.0);
+
+    let moves: Vec<_> = actual
+        .moves
+        .iter()
+        .map(|m| (m.activity, m.move_type, m.difficulty, m.motion.descent))
+        .collect();
+    assert_eq!(
+        moves,
+        vec![
+            (4, MoveType::Ski, Difficulty::Easy, 200.0),
+            (5, MoveType::Wait, Difficulty::Easy, 0.0),
+            (6, MoveType::Ski, Difficulty::Unknown, 200.0),
+            (7, MoveType::Climb, Difficulty::Unknown, 0.0),
+            (9, MoveType::Ski, Difficulty::Advanced, 800.0),
+        ]
+    );
+    assert_eq!(actual.moves[3].motion.ascent, 10.0);
+    assert_eq!(actual.moves[1].motion.average_speed, Some(0.0));
+
+    let difficulties: Vec<_> = actual
+        .difficulties
+        .iter()
+        .map(|d| (d.difficulty, d.descent, d.duration))
+        .collect();
+    assert_eq!(
+        difficulties,
+        vec![
+            (Difficulty::Easy, 200.0, Some(110.0)),
+            (Difficulty::Advanced, 800.0, Some(300.0)),
+            (Difficulty::Unknown, 200.0, Some(80.0)),
+        ]
+    );
+
+    let terrains: Vec<_> = actual
+        .terrains
+        .iter()
+        .map(|t| (t.terrain, t.descent, t.duration))
+        .collect();
+    assert_eq!(
+        terrains,
+        vec![
+            (Terrain::Piste, 1000.0, Some(410.0)),
+            (Terrain::OffPiste, 200.0, Some(80.0)),
+        ]
+    );
+    assert_eq!(actual.moves[2].terrain, Terrain::OffPiste);
+}
+
+#[rstest]
+fn lift_rides(_init: Init, mut ski_area: SkiArea) {
+    let begin = point! { x: 6.0, y: 45.0 };
+    let end = point! { x: 6.0, y: 45.01 };
+    ski_area.lifts_mut().insert(
+        "lift".to_string(),
+        Lift {
+            ref_: String::new(),
+            name: "Lift".to_string(),
+            type_: "chair_lift".to_string(),
+            line: BoundedGeometry::new(line(&[begin.x_y(), end.x_y()]))
+                .unwrap(),
+            stations: vec![
+                PointWithElevation::new(begin, Some(1000.0)),
+                PointWithElevation::new(end, Some(1800.0)),
+            ],
+            can_go_reverse: false,
+            can_disembark: false,
+            lengths: vec![Haversine::distance(begin, end)],
+            underground: false,
+            attributes: LiftAttributes {
+                duration: Some(480.0),
+                ..LiftAttributes::default()
+            },
+        },
+    );
+    let mut unknown_lift = use_lift();
+    if let ActivityType::UseLift(u) = &mut unknown_lift {
+        u.lift_id = "other".to_string();
+    }
+    let activities = vec![
+        activity(
+            use_lift(),
+            &[(6.0, 45.0, 1000.0, 0.0), (6.0, 45.01, 1800.0, 600.0)],
+        ),
+        activity(
+            moving(MoveType::Ski, "p2"),
+            &[(6.0, 45.01, 1800.0, 600.0), (6.0, 45.0, 1000.0, 900.0)],
+        ),
+        activity(
+            unknown_lift,
+            &[(6.0, 45.0, 1000.0, 900.0), (6.0, 45.01, 1800.0, 1400.0)],
+        ),
+    ];
+
+    let actual =
+        RouteStatistics::calculate(&ski_area, &route(&ski_area, activities));
+
+    assert_eq!(
+        actual.lift_rides,
+        vec![
+            LiftRideStatistics {
+                activity: 0,
+                lift_id: "lift".to_string(),
+                duration: Some(600.0),
+                nominal_duration: Some(480.0),
+            },
+            LiftRideStatistics {
+                activity: 2,
+                lift_id: "other".to_string(),
+                duration: Some(500.0),
+                nominal_duration: None,
+            },
+        ]
+    );
+}
+
+#[rstest]
+fn trails(_init: Init, ski_area: SkiArea) {
+    let trail = |id: &str| ActivityType::Trail(id.to_string());
+    let activities = vec![
+        activity(
+            trail("t1"),
+            &[(6.0, 45.0, 1000.0, 0.0), (6.0, 45.01, 1050.0, 300.0)],
+        ),
+        activity(
+            trail(""),
+            &[(6.0, 45.01, 1050.0, 300.0), (6.01, 45.01, 1040.0, 400.0)],
+        ),
+        activity(
+            trail("t2"),
+            &[(6.01, 45.01, 1040.0, 400.0), (6.01, 45.0, 1000.0, 600.0)],
+        ),
+        activity(
+            trail("t1"),
+            &[(6.01, 45.0, 1000.0, 600.0), (6.01, 45.01, 1050.0, 900.0)],
+        ),
+    ];
+
+    let actual =
+        RouteStatistics::calculate(&ski_area, &route(&ski_area, activities));
+
+    let ids: Vec<_> =
+        actual.trails.iter().map(|t| t.trail_id.as_str()).collect();
+    assert_eq!(ids, vec!["t1", "t2"]);
+    let t1: &TrailStatistics = &actual.trails[0];
+    approx_eq(t1.motion.duration, 600.0);
+    assert!((t1.motion.ascent - 100.0).abs() < 1e-6);
+    approx_eq(t1.pace, 600.0 / t1.motion.distance * 1000.0);
+    assert_eq!(t1.laps, 2);
+    assert_eq!(actual.trails[1].laps, 1);
+    approx_eq(actual.ski.duration, 900.0);
+    assert_eq!(actual.run_count, 0);
+}
+
+#[rstest]
+fn bike_trails(_init: Init, ski_area: SkiArea) {
+    let ride = |piste_id: &str| {
+        ActivityType::Moving(Moving {
+            move_type: MoveType::Ski,
+            piste_id: piste_id.to_string(),
+            terrain: Terrain::BikeTrail,
+        })
+    };
+    let activities = vec![
+        activity(
+            ride("b1"),
+            &[(6.0, 45.01, 1800.0, 0.0), (6.0, 45.006, 1600.0, 60.0)],
+        ),
+        activity(
+            moving(MoveType::Wait, ""),
+            &[(6.0, 45.006, 1600.0, 60.0), (6.0, 45.006, 1600.0, 120.0)],
+        ),
+        activity(
+            ride("b1"),
+            &[(6.0, 45.006, 1600.0, 120.0), (6.0, 45.0, 1000.0, 240.0)],
+        ),
+        activity(
+            use_lift(),
+            &[(6.0, 45.0, 1000.0, 240.0), (6.0, 45.01, 1800.0, 600.0)],
+        ),
+        activity(
+            ride("b1"),
+            &[(6.0, 45.01, 1800.0, 600.0), (6.0, 45.006, 1600.0, 660.0)],
+        ),
+        activity(
+            ride("b2"),
+            &[(6.0, 45.006, 1600.0, 660.0), (6.0, 45.0, 1000.0, 780.0)],
+        ),
+    ];
+
+    let actual =
+        RouteStatistics::calculate(&ski_area, &route(&ski_area, activities));
+
+    let laps: Vec<_> = actual
+        .trails
+        .iter()
+        .map(|t| (t.trail_id.as_str(), t.laps))
+        .collect();
+    assert_eq!(laps, vec![("b1", 2), ("b2", 1)]);
+    approx_eq(actual.trails[0].motion.duration, 240.0);
+    assert_eq!(actual.run_count, 2);
+    assert_eq!(actual.terrains.len(), 1);
+    assert_eq!(actual.terrains[0].terrain, Terrain::BikeTrail);
+}
+
+#[rstest]
+fn no_time(_init: Init, ski_area: SkiArea) {
+    let mut activities = vec![activity(
+        moving(MoveType::Ski, "p2"),
+        &[(6.0, 45.01, 1800.0, 0.0), (6.0, 45.0, 1000.0, 0.0)],
+    )];
+    for wp in activities[0].route.0.iter_mut().flatten() {
+        wp.time = None;
+    }
+    activities[0].begin_time = None;
+    activities[0].end_time = None;
+
+    let actual =
+        RouteStatistics::calculate(&ski_area, &route(&ski_area, activities));
+    assert_eq!(actual.run_count, 1);
+    assert_eq!(actual.ski.descent, 800.0);
+    assert_eq!(actual.ski.duration, None);
+    assert_eq!(actual.ski.top_speed, None);
+    assert_eq!(actual.ski.average_speed, None);
+    assert_eq!(actual.difficulties[0].duration, None);
+}
+
+#[rstest]
+fn trip(_init: Init, ski_area: SkiArea) {
+    const DAY: f64 = 86400.0;
+    // 23:00 UTC on day 0, which is already day 1 at UTC+2.
+    let late = DAY - 3600.0;
+    let activities = vec![
+        activity(
+            moving(MoveType::Ski, "p1"),
+            &[(6.0, 45.01, 1800.0, 0.0), (6.0, 45.006, 1600.0, 100.0)],
+        ),
+        activity(
+            use_lift(),
+            &[(6.0, 45.006, 1600.0, 100.0), (6.0, 45.01, 1800.0, 400.0)],
+        ),
+        activity(
+            moving(MoveType::Ski, "p2"),
+            &[
+                (6.0, 45.01, 1800.0, late),
+                (6.0, 45.0, 1000.0, late + 200.0),
+            ],
+        ),
+        activity(
+            moving(MoveType::Ski, "p2"),
+            &[
+                (6.0, 45.01, 1800.0, DAY + 7200.0),
+                (6.0, 45.0, 1000.0, DAY + 7500.0),
+            ],
+        ),
+    ];
+    let route = route(&ski_area, activities);
+
+    let get_days = |actual: &TripStatistics| {
+        actual
+            .days
+            .iter()
+            .map(|d| {
+                (
+                    d.date.clone(),
+                    d.first_activity,
+                    d.last_activity,
+                    d.statistics.run_count,
+                    d.statistics.ski.descent,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let actual = TripStatistics::calculate(&ski_area, &route, UtcOffset::UTC);
+    assert_eq!(actual.total.run_count, 3);
+    assert_eq!(actual.total.ski.descent, 1800.0);
+    assert_eq!(
+        get_days(&actual),
+        vec![
+            (Some("1970-01-01".to_string()), 0, 2, 2, 1000.0),
+            (Some("1970-01-02".to_string()), 3, 3, 1, 800.0),
+        ]
+    );
+    assert_eq!(actual.days[1].statistics.runs[0].first_activity, 3);
+
+    let actual = TripStatistics::calculate(
+        &ski_area,
+        &route,
+        UtcOffset::from_hms(2, 0, 0).unwrap(),
+    );
+    assert_eq!(
+        get_days(&actual),
+        vec![
+            (Some("1970-01-01".to_string()), 0, 1, 1, 200.0),
+            (Some("1970-01-02".to_string()), 2, 3, 2, 1600.0),
+        ]
+    );
+}
+
+#[rstest]
+fn trip_no_time(_init: Init, ski_area: SkiArea) {
+    let mut activities = vec![activity(
+        moving(MoveType::Ski, "p2"),
+        &[(6.0, 45.01, 1800.0, 0.0), (6.0, 45.0, 1000.0, 0.0)],
+    )];
+    activities[0].begin_time = None;
+    activities[0].end_time = None;
+
+    let actual = TripStatistics::calculate(
+        &ski_area,
+        &route(&ski_area, activities),
+        UtcOffset::UTC,
+    );
+    assert_eq!(actual.days.len(), 1);
+    assert_eq!(actual.days[0].date, None);
+    assert_eq!(actual.days[0].statistics, actual.total);
+}
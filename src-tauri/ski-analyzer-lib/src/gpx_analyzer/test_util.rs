@@ -1,10 +1,11 @@
-use super::Activity;
+use super::{Activity, ActivityType, Segments};
 use crate::utils::{
     bounded_geometry::BoundedGeometry, json::save_to_file,
     rect::union_rects_all,
 };
 use geo::{point, Rect};
 use gpx::Waypoint;
+use time::{Duration, OffsetDateTime};
 
 pub fn save_analyzed_route(result: &Vec<Activity>, filename: &str) {
     let bounding_rect = union_rects_all(
@@ -32,3 +33,26 @@ pub fn wp(x: f64, y: f64, h: Option<f64>) -> Waypoint {
     result.hdop = h;
     result
 }
+
+pub fn time(seconds: f64) -> OffsetDateTime {
+    OffsetDateTime::UNIX_EPOCH + Duration::seconds_f64(seconds)
+}
+
+/// Creates an activity from (x, y, elevation, time) tuples.
+pub fn activity(
+    type_: ActivityType,
+    points: &[(f64, f64, f64, f64)],
+) -> Activity {
+    Activity::new(
+        type_,
+        Segments::new(vec![points
+            .iter()
+            .map(|(x, y, ele, t)| {
+                let mut wp = Waypoint::new(point! { x: *x, y: *y });
+                wp.elevation = Some(*ele);
+                wp.time = Some(time(*t).into());
+                wp
+            })
+            .collect()]),
+    )
+}
@@ -1,4 +1,6 @@
-use super::{get_speed, Activity, ActivityType, SegmentCoordinate, Segments};
+use super::{
+    get_speed, Activity, ActivityType, Segment, SegmentCoordinate, Segments,
+};
 use crate::config::get_config;
 use crate::error::Result;
 use crate::ski_area::{Lift, SkiArea};
@@ -14,6 +16,8 @@ use gpx::Waypoint;
 use serde::{Deserialize, Serialize};
 
 const MIN_DISTANCE: f64 = 15.0;
+// GPS is unreliable in tunnels, so allow the track to be further away.
+const MIN_DISTANCE_UNDERGROUND: f64 = 100.0;
 const MIN_MOVE_DISTANCE: f64 = 5.0;
 const MIN_SPEED: f64 = 1.0;
 
@@ -29,12 +33,21 @@ pub struct UseLift {
     pub is_reverse: bool,
 }
 
+fn get_min_distance(lift: &Lift) -> f64 {
+    if lift.underground {
+        MIN_DISTANCE_UNDERGROUND
+    } else {
+        MIN_DISTANCE
+    }
+}
+
 fn get_station(lift: &Lift, p: Point) -> LiftEnd {
+    let min_distance = get_min_distance(lift);
     lift.stations
         .iter()
         .enumerate()
         .map(|(i, m)| (i, Haversine::distance(m.point, p)))
-        .filter(|(_, m)| *m < MIN_DISTANCE)
+        .filter(|(_, m)| *m < min_distance)
         .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
         .map(|(i, _)| i)
 }
@@ -47,7 +60,7 @@ struct LiftDistance {
 impl LiftDistance {
     fn get(lift: &Lift, p: Point) -> Option<Self> {
         let distance = lift.get_closest_point(p)?;
-        if distance.distance > MIN_DISTANCE {
+        if distance.distance > get_min_distance(lift) {
             return None;
         }
         Some(LiftDistance {
@@ -213,7 +226,7 @@ impl<'s> LiftCandidate<'s> {
                 Haversine::distance(
                     wp.point(),
                     self.lift.stations[*station].point,
-                ) < MIN_DISTANCE
+                ) < get_min_distance(self.lift)
             }
         }
     }
@@ -376,70 +389,140 @@ fn commit_lift_candidates<'s>(
 
 type Candidates<'s> = Vec<LiftCandidate<'s>>;
 
-pub fn find_lift_usage<'s>(
+pub fn find_lift_usage(
     cancel: &CancellationToken,
-    ski_area: &'s SkiArea,
+    ski_area: &SkiArea,
     segments: Segments,
 ) -> Result<Vec<Activity>> {
     let mut result = Vec::new();
+    let mut finder = LiftUsageFinder::new(ski_area);
+    for segment in segments.0 {
+        for point in segment {
+            cancel.check()?;
+            result.append(&mut finder.add_point(point));
+        }
+        finder.end_segment();
+    }
+    result.append(&mut finder.finish());
+    Ok(result)
+}
 
-    let bounding_rects: HashMap<String, Rect> = ski_area
-        .lifts
-        .iter()
-        .map(|(id, l)| (id.clone(), l.line.expanded_rect(MIN_DISTANCE)))
-        .collect();
+/// Finds lift usage one waypoint at a time. Activities are returned as soon
+/// as they are final.
+pub struct LiftUsageFinder<'s> {
+    ski_area: &'s SkiArea,
+    bounding_rects: HashMap<String, Rect>,
+    candidates: Candidates<'s>,
+    finished_candidates: Candidates<'s>,
+    current_route: Segments,
+    route_segment: Segment,
+}
 
-    let mut candidates: Candidates = Vec::new();
-    let mut finished_candidates: Candidates = Vec::new();
+impl<'s> LiftUsageFinder<'s> {
+    pub fn new(ski_area: &'s SkiArea) -> Self {
+        Self {
+            ski_area,
+            bounding_rects: ski_area
+                .lifts()
+                .iter()
+                .map(|(id, l)| {
+                    (id.clone(), l.line.expanded_rect(get_min_distance(l)))
+                })
+                .collect(),
+            candidates: Vec::new(),
+            finished_candidates: Vec::new(),
+            current_route: Segments::default(),
+            route_segment: Vec::new(),
+        }
+    }
 
-    let current_route = segments.process(
-        |current_route, route_segment, point, mut coordinate| {
-            cancel.check()?;
-            let (mut finished, unfinished): (Candidates, Candidates) =
-                take(&mut candidates)
-                    .into_iter()
-                    .filter_map(|mut l| match l.add_point(&point, coordinate) {
-                        LiftResult::Failure => None,
-                        _ => Some(l),
-                    })
-                    .partition(|l| l.result == LiftResult::Finished);
-            candidates = unfinished;
-            finished_candidates.append(&mut finished);
-
-            if candidates.is_empty() && !finished_candidates.is_empty() {
-                let mut to_add =
-                    current_route.commit(Some(route_segment), |r| {
-                        commit_lift_candidates(
-                            take(&mut finished_candidates),
-                            r,
-                        )
-                    });
-                result.append(&mut to_add);
-                coordinate = (current_route.0.len(), route_segment.len());
-            }
+    pub fn add_point(&mut self, point: Waypoint) -> Vec<Activity> {
+        let mut result = Vec::new();
+        let mut coordinate =
+            (self.current_route.0.len(), self.route_segment.len());
+        let (mut finished, unfinished): (Candidates, Candidates) =
+            take(&mut self.candidates)
+                .into_iter()
+                .filter_map(|mut l| match l.add_point(&point, coordinate) {
+                    LiftResult::Failure => None,
+                    _ => Some(l),
+                })
+                .partition(|l| l.result == LiftResult::Finished);
+        self.candidates = unfinished;
+        self.finished_candidates.append(&mut finished);
+
+        if self.candidates.is_empty() && !self.finished_candidates.is_empty() {
+            let finished_candidates = take(&mut self.finished_candidates);
+            result = self
+                .current_route
+                .commit(Some(&mut self.route_segment), |r| {
+                    commit_lift_candidates(finished_candidates, r)
+                });
+            coordinate = (self.current_route.0.len(), self.route_segment.len());
+        }
 
-            let mut new_candidates = LiftCandidate::find(
-                &bounding_rects,
-                ski_area.lifts.iter().filter(|l| {
+        let candidates = &self.candidates;
+        let finished_candidates = &self.finished_candidates;
+        let mut new_candidates = LiftCandidate::find(
+            &self.bounding_rects,
+            // MIN_DISTANCE_UNDERGROUND is the largest distance returned by
+            // get_min_distance().
+            self.ski_area
+                .index()
+                .lifts_near(point.point(), MIN_DISTANCE_UNDERGROUND)
+                .filter_map(|id| self.ski_area.lifts().get_key_value(id))
+                .filter(|l| {
                     candidates
                         .iter()
                         .chain(finished_candidates.iter())
-                        .find(|c| {
-                            (*&l.1 as *const Lift) == (c.lift as *const Lift)
-                        })
+                        .find(|c| std::ptr::eq(l.1, c.lift))
                         .is_none()
                 }),
-                coordinate,
-                &point,
-            );
-            candidates.append(&mut new_candidates);
-            Ok(())
-        },
-    )?;
+            coordinate,
+            &point,
+        );
+        self.candidates.append(&mut new_candidates);
+        self.route_segment.push(point);
+        result
+    }
 
-    if !current_route.0.is_empty() {
-        result.push(Activity::new(ActivityType::default(), current_route));
+    /// The next point starts a new segment.
+    pub fn end_segment(&mut self) {
+        if !self.route_segment.is_empty() {
+            self.current_route.0.push(take(&mut self.route_segment));
+        }
     }
 
-    Ok(result)
+    /// The part of the route that is not final yet.
+    pub fn pending_route(&self) -> Segments {
+        let mut result = self.current_route.clone();
+        if !self.route_segment.is_empty() {
+            result.0.push(self.route_segment.clone());
+        }
+        result
+    }
+
+    /// IDs of the lifts that the route may currently be using.
+    pub fn possible_lifts(&self) -> Vec<&'s str> {
+        let ski_area = self.ski_area;
+        self.candidates
+            .iter()
+            .filter_map(|c| {
+                ski_area
+                    .lifts()
+                    .get_key_value(&c.data.lift_id)
+                    .map(|(id, _)| id.as_str())
+            })
+            .collect()
+    }
+
+    /// Returns the rest of the route as an unknown activity.
+    pub fn finish(mut self) -> Vec<Activity> {
+        self.end_segment();
+        if self.current_route.0.is_empty() {
+            Vec::new()
+        } else {
+            vec![Activity::new(ActivityType::default(), self.current_route)]
+        }
+    }
 }
@@ -3,7 +3,7 @@ use super::use_lift::find_lift_usage;
 use super::Segments;
 use super::{Activity, ActivityType, UseLift};
 use crate::assert_eq_pretty;
-use crate::ski_area::{Lift, PointWithElevation, SkiArea};
+use crate::ski_area::{Lift, LiftAttributes, PointWithElevation, SkiArea};
 use crate::utils::bounded_geometry::BoundedGeometry;
 use crate::utils::cancel::CancellationToken;
 use crate::utils::test_util::{
@@ -12,8 +12,8 @@ use crate::utils::test_util::{
 };
 
 use function_name::named;
-use geo::{Distance, Haversine, LineString};
-use gpx::TrackSegment;
+use geo::{point, Distance, Haversine, LineString};
+use gpx::{TrackSegment, Waypoint};
 use rstest::{fixture, rstest};
 use std::collections::HashMap;
 use std::fs;
@@ -32,7 +32,7 @@ fn lift(
         .chain([line_.0.len() - 1].iter())
         .map(|i| PointWithElevation {
             point: line_[*i].into(),
-            elevation: 0,
+            elevation: None,
         })
         .collect();
     let line = BoundedGeometry::new(line_).unwrap();
@@ -43,11 +43,21 @@ fn lift(
         line,
         stations,
         lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
         can_go_reverse,
         can_disembark,
     }
 }
 
+fn underground_lift(name: String, line_: LineString) -> Lift {
+    Lift {
+        underground: true,
+        attributes: LiftAttributes::default(),
+        ..lift(name, line_, &[], true, false)
+    }
+}
+
 fn ski_area(name: &str, lifts: Vec<Lift>) -> SkiArea {
     SkiArea::new(
         create_ski_area_metadata(name.to_string()),
@@ -1233,3 +1243,111 @@ fn speed_slow_then_fast(
 
     run(&s, segments, expected, function_name!());
 }
+
+// Continue far enough from the top station to leave the lift.
+fn extend_segment(mut segment: TrackSegment) -> TrackSegment {
+    segment.points.extend(
+        [(6.651274, 45.3723031), (6.6499512, 45.3720526)]
+            .iter()
+            .map(|(x, y)| Waypoint::new(point!(x: *x, y: *y))),
+    );
+    segment
+}
+
+// Points inside the tunnel are about 60 m off the line.
+fn drift_segment(mut segment: TrackSegment) -> TrackSegment {
+    for p in &mut segment.points[5..17] {
+        let point = p.point();
+        *p = Waypoint::new(point!(x: point.x() + 0.0008, y: point.y()));
+    }
+    extend_segment(segment)
+}
+
+#[rstest]
+#[named]
+fn underground_drift_bad(
+    _init: Init,
+    line00: LineString,
+    simple_segment: TrackSegment,
+) {
+    let s = ski_area(
+        function_name!(),
+        vec![lift("Lift 1".to_string(), line00, &[], true, false)],
+    );
+    let g = make_gpx(vec![drift_segment(simple_segment)]);
+    let segments = get_segments(g);
+
+    let expected: Vec<Activity> = vec![Activity::new(
+        ActivityType::Unknown(()),
+        segments.clone_part((0, 0), (0, 23)),
+    )];
+
+    run(&s, segments, expected, function_name!());
+}
+
+#[rstest]
+#[named]
+fn underground_drift_good(
+    _init: Init,
+    line00: LineString,
+    simple_segment: TrackSegment,
+) {
+    let s = ski_area(
+        function_name!(),
+        vec![underground_lift("Lift 1".to_string(), line00)],
+    );
+    let g = make_gpx(vec![drift_segment(simple_segment)]);
+    let segments = get_segments(g);
+    // The station is also less accurate, so the ride starts earlier and
+    // ends later.
+    let expected: Vec<Activity> = vec![
+        Activity::new(
+            ActivityType::UseLift(UseLift {
+                lift_id: "Lift 1".to_string(),
+                begin_station: Some(0),
+                end_station: Some(1),
+                is_reverse: false,
+            }),
+            segments.clone_part((0, 0), (0, 21)),
+        ),
+        Activity::new(
+            ActivityType::Unknown(()),
+            segments.clone_part((0, 20), (0, 23)),
+        ),
+    ];
+
+    run(&s, segments, expected, function_name!());
+}
+
+#[rstest]
+#[named]
+fn underground_dropout(
+    _init: Init,
+    line00: LineString,
+    mut simple_segment: TrackSegment,
+) {
+    let s = ski_area(
+        function_name!(),
+        vec![underground_lift("Lift 1".to_string(), line00)],
+    );
+    simple_segment.points.drain(5..17);
+    let g = make_gpx(vec![extend_segment(simple_segment)]);
+    let segments = get_segments(g);
+    let expected: Vec<Activity> = vec![
+        Activity::new(
+            ActivityType::UseLift(UseLift {
+                lift_id: "Lift 1".to_string(),
+                begin_station: Some(0),
+                end_station: Some(1),
+                is_reverse: false,
+            }),
+            segments.clone_part((0, 0), (0, 9)),
+        ),
+        Activity::new(
+            ActivityType::Unknown(()),
+            segments.clone_part((0, 8), (0, 11)),
+        ),
+    ];
+
+    run(&s, segments, expected, function_name!());
+}
@@ -0,0 +1,463 @@
+use geo::{
+    Closest, Coord, Distance, Haversine, HaversineClosestPoint, Intersects,
+    Length, LineString, Point,
+};
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+
+use crate::dem::Dem;
+use crate::error::Result;
+use crate::ski_area::SkiArea;
+use crate::utils::bounded_geometry::BoundedGeometry;
+use crate::utils::cancel::CancellationToken;
+
+mod export;
+mod planner;
+mod reachability;
+
+#[cfg(test)]
+mod export_test;
+#[cfg(test)]
+mod planner_test;
+#[cfg(test)]
+mod reachability_test;
+
+pub use export::{export_graph, graph_to_dot, graph_to_graphml, GraphFormat};
+pub use planner::{plan_route, CostModel, PlannedRoute, RouteStep};
+pub use reachability::{
+    reachable_from_lift, way_to_valley, Reachable, ReachablePiste,
+};
+
+/// Lift stations and piste ends closer than this (in meters) are the same
+/// place.
+const MERGE_DISTANCE: f64 = 5.0;
+/// Lift stations are connected to lifts and pistes at most this far away (in
+/// meters).
+const MAX_LIFT_DISTANCE: f64 = 50.0;
+/// The end of a piste is connected to another piste at most this far away (in
+/// meters).
+const MAX_PISTE_DISTANCE: f64 = 20.0;
+
+/// A place where lifts and pistes can be entered or left.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Node {
+    pub point: Point,
+    /// In meters. None if unknown.
+    pub elevation: Option<f64>,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum EdgeKind {
+    /// Riding a lift from one station to the next.
+    Lift {
+        lift_id: String,
+        begin_station: usize,
+        end_station: usize,
+    },
+    /// Skiing down a piste between two places on it.
+    Piste { piste_id: String },
+    /// Skiing or walking a short distance between lifts and pistes that are
+    /// close to each other.
+    Connection,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Edge {
+    /// Index of the node where the edge begins.
+    pub from: usize,
+    /// Index of the node where the edge ends.
+    pub to: usize,
+    pub kind: EdgeKind,
+    /// In meters.
+    pub length: f64,
+    pub line: LineString,
+}
+
+/// Directed graph of the places of the ski area connected by lifts and
+/// pistes. Lift edges go in the direction of the lift, and in both directions
+/// if it can go in reverse. Piste edges go downhill.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// A point on a line string, used for cutting the line at nodes.
+#[derive(Debug, Clone, Copy)]
+struct LinePosition {
+    segment: usize,
+    /// The distance from the beginning of the segment in degrees. It is only
+    /// used for ordering positions on the same segment.
+    offset: f64,
+    point: Point,
+}
+
+impl LinePosition {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.segment
+            .cmp(&other.segment)
+            .then_with(|| self.offset.total_cmp(&other.offset))
+    }
+}
+
+/// The closest position of the line to the point and its distance in meters.
+fn locate(line: &LineString, p: Point) -> Option<(LinePosition, f64)> {
+    line.lines()
+        .enumerate()
+        .filter_map(|(segment, l)| {
+            let point = match l.haversine_closest_point(&p) {
+                Closest::Intersection(c) | Closest::SinglePoint(c) => c,
+                Closest::Indeterminate => return None,
+            };
+            let diff = point.0 - l.start;
+            let position = LinePosition {
+                segment,
+                offset: diff.x.hypot(diff.y),
+                point,
+            };
+            Some((position, Haversine::distance(p, point)))
+        })
+        .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+}
+
+/// The part of the line between two positions.
+fn sub_line(
+    line: &LineString,
+    from: &LinePosition,
+    to: &LinePosition,
+) -> LineString {
+    let mut coords = vec![from.point.0];
+    coords.extend_from_slice(&line.0[from.segment + 1..=to.segment]);
+    coords.push(to.point.0);
+    LineString::new(coords)
+}
+
+/// One line of a piste, oriented downhill.
+struct PisteLine<'s> {
+    piste_id: &'s str,
+    line: BoundedGeometry<LineString>,
+    start: usize,
+    end: usize,
+}
+
+struct Builder<'d> {
+    dem: Option<&'d Dem>,
+    graph: Graph,
+}
+
+impl<'d> Builder<'d> {
+    fn add_node(&mut self, point: Point, elevation: Option<f64>) -> usize {
+        let elevation =
+            elevation.or_else(|| self.dem.and_then(|d| d.elevation(point)));
+        self.graph.nodes.push(Node { point, elevation });
+        self.graph.nodes.len() - 1
+    }
+
+    /// Returns an existing node if there is one close enough.
+    fn find_or_add_node(
+        &mut self,
+        point: Point,
+        elevation: Option<f64>,
+    ) -> usize {
+        let existing = self.graph.nodes.iter().position(|n| {
+            Haversine::distance(n.point, point) <= MERGE_DISTANCE
+        });
+        match existing {
+            Some(index) => {
+                let node = &mut self.graph.nodes[index];
+                node.elevation = node.elevation.or(elevation);
+                index
+            }
+            None => self.add_node(point, elevation),
+        }
+    }
+
+    fn add_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        kind: EdgeKind,
+        line: LineString,
+    ) {
+        self.graph.edges.push(Edge {
+            from,
+            to,
+            kind,
+            length: line.length::<Haversine>(),
+            line,
+        });
+    }
+
+    fn connect(&mut self, node1: usize, node2: usize) {
+        let p1 = self.graph.nodes[node1].point;
+        let p2 = self.graph.nodes[node2].point;
+        self.add_edge(
+            node1,
+            node2,
+            EdgeKind::Connection,
+            LineString::from(vec![p1, p2]),
+        );
+        self.add_edge(
+            node2,
+            node1,
+            EdgeKind::Connection,
+            LineString::from(vec![p2, p1]),
+        );
+    }
+
+    fn is_downhill(&self, line: &LineString) -> bool {
+        let (Some(first), Some(last)) = (line.0.first(), line.0.last()) else {
+            return true;
+        };
+        let elevation =
+            |c: &Coord| self.dem.and_then(|d| d.elevation((*c).into()));
+        match (elevation(first), elevation(last)) {
+            (Some(e1), Some(e2)) => e1 >= e2,
+            _ => true,
+        }
+    }
+
+    /// Adds the station nodes and the edges between them. Returns the station
+    /// nodes.
+    fn add_lift(&mut self, lift_id: &str, ski_area: &SkiArea) -> Vec<usize> {
+        let lift = &ski_area.lifts()[lift_id];
+        let nodes: Vec<usize> = lift
+            .stations
+            .iter()
+            .map(|s| self.find_or_add_node(s.point, s.elevation))
+            .collect();
+        let positions: Vec<Option<LinePosition>> = lift
+            .stations
+            .iter()
+            .map(|s| locate(&lift.line.item, s.point).map(|(p, _)| p))
+            .collect();
+        for i in 1..nodes.len() {
+            let (Some(from), Some(to)) = (&positions[i - 1], &positions[i])
+            else {
+                continue;
+            };
+            let line = sub_line(&lift.line.item, from, to);
+            if lift.can_go_reverse {
+                let mut reverse = line.clone();
+                reverse.0.reverse();
+                self.add_edge(
+                    nodes[i],
+                    nodes[i - 1],
+                    EdgeKind::Lift {
+                        lift_id: lift_id.to_string(),
+                        begin_station: i,
+                        end_station: i - 1,
+                    },
+                    reverse,
+                );
+            }
+            self.add_edge(
+                nodes[i - 1],
+                nodes[i],
+                EdgeKind::Lift {
+                    lift_id: lift_id.to_string(),
+                    begin_station: i - 1,
+                    end_station: i,
+                },
+                line,
+            );
+        }
+        nodes
+    }
+
+    /// Cuts the piste line at the given nodes and adds the parts as edges.
+    /// Nodes that are not close enough to the line are ignored, and nodes not
+    /// on the line are connected to a new node on the line.
+    fn add_piste_line(
+        &mut self,
+        piste_line: &PisteLine,
+        nodes: &[(usize, f64)],
+    ) {
+        let line = &piste_line.line.item;
+        let last_segment = line.0.len() - 2;
+        let mut cuts: Vec<(LinePosition, usize)> = vec![
+            (
+                LinePosition {
+                    segment: 0,
+                    offset: 0.0,
+                    point: line.0[0].into(),
+                },
+                piste_line.start,
+            ),
+            (
+                LinePosition {
+                    segment: last_segment,
+                    offset: f64::INFINITY,
+                    point: line.0[last_segment + 1].into(),
+                },
+                piste_line.end,
+            ),
+        ];
+        for (node, max_distance) in nodes {
+            if *node == piste_line.start || *node == piste_line.end {
+                continue;
+            }
+            let point = self.graph.nodes[*node].point;
+            if !piste_line
+                .line
+                .expanded_rect(*max_distance)
+                .intersects(&point)
+            {
+                continue;
+            }
+            let Some((position, distance)) = locate(line, point) else {
+                continue;
+            };
+            if distance > *max_distance {
+                continue;
+            }
+            if distance <= MERGE_DISTANCE {
+                cuts.push((position, *node));
+                continue;
+            }
+            let on_line = cuts
+                .iter()
+                .find(|(p, _)| {
+                    Haversine::distance(p.point, position.point)
+                        <= MERGE_DISTANCE
+                })
+                .map(|(_, n)| *n);
+            let on_line = match on_line {
+                Some(n) => n,
+                None => {
+                    let n = self.add_node(position.point, None);
+                    cuts.push((position, n));
+                    n
+                }
+            };
+            self.connect(*node, on_line);
+        }
+
+        cuts.sort_by(|(p1, _), (p2, _)| p1.cmp(p2));
+        cuts.dedup_by_key(|(_, n)| *n);
+        for parts in cuts.windows(2) {
+            let (from, from_node) = &parts[0];
+            let (to, to_node) = &parts[1];
+            self.add_edge(
+                *from_node,
+                *to_node,
+                EdgeKind::Piste {
+                    piste_id: piste_line.piste_id.to_string(),
+                },
+                sub_line(line, from, to),
+            );
+        }
+    }
+}
+
+impl Graph {
+    /// Builds the graph of the lifts and pistes. Piste lines are assumed to
+    /// be drawn downhill, as OSM recommends, unless the elevation model
+    /// shows otherwise.
+    pub fn new(
+        cancel: &CancellationToken,
+        ski_area: &SkiArea,
+        dem: Option<&Dem>,
+    ) -> Result<Self> {
+        let mut builder = Builder {
+            dem,
+            graph: Graph::default(),
+        };
+
+        let mut lift_ids: Vec<&String> = ski_area.lifts().keys().collect();
+        lift_ids.sort();
+        let mut stations: Vec<usize> = Vec::new();
+        for lift_id in lift_ids {
+            cancel.check()?;
+            let nodes = builder.add_lift(lift_id, ski_area);
+            // Connect to the stations of other lifts nearby.
+            for node in &nodes {
+                let point = builder.graph.nodes[*node].point;
+                let close: Vec<usize> = stations
+                    .iter()
+                    .copied()
+                    .filter(|s| {
+                        !nodes.contains(s)
+                            && Haversine::distance(
+                                builder.graph.nodes[*s].point,
+                                point,
+                            ) <= MAX_LIFT_DISTANCE
+                    })
+                    .collect();
+                for s in close {
+                    builder.connect(s, *node);
+                }
+            }
+            for node in nodes {
+                if !stations.contains(&node) {
+                    stations.push(node);
+                }
+            }
+        }
+
+        let mut piste_ids: Vec<&String> = ski_area.pistes().keys().collect();
+        piste_ids.sort();
+        let mut piste_lines: Vec<PisteLine> = Vec::new();
+        for piste_id in piste_ids {
+            let piste = &ski_area.pistes()[piste_id];
+            if piste.data.attributes.is_abandoned() {
+                continue;
+            }
+            for line in &piste.data.lines {
+                if line.0.len() < 2 {
+                    continue;
+                }
+                let mut line = line.clone();
+                if !builder.is_downhill(&line) {
+                    line.0.reverse();
+                }
+                let start = builder.find_or_add_node(line.0[0].into(), None);
+                let end = builder
+                    .find_or_add_node((*line.0.last().unwrap()).into(), None);
+                piste_lines.push(PisteLine {
+                    piste_id,
+                    line: BoundedGeometry::new(line)?,
+                    start,
+                    end,
+                });
+            }
+        }
+
+        let mut nodes: Vec<(usize, f64)> =
+            stations.iter().map(|s| (*s, MAX_LIFT_DISTANCE)).collect();
+        for piste_line in &piste_lines {
+            for node in [piste_line.start, piste_line.end] {
+                if !nodes.iter().any(|(n, _)| *n == node) {
+                    nodes.push((node, MAX_PISTE_DISTANCE));
+                }
+            }
+        }
+        for piste_line in &piste_lines {
+            cancel.check()?;
+            builder.add_piste_line(piste_line, &nodes);
+        }
+
+        Ok(builder.graph)
+    }
+
+    /// The indices of the edges leaving each node.
+    pub fn outgoing_edges(&self) -> Vec<Vec<usize>> {
+        let mut result = vec![Vec::new(); self.nodes.len()];
+        for (i, edge) in self.edges.iter().enumerate() {
+            result[edge.from].push(i);
+        }
+        result
+    }
+
+    /// The index of the node closest to the point.
+    pub fn closest_node(&self, p: Point) -> Option<usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (i, Haversine::distance(n.point, p)))
+            .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+            .map(|(i, _)| i)
+    }
+}
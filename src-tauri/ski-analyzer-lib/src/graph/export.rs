@@ -0,0 +1,192 @@
+use quick_xml::escape::escape;
+
+use super::{Edge, EdgeKind, Graph};
+use crate::error::Result;
+use crate::ski_area::{Difficulty, SkiArea};
+
+/// The file format of an exported graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    Json,
+    /// Graphviz.
+    Dot,
+    #[value(name = "graphml")]
+    GraphMl,
+}
+
+/// The properties of an edge that are looked up in the ski area.
+struct EdgeInfo<'s> {
+    kind: &'static str,
+    id: &'s str,
+    name: &'s str,
+    difficulty: Option<Difficulty>,
+}
+
+fn name_or_ref<'s>(name: &'s str, ref_: &'s str) -> &'s str {
+    if name.is_empty() {
+        ref_
+    } else {
+        name
+    }
+}
+
+fn edge_info<'s>(ski_area: &'s SkiArea, edge: &'s Edge) -> EdgeInfo<'s> {
+    match &edge.kind {
+        EdgeKind::Lift { lift_id, .. } => EdgeInfo {
+            kind: "lift",
+            id: lift_id,
+            name: ski_area
+                .lifts()
+                .get(lift_id)
+                .map_or("", |l| name_or_ref(&l.name, &l.ref_)),
+            difficulty: None,
+        },
+        EdgeKind::Piste { piste_id } => {
+            let metadata = ski_area.pistes().get(piste_id).map(|p| &p.metadata);
+            EdgeInfo {
+                kind: "piste",
+                id: piste_id,
+                name: metadata.map_or("", |m| name_or_ref(&m.name, &m.ref_)),
+                difficulty: metadata.map(|m| m.difficulty),
+            }
+        }
+        EdgeKind::Connection => EdgeInfo {
+            kind: "connection",
+            id: "",
+            name: "",
+            difficulty: None,
+        },
+    }
+}
+
+fn difficulty_color(difficulty: Difficulty) -> &'static str {
+    match difficulty {
+        Difficulty::Novice => "green",
+        Difficulty::Easy => "blue",
+        Difficulty::Intermediate => "red",
+        Difficulty::Advanced | Difficulty::Expert => "black",
+        Difficulty::Freeride => "orange",
+        Difficulty::Unknown => "gray",
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Converts the graph to the Graphviz DOT format. Nodes are labeled with
+/// their index and elevation, edges with the name of the lift or piste.
+pub fn graph_to_dot(ski_area: &SkiArea, graph: &Graph) -> String {
+    let mut out =
+        format!("digraph \"{}\" {{\n", dot_escape(&ski_area.metadata.name));
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let label = match node.elevation {
+            Some(e) => format!("{}\\n{:.0} m", i, e),
+            None => i.to_string(),
+        };
+        out.push_str(&format!("  n{} [label=\"{}\"];\n", i, label));
+    }
+    for edge in &graph.edges {
+        let info = edge_info(ski_area, edge);
+        let style = match &edge.kind {
+            EdgeKind::Lift { .. } => "style=bold".to_string(),
+            EdgeKind::Piste { .. } => format!(
+                "color={}",
+                difficulty_color(
+                    info.difficulty.unwrap_or(Difficulty::Unknown)
+                )
+            ),
+            EdgeKind::Connection => "style=dashed".to_string(),
+        };
+        out.push_str(&format!(
+            "  n{} -> n{} [label=\"{}\", {}];\n",
+            edge.from,
+            edge.to,
+            dot_escape(info.name),
+            style
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn graphml_data(key: &str, value: &str) -> String {
+    format!("<data key=\"{}\">{}</data>", key, escape(value))
+}
+
+/// Converts the graph to GraphML, with the coordinates of the nodes and the
+/// properties of the edges as data.
+pub fn graph_to_graphml(ski_area: &SkiArea, graph: &Graph) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+    );
+    for (id, for_, type_) in [
+        ("lon", "node", "double"),
+        ("lat", "node", "double"),
+        ("elevation", "node", "double"),
+        ("kind", "edge", "string"),
+        ("id", "edge", "string"),
+        ("name", "edge", "string"),
+        ("difficulty", "edge", "string"),
+        ("length", "edge", "double"),
+    ] {
+        out.push_str(&format!(
+            "  <key id=\"{0}\" for=\"{1}\" attr.name=\"{0}\" \
+             attr.type=\"{2}\"/>\n",
+            id, for_, type_
+        ));
+    }
+    out.push_str(&format!(
+        "  <graph id=\"{}\" edgedefault=\"directed\">\n",
+        escape(ski_area.metadata.name.as_str())
+    ));
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let mut data = vec![
+            graphml_data("lon", &node.point.x().to_string()),
+            graphml_data("lat", &node.point.y().to_string()),
+        ];
+        if let Some(e) = node.elevation {
+            data.push(graphml_data("elevation", &e.to_string()));
+        }
+        out.push_str(&format!(
+            "    <node id=\"n{}\">{}</node>\n",
+            i,
+            data.join("")
+        ));
+    }
+    for edge in &graph.edges {
+        let info = edge_info(ski_area, edge);
+        let mut data = vec![graphml_data("kind", info.kind)];
+        if !info.id.is_empty() {
+            data.push(graphml_data("id", info.id));
+        }
+        if !info.name.is_empty() {
+            data.push(graphml_data("name", info.name));
+        }
+        if let Some(d) = info.difficulty {
+            data.push(graphml_data("difficulty", &d.to_string()));
+        }
+        data.push(graphml_data("length", &edge.length.to_string()));
+        out.push_str(&format!(
+            "    <edge source=\"n{}\" target=\"n{}\">{}</edge>\n",
+            edge.from,
+            edge.to,
+            data.join("")
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+pub fn export_graph(
+    ski_area: &SkiArea,
+    graph: &Graph,
+    format: GraphFormat,
+) -> Result<String> {
+    Ok(match format {
+        GraphFormat::Json => serde_json::to_string(graph)?,
+        GraphFormat::Dot => graph_to_dot(ski_area, graph),
+        GraphFormat::GraphMl => graph_to_graphml(ski_area, graph),
+    })
+}
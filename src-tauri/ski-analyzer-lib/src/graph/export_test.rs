@@ -0,0 +1,120 @@
+use super::{export_graph, graph_to_dot, graph_to_graphml, GraphFormat};
+use crate::graph::{Edge, EdgeKind, Graph, Node};
+use crate::ski_area::{Difficulty, SkiArea};
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, lift, line, piste, Init,
+};
+
+use geo::point;
+use rstest::{fixture, rstest};
+use time::OffsetDateTime;
+
+use std::collections::HashMap;
+
+#[fixture]
+fn ski_area() -> SkiArea {
+    let mut piste = piste(
+        "Red & \"Black\"",
+        vec![line(&[(6.0, 45.01), (6.0, 45.0)])],
+        vec![],
+    );
+    piste.metadata.difficulty = Difficulty::Intermediate;
+    let mut lift = lift("", &[(6.0, 45.0), (6.0, 45.01)]);
+    lift.ref_ = "A".to_string();
+    SkiArea::new(
+        create_ski_area_metadata("Test".to_string()),
+        HashMap::from([("l1".to_string(), lift)]),
+        HashMap::from([("p1".to_string(), piste)]),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+#[fixture]
+fn graph() -> Graph {
+    let edge = |from, to, kind| Edge {
+        from,
+        to,
+        kind,
+        length: 1000.0,
+        line: line(&[(6.0, 45.0), (6.0, 45.01)]),
+    };
+    Graph {
+        nodes: vec![
+            Node {
+                point: point! { x: 6.0, y: 45.0 },
+                elevation: Some(1000.0),
+            },
+            Node {
+                point: point! { x: 6.0, y: 45.01 },
+                elevation: None,
+            },
+        ],
+        edges: vec![
+            edge(
+                0,
+                1,
+                EdgeKind::Lift {
+                    lift_id: "l1".to_string(),
+                    begin_station: 0,
+                    end_station: 1,
+                },
+            ),
+            edge(
+                1,
+                0,
+                EdgeKind::Piste {
+                    piste_id: "p1".to_string(),
+                },
+            ),
+            edge(1, 0, EdgeKind::Connection),
+        ],
+    }
+}
+
+#[rstest]
+fn dot(_init: Init, ski_area: SkiArea, graph: Graph) {
+    let actual = graph_to_dot(&ski_area, &graph);
+    let lines: Vec<&str> = actual.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "digraph \"Test\" {",
+            "  n0 [label=\"0\\n1000 m\"];",
+            "  n1 [label=\"1\"];",
+            "  n0 -> n1 [label=\"A\", style=bold];",
+            "  n1 -> n0 [label=\"Red & \\\"Black\\\"\", color=red];",
+            "  n1 -> n0 [label=\"\", style=dashed];",
+            "}",
+        ]
+    );
+}
+
+#[rstest]
+fn graphml(_init: Init, ski_area: SkiArea, graph: Graph) {
+    let actual = graph_to_graphml(&ski_area, &graph);
+    assert!(actual.contains("<graph id=\"Test\" edgedefault=\"directed\">"));
+    assert!(actual.contains(
+        "<node id=\"n0\"><data key=\"lon\">6</data><data key=\"lat\">45</data>\
+         <data key=\"elevation\">1000</data></node>"
+    ));
+    assert!(actual.contains(
+        "<edge source=\"n1\" target=\"n0\"><data key=\"kind\">piste</data>\
+         <data key=\"id\">p1</data>\
+         <data key=\"name\">Red &amp; &quot;Black&quot;</data>\
+         <data key=\"difficulty\">intermediate</data>\
+         <data key=\"length\">1000</data></edge>"
+    ));
+    assert!(actual.contains(
+        "<edge source=\"n1\" target=\"n0\"><data key=\"kind\">connection</data>\
+         <data key=\"length\">1000</data></edge>"
+    ));
+    assert_eq!(actual.matches("<edge ").count(), 3);
+}
+
+#[rstest]
+fn json(_init: Init, ski_area: SkiArea, graph: Graph) {
+    let actual = export_graph(&ski_area, &graph, GraphFormat::Json).unwrap();
+    let parsed: Graph = serde_json::from_str(&actual).unwrap();
+    assert_eq!(parsed.edges, graph.edges);
+}
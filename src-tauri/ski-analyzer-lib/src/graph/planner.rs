@@ -0,0 +1,434 @@
+use geo::{LineString, Point};
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::{Edge, EdgeKind, Graph};
+use crate::ski_area::{Difficulty, SkiArea};
+
+#[cfg(feature = "specta")]
+use crate::typescript_gen::geo::LineStringDef;
+
+/// The parameters for estimating the time needed to get through the ski
+/// area.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CostModel {
+    /// The speed of lifts without a known ride time, in m/s.
+    pub lift_speed: f64,
+    /// The time spent waiting for and getting on a lift, in seconds. It is
+    /// added once for each lift ride, riding through middle stations does
+    /// not add it again.
+    pub lift_boarding_time: f64,
+    /// The average skiing speed on pistes, in m/s.
+    pub ski_speed: f64,
+    /// The speed on connections between lifts and pistes, in m/s.
+    pub walk_speed: f64,
+    /// Makes harder pistes less preferred. The skiing time is multiplied by
+    /// `1 + difficulty_penalty * level` when choosing the route, where the
+    /// level is 0 for novice pistes and grows by one with each difficulty.
+    pub difficulty_penalty: f64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            lift_speed: 4.0,
+            lift_boarding_time: 60.0,
+            ski_speed: 8.0,
+            walk_speed: 1.0,
+            difficulty_penalty: 0.25,
+        }
+    }
+}
+
+/// The level of the difficulty, from 0 for novice. Unknown pistes are
+/// treated as intermediate.
+pub(crate) fn difficulty_level(difficulty: Difficulty) -> u32 {
+    match difficulty {
+        Difficulty::Novice => 0,
+        Difficulty::Easy => 1,
+        Difficulty::Intermediate | Difficulty::Unknown => 2,
+        Difficulty::Advanced => 3,
+        Difficulty::Expert => 4,
+        Difficulty::Freeride => 5,
+    }
+}
+
+pub(super) fn piste_difficulty(
+    ski_area: &SkiArea,
+    kind: &EdgeKind,
+) -> Option<Difficulty> {
+    match kind {
+        EdgeKind::Piste { piste_id } => Some(
+            ski_area
+                .pistes()
+                .get(piste_id)
+                .map_or(Difficulty::Unknown, |p| p.metadata.difficulty),
+        ),
+        _ => None,
+    }
+}
+
+impl CostModel {
+    /// The estimated time to go along the edge, in seconds. Getting on a lift
+    /// is not included, see [`Self::boarding_time`].
+    pub fn duration(&self, ski_area: &SkiArea, edge: &Edge) -> f64 {
+        match &edge.kind {
+            EdgeKind::Lift {
+                lift_id,
+                begin_station,
+                end_station,
+            } => ski_area
+                .lifts()
+                .get(lift_id)
+                .and_then(|l| {
+                    l.nominal_duration(Some(*begin_station), Some(*end_station))
+                })
+                .unwrap_or(edge.length / self.lift_speed),
+            EdgeKind::Piste { .. } => edge.length / self.ski_speed,
+            EdgeKind::Connection => edge.length / self.walk_speed,
+        }
+    }
+
+    /// The time needed to get on the lift of the edge, in seconds. It is zero
+    /// if the edge is not a lift, or if `previous` is the section of the same
+    /// lift that ends where the edge begins.
+    pub fn boarding_time(&self, previous: Option<&Edge>, edge: &Edge) -> f64 {
+        let EdgeKind::Lift {
+            lift_id,
+            begin_station,
+            ..
+        } = &edge.kind
+        else {
+            return 0.0;
+        };
+        match previous.map(|e| &e.kind) {
+            Some(EdgeKind::Lift {
+                lift_id: previous_id,
+                end_station,
+                ..
+            }) if previous_id == lift_id && end_station == begin_station => 0.0,
+            _ => self.lift_boarding_time,
+        }
+    }
+
+    /// The duration of the edge with the difficulty penalty applied, when
+    /// coming from `previous`.
+    fn cost(
+        &self,
+        ski_area: &SkiArea,
+        previous: Option<&Edge>,
+        edge: &Edge,
+    ) -> f64 {
+        let duration = self.duration(ski_area, edge);
+        let boarding_time = self.boarding_time(previous, edge);
+        match piste_difficulty(ski_area, &edge.kind) {
+            Some(d) => {
+                duration
+                    * (1.0
+                        + self.difficulty_penalty
+                            * f64::from(difficulty_level(d)))
+            }
+            None => duration + boarding_time,
+        }
+    }
+
+    /// The cost of the edge when coming from `previous`, if it can be used
+    /// with the given difficulties. All pistes can be used if `difficulties`
+    /// is empty. Difficulties of the same level are interchangeable, so
+    /// unknown pistes can be used where intermediate ones can.
+    pub(super) fn allowed_cost(
+        &self,
+        ski_area: &SkiArea,
+        difficulties: &[Difficulty],
+        previous: Option<&Edge>,
+        edge: &Edge,
+    ) -> Option<f64> {
+        match piste_difficulty(ski_area, &edge.kind) {
+            Some(d)
+                if !difficulties.is_empty()
+                    && !difficulties.iter().any(|allowed| {
+                        difficulty_level(*allowed) == difficulty_level(d)
+                    }) =>
+            {
+                None
+            }
+            _ => Some(self.cost(ski_area, previous, edge)),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+struct Candidate {
+    cost: f64,
+    node: usize,
+    /// The edge that the node was reached through. None for start nodes.
+    edge: Option<usize>,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    // Reversed, so that BinaryHeap pops the cheapest candidate first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.node.cmp(&self.node))
+            .then_with(|| other.edge.cmp(&self.edge))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The cheapest ways from any of the start nodes to every other node.
+pub(crate) struct ShortestPaths {
+    pub costs: Vec<f64>,
+    /// The last edge of the cheapest path to each node.
+    pub previous: Vec<Option<usize>>,
+    /// The edge before each edge on the cheapest path through it.
+    previous_edges: Vec<Option<usize>>,
+}
+
+impl ShortestPaths {
+    /// Dijkstra's algorithm. Edges without a cost can't be used. The cost of
+    /// an edge may depend on the edge before it on the path, which is None
+    /// at the start nodes, so the cheapest path is searched to each edge
+    /// instead of each node.
+    pub fn new<F>(graph: &Graph, starts: &[usize], edge_cost: F) -> Self
+    where
+        F: Fn(Option<&Edge>, &Edge) -> Option<f64>,
+    {
+        let outgoing = graph.outgoing_edges();
+        let mut costs = vec![f64::INFINITY; graph.nodes.len()];
+        let mut previous = vec![None; graph.nodes.len()];
+        let mut edge_costs = vec![f64::INFINITY; graph.edges.len()];
+        let mut previous_edges = vec![None; graph.edges.len()];
+        let mut queue = BinaryHeap::new();
+        for start in starts {
+            costs[*start] = 0.0;
+            queue.push(Candidate {
+                cost: 0.0,
+                node: *start,
+                edge: None,
+            });
+        }
+        while let Some(Candidate { cost, node, edge }) = queue.pop() {
+            let best = edge.map_or(0.0, |e| edge_costs[e]);
+            if cost > best {
+                continue;
+            }
+            for edge_id in &outgoing[node] {
+                let next = &graph.edges[*edge_id];
+                let Some(edge_cost) =
+                    edge_cost(edge.map(|e| &graph.edges[e]), next)
+                else {
+                    continue;
+                };
+                let new_cost = cost + edge_cost;
+                if new_cost < edge_costs[*edge_id] {
+                    edge_costs[*edge_id] = new_cost;
+                    previous_edges[*edge_id] = edge;
+                    if new_cost < costs[next.to] {
+                        costs[next.to] = new_cost;
+                        previous[next.to] = Some(*edge_id);
+                    }
+                    queue.push(Candidate {
+                        cost: new_cost,
+                        node: next.to,
+                        edge: Some(*edge_id),
+                    });
+                }
+            }
+        }
+        ShortestPaths {
+            costs,
+            previous,
+            previous_edges,
+        }
+    }
+
+    pub fn is_reachable(&self, node: usize) -> bool {
+        self.costs[node].is_finite()
+    }
+
+    /// The edges of the cheapest path to the node.
+    pub fn path(&self, node: usize) -> Option<Vec<usize>> {
+        if !self.is_reachable(node) {
+            return None;
+        }
+        let mut result = Vec::new();
+        let mut current = self.previous[node];
+        while let Some(edge_id) = current {
+            result.push(edge_id);
+            current = self.previous_edges[edge_id];
+        }
+        result.reverse();
+        Some(result)
+    }
+}
+
+/// A lift ride, a run on a piste or a connection between them.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RouteStep {
+    pub kind: EdgeKind,
+    #[cfg_attr(feature = "specta", specta(type = LineStringDef))]
+    pub line: LineString,
+    /// In meters.
+    pub length: f64,
+    /// Estimated, in seconds.
+    pub duration: f64,
+}
+
+impl RouteStep {
+    /// The step along the edge when coming from `previous`, which decides
+    /// whether getting on a lift is needed.
+    pub(super) fn new(
+        ski_area: &SkiArea,
+        cost_model: &CostModel,
+        previous: Option<&Edge>,
+        edge: &Edge,
+    ) -> Self {
+        RouteStep {
+            kind: edge.kind.clone(),
+            line: edge.line.clone(),
+            length: edge.length,
+            duration: cost_model.duration(ski_area, edge)
+                + cost_model.boarding_time(previous, edge),
+        }
+    }
+
+    /// The steps along the edges of the path. `previous` is the edge before
+    /// the first one, if any.
+    pub(super) fn for_path<'g>(
+        ski_area: &SkiArea,
+        graph: &'g Graph,
+        cost_model: &CostModel,
+        mut previous: Option<&'g Edge>,
+        path: &[usize],
+    ) -> Vec<RouteStep> {
+        path.iter()
+            .map(|e| {
+                let edge = &graph.edges[*e];
+                let step = RouteStep::new(ski_area, cost_model, previous, edge);
+                previous = Some(edge);
+                step
+            })
+            .collect()
+    }
+
+    /// Extends the step with the next edge if it continues the same lift,
+    /// piste or connection.
+    fn try_extend(&mut self, step: &RouteStep) -> bool {
+        match (&mut self.kind, &step.kind) {
+            (
+                EdgeKind::Lift {
+                    lift_id,
+                    end_station,
+                    ..
+                },
+                EdgeKind::Lift {
+                    lift_id: next_id,
+                    begin_station,
+                    end_station: next_end,
+                },
+            ) if lift_id == next_id && end_station == begin_station => {
+                *end_station = *next_end;
+            }
+            (
+                EdgeKind::Piste { piste_id },
+                EdgeKind::Piste { piste_id: next_id },
+            ) if piste_id == next_id => (),
+            (EdgeKind::Connection, EdgeKind::Connection) => (),
+            _ => return false,
+        }
+        let skip = usize::from(self.line.0.last() == step.line.0.first());
+        self.line.0.extend_from_slice(&step.line.0[skip..]);
+        self.length += step.length;
+        self.duration += step.duration;
+        true
+    }
+}
+
+/// The result of route planning.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlannedRoute {
+    pub steps: Vec<RouteStep>,
+    /// In meters.
+    pub length: f64,
+    /// Estimated, in seconds.
+    pub duration: f64,
+    /// The hardest piste on the route. None if the route has no pistes.
+    pub hardest_difficulty: Option<Difficulty>,
+}
+
+/// The hardest of the difficulties. Of equally hard ones, the first is
+/// returned.
+pub(super) fn hardest<I>(difficulties: I) -> Option<Difficulty>
+where
+    I: IntoIterator<Item = Difficulty>,
+{
+    difficulties
+        .into_iter()
+        .fold(None, |result, d| match result {
+            Some(h) if difficulty_level(h) >= difficulty_level(d) => Some(h),
+            _ => Some(d),
+        })
+}
+
+impl PlannedRoute {
+    /// Joins consecutive steps on the same lift, piste or connection.
+    pub(super) fn new<I>(ski_area: &SkiArea, route_steps: I) -> Self
+    where
+        I: IntoIterator<Item = RouteStep>,
+    {
+        let mut steps: Vec<RouteStep> = Vec::new();
+        for step in route_steps {
+            if !steps.last_mut().is_some_and(|s| s.try_extend(&step)) {
+                steps.push(step);
+            }
+        }
+        PlannedRoute {
+            length: steps.iter().map(|s| s.length).sum(),
+            duration: steps.iter().map(|s| s.duration).sum(),
+            hardest_difficulty: hardest(
+                steps
+                    .iter()
+                    .filter_map(|s| piste_difficulty(ski_area, &s.kind)),
+            ),
+            steps,
+        }
+    }
+}
+
+/// Finds the fastest route between the nodes closest to the given points,
+/// using only pistes with the given difficulties. All pistes can be used if
+/// `difficulties` is empty. Returns None if there is no such route.
+pub fn plan_route(
+    ski_area: &SkiArea,
+    graph: &Graph,
+    from: Point,
+    to: Point,
+    difficulties: &[Difficulty],
+    cost_model: &CostModel,
+) -> Option<PlannedRoute> {
+    let start = graph.closest_node(from)?;
+    let end = graph.closest_node(to)?;
+    let paths = ShortestPaths::new(graph, &[start], |previous, edge| {
+        cost_model.allowed_cost(ski_area, difficulties, previous, edge)
+    });
+    let path = paths.path(end)?;
+    Some(PlannedRoute::new(
+        ski_area,
+        RouteStep::for_path(ski_area, graph, cost_model, None, &path),
+    ))
+}
@@ -0,0 +1,207 @@
+use super::{plan_route, CostModel, EdgeKind, Graph, PlannedRoute};
+use crate::ski_area::{Difficulty, PointWithElevation, SkiArea};
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{
+    create_ski_area, init, lift, simple_piste, Init,
+};
+
+use geo::Point;
+use rstest::{fixture, rstest};
+
+fn describe(route: &PlannedRoute) -> Vec<String> {
+    route
+        .steps
+        .iter()
+        .map(|s| match &s.kind {
+            EdgeKind::Lift {
+                lift_id,
+                begin_station,
+                end_station,
+            } => format!("lift {} {}-{}", lift_id, begin_station, end_station),
+            EdgeKind::Piste { piste_id } => format!("piste {}", piste_id),
+            EdgeKind::Connection => "connection".to_string(),
+        })
+        .collect()
+}
+
+/// A lift with a short red and a longer blue piste from the top station to
+/// the same place.
+#[fixture]
+fn two_pistes() -> SkiArea {
+    create_ski_area(
+        vec![("l1", lift("Lift", &[(6.0, 45.0), (6.0, 45.01)]))],
+        vec![
+            (
+                "blue",
+                simple_piste(
+                    "Blue",
+                    Difficulty::Easy,
+                    &[(6.0, 45.01), (6.01, 45.01), (6.01, 45.005)],
+                ),
+            ),
+            (
+                "red",
+                simple_piste(
+                    "Red",
+                    Difficulty::Intermediate,
+                    &[(6.0, 45.01), (6.01, 45.005)],
+                ),
+            ),
+        ],
+    )
+}
+
+#[rstest]
+#[case::all(vec![], Some(vec!["lift l1 0-1", "piste red"]))]
+#[case::easy(
+    vec![Difficulty::Novice, Difficulty::Easy],
+    Some(vec!["lift l1 0-1", "piste blue"])
+)]
+#[case::novice(vec![Difficulty::Novice], None)]
+#[case::unknown(vec![Difficulty::Unknown], Some(vec!["lift l1 0-1", "piste red"]))]
+fn allowed_difficulties(
+    _init: Init,
+    two_pistes: SkiArea,
+    #[case] difficulties: Vec<Difficulty>,
+    #[case] expected: Option<Vec<&str>>,
+) {
+    let graph =
+        Graph::new(&CancellationToken::new(), &two_pistes, None).unwrap();
+    let route = plan_route(
+        &two_pistes,
+        &graph,
+        Point::new(6.0001, 45.0),
+        Point::new(6.0099, 45.005),
+        &difficulties,
+        &CostModel::default(),
+    );
+    assert_eq!(
+        route.as_ref().map(describe),
+        expected.map(|e| e.into_iter().map(String::from).collect())
+    );
+}
+
+#[rstest]
+fn durations(_init: Init, mut two_pistes: SkiArea) {
+    two_pistes
+        .lifts_mut()
+        .get_mut("l1")
+        .unwrap()
+        .attributes
+        .duration = Some(300.0);
+    let graph =
+        Graph::new(&CancellationToken::new(), &two_pistes, None).unwrap();
+    let cost_model = CostModel {
+        lift_boarding_time: 30.0,
+        ski_speed: 10.0,
+        ..Default::default()
+    };
+    let route = plan_route(
+        &two_pistes,
+        &graph,
+        Point::new(6.0, 45.0),
+        Point::new(6.01, 45.005),
+        &[Difficulty::Easy],
+        &cost_model,
+    )
+    .unwrap();
+    assert_eq!(route.steps.len(), 2);
+    assert_eq!(route.steps[0].duration, 330.0);
+    assert_eq!(route.steps[1].duration, route.steps[1].length / 10.0);
+    assert_eq!(route.steps[1].line.0.len(), 3);
+    assert!(
+        (route.length - 1112.0 - 1342.0).abs() < 20.0,
+        "length={}",
+        route.length
+    );
+    assert_eq!(
+        route.duration,
+        route.steps[0].duration + route.steps[1].duration
+    );
+}
+
+#[rstest]
+fn boarding_once_per_ride(_init: Init) {
+    let mut l1 = lift("Lift", &[(6.0, 45.0), (6.0, 45.005), (6.0, 45.01)]);
+    l1.stations
+        .insert(1, PointWithElevation::new(Point::new(6.0, 45.005), None));
+    l1.lengths = vec![556.0, 556.0];
+    l1.attributes.duration = Some(300.0);
+    let ski_area = create_ski_area(vec![("l1", l1)], vec![]);
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    let cost_model = CostModel {
+        lift_boarding_time: 30.0,
+        ..Default::default()
+    };
+    let route = plan_route(
+        &ski_area,
+        &graph,
+        Point::new(6.0, 45.0),
+        Point::new(6.0, 45.01),
+        &[],
+        &cost_model,
+    )
+    .unwrap();
+    assert_eq!(describe(&route), vec!["lift l1 0-2"]);
+    assert_eq!(route.duration, 330.0);
+}
+
+#[rstest]
+fn merged_steps(_init: Init) {
+    let ski_area = create_ski_area(
+        vec![("l1", lift("Lift", &[(6.0, 45.0), (6.0, 45.01)]))],
+        vec![
+            (
+                "p1",
+                simple_piste(
+                    "Blue",
+                    Difficulty::Easy,
+                    &[(6.0, 45.01), (6.002, 45.005), (6.0, 45.0)],
+                ),
+            ),
+            // Cuts p1 in two.
+            (
+                "p2",
+                simple_piste(
+                    "Red",
+                    Difficulty::Intermediate,
+                    &[(6.002, 45.005), (6.006, 45.0)],
+                ),
+            ),
+        ],
+    );
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    let route = plan_route(
+        &ski_area,
+        &graph,
+        Point::new(6.0, 45.01),
+        Point::new(6.0, 45.0),
+        &[],
+        &CostModel::default(),
+    )
+    .unwrap();
+    assert_eq!(describe(&route), vec!["piste p1"]);
+    let line = &route.steps[0].line.0;
+    assert_eq!(line[0], (6.0, 45.01).into());
+    assert_eq!(line[line.len() - 1], (6.0, 45.0).into());
+}
+
+#[rstest]
+fn lift_downwards(_init: Init) {
+    let ski_area = create_ski_area(
+        vec![("l1", lift("Lift", &[(6.0, 45.0), (6.0, 45.01)]))],
+        vec![],
+    );
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    assert_eq!(
+        plan_route(
+            &ski_area,
+            &graph,
+            Point::new(6.0, 45.01),
+            Point::new(6.0, 45.0),
+            &[],
+            &CostModel::default(),
+        ),
+        None
+    );
+}
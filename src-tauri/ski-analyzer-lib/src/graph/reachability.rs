@@ -0,0 +1,261 @@
+use geo::{Distance, Haversine, Length, Point};
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::planner::{
+    difficulty_level, hardest, piste_difficulty, CostModel, PlannedRoute,
+    RouteStep, ShortestPaths,
+};
+use super::{
+    locate, sub_line, Edge, EdgeKind, Graph, LinePosition, MERGE_DISTANCE,
+};
+use crate::ski_area::{Difficulty, SkiArea};
+
+/// All difficulties, from the easiest.
+const DIFFICULTIES: [Difficulty; 7] = [
+    Difficulty::Novice,
+    Difficulty::Easy,
+    Difficulty::Intermediate,
+    Difficulty::Unknown,
+    Difficulty::Advanced,
+    Difficulty::Expert,
+    Difficulty::Freeride,
+];
+
+fn difficulties_up_to(level: u32) -> Vec<Difficulty> {
+    DIFFICULTIES
+        .iter()
+        .copied()
+        .filter(|d| difficulty_level(*d) <= level)
+        .collect()
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReachablePiste {
+    pub piste_id: String,
+    /// The hardest piste on the easiest way to this piste, including the
+    /// piste itself.
+    pub hardest_difficulty: Difficulty,
+}
+
+/// The pistes and lifts that can be reached after taking a lift.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Reachable {
+    pub lift_id: String,
+    /// Ordered by ID.
+    pub pistes: Vec<ReachablePiste>,
+    /// The IDs of the other lifts that can be reached, ordered.
+    pub lifts: Vec<String>,
+    /// The hardest of the reachable pistes. None if there are none.
+    pub hardest_difficulty: Option<Difficulty>,
+}
+
+/// Finds the pistes that can be reached from the lift without going on
+/// pistes harder than `max_difficulty`. Pistes of unknown difficulty are
+/// treated as intermediate. Other lifts can also be used. Returns None if the
+/// lift is not in the graph.
+pub fn reachable_from_lift(
+    ski_area: &SkiArea,
+    graph: &Graph,
+    lift_id: &str,
+    max_difficulty: Difficulty,
+    cost_model: &CostModel,
+) -> Option<Reachable> {
+    let starts: Vec<usize> = graph
+        .edges
+        .iter()
+        .filter(|e| {
+            matches!(&e.kind, EdgeKind::Lift { lift_id: id, .. } if id == lift_id)
+        })
+        .map(|e| e.to)
+        .collect();
+    if starts.is_empty() {
+        return None;
+    }
+
+    let mut pistes: BTreeMap<&str, Difficulty> = BTreeMap::new();
+    let mut lifts: BTreeSet<&str> = BTreeSet::new();
+    // Easier levels first, so that each piste is found on its easiest way.
+    for level in 0..=difficulty_level(max_difficulty) {
+        let difficulties = difficulties_up_to(level);
+        let paths = ShortestPaths::new(graph, &starts, |previous, e| {
+            cost_model.allowed_cost(ski_area, &difficulties, previous, e)
+        });
+        for edge in &graph.edges {
+            if !paths.is_reachable(edge.from) {
+                continue;
+            }
+            match &edge.kind {
+                EdgeKind::Piste { piste_id } => {
+                    let difficulty = piste_difficulty(ski_area, &edge.kind)
+                        .unwrap_or(Difficulty::Unknown);
+                    if pistes.contains_key(piste_id.as_str())
+                        || !difficulties.contains(&difficulty)
+                    {
+                        continue;
+                    }
+                    let path = paths.path(edge.from).unwrap_or_default();
+                    let hardest_difficulty = hardest(
+                        path.iter()
+                            .filter_map(|e| {
+                                piste_difficulty(
+                                    ski_area,
+                                    &graph.edges[*e].kind,
+                                )
+                            })
+                            .chain([difficulty]),
+                    )
+                    .unwrap_or(difficulty);
+                    pistes.insert(piste_id, hardest_difficulty);
+                }
+                EdgeKind::Lift { lift_id: id, .. } if id != lift_id => {
+                    lifts.insert(id);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    Some(Reachable {
+        lift_id: lift_id.to_string(),
+        hardest_difficulty: hardest(pistes.values().copied()),
+        pistes: pistes
+            .into_iter()
+            .map(|(piste_id, hardest_difficulty)| ReachablePiste {
+                piste_id: piste_id.to_string(),
+                hardest_difficulty,
+            })
+            .collect(),
+        lifts: lifts.into_iter().map(|id| id.to_string()).collect(),
+    })
+}
+
+/// The bottom stations of lifts in the valley. Bottom stations are grouped
+/// by which ones can reach each other without taking a lift, and the groups
+/// that have no way further down to another group are in the valley. Stations
+/// next to each other are connected both ways, so they are in the same
+/// group.
+fn valley_nodes(graph: &Graph) -> Vec<usize> {
+    let mut bottoms: Vec<usize> = graph
+        .edges
+        .iter()
+        .filter_map(|e| match e.kind {
+            EdgeKind::Lift {
+                begin_station: 0, ..
+            } => Some(e.from),
+            _ => None,
+        })
+        .collect();
+    bottoms.sort();
+    bottoms.dedup();
+    let downhill: Vec<ShortestPaths> = bottoms
+        .iter()
+        .map(|bottom| {
+            ShortestPaths::new(graph, &[*bottom], |_, e| match e.kind {
+                EdgeKind::Lift { .. } => None,
+                _ => Some(0.0),
+            })
+        })
+        .collect();
+    bottoms
+        .iter()
+        .enumerate()
+        .filter(|(i, bottom)| {
+            bottoms.iter().enumerate().all(|(j, other)| {
+                !downhill[*i].is_reachable(*other)
+                    || downhill[j].is_reachable(**bottom)
+            })
+        })
+        .map(|(_, bottom)| *bottom)
+        .collect()
+}
+
+/// Finds where the position is on the graph. Returns the node to continue
+/// from, and if the position is not at a node, the edge it is on and the
+/// rest of that edge until the node. Lifts are only considered if there is
+/// no piste within `max_distance`, because lifts often go above pistes.
+fn find_start<'g>(
+    ski_area: &SkiArea,
+    graph: &'g Graph,
+    cost_model: &CostModel,
+    p: Point,
+    max_distance: f64,
+) -> Option<(usize, Option<(&'g Edge, RouteStep)>)> {
+    if let Some(node) = graph.closest_node(p).filter(|n| {
+        Haversine::distance(graph.nodes[*n].point, p) <= MERGE_DISTANCE
+    }) {
+        return Some((node, None));
+    }
+    let closest_edge = |lifts: bool| {
+        graph
+            .edges
+            .iter()
+            .filter(|e| matches!(e.kind, EdgeKind::Lift { .. }) == lifts)
+            .filter_map(|e| {
+                let (position, distance) = locate(&e.line, p)?;
+                (distance <= max_distance).then_some((e, position, distance))
+            })
+            .min_by(|(_, _, d1), (_, _, d2)| d1.total_cmp(d2))
+    };
+    let (edge, position, _) =
+        closest_edge(false).or_else(|| closest_edge(true))?;
+    let last_segment = edge.line.0.len() - 2;
+    let end = LinePosition {
+        segment: last_segment,
+        offset: f64::INFINITY,
+        point: edge.line.0[last_segment + 1].into(),
+    };
+    let mut step = RouteStep::new(ski_area, cost_model, None, edge);
+    step.line = sub_line(&edge.line, &position, &end);
+    step.length = step.line.length::<Haversine>();
+    // Already on the lift, so there is no need to get on it.
+    step.duration = cost_model.duration(ski_area, edge);
+    if edge.length > 0.0 {
+        step.duration *= step.length / edge.length;
+    }
+    Some((edge.to, Some((edge, step))))
+}
+
+/// Finds the easiest way from the position to the bottom station of a lift
+/// in the valley: the one with the easiest hardest piste, and of those the
+/// fastest one. Lifts can also be used. The position must be at most
+/// `max_distance` meters from a lift or piste. Returns None if there is no
+/// way down.
+pub fn way_to_valley(
+    ski_area: &SkiArea,
+    graph: &Graph,
+    position: Point,
+    max_distance: f64,
+    cost_model: &CostModel,
+) -> Option<PlannedRoute> {
+    let (start, first_step) =
+        find_start(ski_area, graph, cost_model, position, max_distance)?;
+    let first_edge = first_step.as_ref().map(|(e, _)| *e);
+    let valley = valley_nodes(graph);
+    let max_level = difficulty_level(Difficulty::Freeride);
+    (0..=max_level).find_map(|level| {
+        let difficulties = difficulties_up_to(level);
+        // Only the edges from the start have no previous edge.
+        let paths = ShortestPaths::new(graph, &[start], |previous, e| {
+            let previous = previous.or(first_edge);
+            cost_model.allowed_cost(ski_area, &difficulties, previous, e)
+        });
+        let end = valley
+            .iter()
+            .copied()
+            .filter(|n| paths.is_reachable(*n))
+            .min_by(|n1, n2| paths.costs[*n1].total_cmp(&paths.costs[*n2]))?;
+        let path = paths.path(end)?;
+        Some(PlannedRoute::new(
+            ski_area,
+            first_step.iter().map(|(_, s)| s.clone()).chain(
+                RouteStep::for_path(
+                    ski_area, graph, cost_model, first_edge, &path,
+                ),
+            ),
+        ))
+    })
+}
@@ -0,0 +1,219 @@
+use super::planner::difficulty_level;
+use super::{
+    reachable_from_lift, way_to_valley, CostModel, EdgeKind, Graph,
+    ReachablePiste,
+};
+use crate::ski_area::{Difficulty, Lift, SkiArea};
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{
+    create_ski_area, init, lift, simple_piste, Coord, Init,
+};
+
+use geo::Point;
+use rstest::{fixture, rstest};
+
+/// Lift l1 goes from the valley (A) to B, lift l2 from C to D. The only easy
+/// ways down are from B and from C. C can only be reached from B on a black
+/// piste.
+#[fixture]
+fn ski_area() -> SkiArea {
+    let a = (6.0, 45.0);
+    let b = (6.0, 45.01);
+    let c = (6.01, 45.01);
+    let d = (6.01, 45.02);
+    let lifts: Vec<(&str, Lift)> =
+        vec![("l1", lift("L1", &[a, b])), ("l2", lift("L2", &[c, d]))];
+    let pistes = vec![
+        (
+            "easy",
+            simple_piste("Easy", Difficulty::Easy, &[b, (6.003, 45.005), a]),
+        ),
+        (
+            "black",
+            simple_piste("Black", Difficulty::Advanced, &[b, c]),
+        ),
+        ("blue", simple_piste("Blue", Difficulty::Easy, &[c, a])),
+        (
+            "red",
+            simple_piste("Red", Difficulty::Intermediate, &[d, c]),
+        ),
+        (
+            "freeride",
+            simple_piste("Freeride", Difficulty::Freeride, &[d, a]),
+        ),
+    ];
+    create_ski_area(lifts, pistes)
+}
+
+fn reachable(id: &str, hardest_difficulty: Difficulty) -> ReachablePiste {
+    ReachablePiste {
+        piste_id: id.to_string(),
+        hardest_difficulty,
+    }
+}
+
+#[rstest]
+#[case::novice("l1", Difficulty::Novice, vec![], vec![])]
+#[case::easy(
+    "l1",
+    Difficulty::Easy,
+    vec![reachable("easy", Difficulty::Easy)],
+    vec![]
+)]
+#[case::advanced(
+    "l1",
+    Difficulty::Advanced,
+    vec![
+        reachable("black", Difficulty::Advanced),
+        reachable("blue", Difficulty::Advanced),
+        reachable("easy", Difficulty::Easy),
+        reachable("red", Difficulty::Advanced),
+    ],
+    vec!["l2"]
+)]
+#[case::upper_lift_easy("l2", Difficulty::Easy, vec![], vec![])]
+#[case::upper_lift_intermediate(
+    "l2",
+    Difficulty::Intermediate,
+    vec![
+        reachable("blue", Difficulty::Intermediate),
+        reachable("easy", Difficulty::Intermediate),
+        reachable("red", Difficulty::Intermediate),
+    ],
+    vec!["l1"]
+)]
+fn reachable_pistes(
+    _init: Init,
+    ski_area: SkiArea,
+    #[case] lift_id: &str,
+    #[case] max_difficulty: Difficulty,
+    #[case] expected_pistes: Vec<ReachablePiste>,
+    #[case] expected_lifts: Vec<&str>,
+) {
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    let actual = reachable_from_lift(
+        &ski_area,
+        &graph,
+        lift_id,
+        max_difficulty,
+        &CostModel::default(),
+    )
+    .unwrap();
+    assert_eq!(actual.lift_id, lift_id);
+    assert_eq!(
+        actual.hardest_difficulty,
+        expected_pistes
+            .iter()
+            .map(|p| p.hardest_difficulty)
+            .max_by_key(|d| difficulty_level(*d))
+    );
+    assert_eq!(actual.pistes, expected_pistes);
+    assert_eq!(actual.lifts, expected_lifts);
+}
+
+#[rstest]
+fn unknown_lift(_init: Init, ski_area: SkiArea) {
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    assert_eq!(
+        reachable_from_lift(
+            &ski_area,
+            &graph,
+            "l3",
+            Difficulty::Expert,
+            &CostModel::default(),
+        ),
+        None
+    );
+}
+
+#[rstest]
+#[case::top_of_upper_lift(
+    (6.01, 45.02),
+    Some((vec!["piste red", "piste blue"], Difficulty::Intermediate))
+)]
+#[case::on_piste(
+    (6.0101, 45.015),
+    Some((vec!["piste red", "piste blue"], Difficulty::Intermediate))
+)]
+#[case::top_of_lower_lift(
+    (6.0, 45.01),
+    Some((vec!["piste easy"], Difficulty::Easy))
+)]
+#[case::on_lower_lift(
+    (6.0001, 45.005),
+    Some((vec!["lift l1", "piste easy"], Difficulty::Easy))
+)]
+#[case::valley((6.0, 45.0), Some((vec![], Difficulty::Unknown)))]
+#[case::too_far((6.1, 45.1), None)]
+fn way_down(
+    _init: Init,
+    ski_area: SkiArea,
+    #[case] position: Coord,
+    #[case] expected: Option<(Vec<&str>, Difficulty)>,
+) {
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    let actual = way_to_valley(
+        &ski_area,
+        &graph,
+        Point::new(position.0, position.1),
+        100.0,
+        &CostModel::default(),
+    )
+    .map(|route| {
+        let steps: Vec<String> = route
+            .steps
+            .iter()
+            .map(|s| match &s.kind {
+                EdgeKind::Lift { lift_id, .. } => format!("lift {}", lift_id),
+                EdgeKind::Piste { piste_id } => format!("piste {}", piste_id),
+                EdgeKind::Connection => "connection".to_string(),
+            })
+            .collect();
+        (
+            steps,
+            route.hardest_difficulty.unwrap_or(Difficulty::Unknown),
+        )
+    });
+    assert_eq!(
+        actual,
+        expected.map(|(steps, difficulty)| (
+            steps.into_iter().map(String::from).collect(),
+            difficulty
+        ))
+    );
+}
+
+/// Without elevation data, two pistes between the bottom stations of l1 and
+/// l2 can be drawn in opposite directions. Both stations are still in the
+/// valley.
+#[rstest]
+fn valley_lifts_reaching_each_other(_init: Init) {
+    let a = (6.0, 45.0);
+    let b = (6.0, 45.01);
+    let c = (6.01, 45.0);
+    let d = (6.01, 45.01);
+    let ski_area = create_ski_area(
+        vec![("l1", lift("L1", &[a, b])), ("l2", lift("L2", &[c, d]))],
+        vec![
+            ("down", simple_piste("Down", Difficulty::Easy, &[b, a])),
+            ("east", simple_piste("East", Difficulty::Easy, &[a, c])),
+            ("west", simple_piste("West", Difficulty::Easy, &[c, a])),
+        ],
+    );
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    let route = way_to_valley(
+        &ski_area,
+        &graph,
+        Point::new(b.0, b.1),
+        100.0,
+        &CostModel::default(),
+    )
+    .unwrap();
+    assert_eq!(route.steps.len(), 1);
+    assert_eq!(
+        route.steps[0].kind,
+        EdgeKind::Piste {
+            piste_id: "down".to_string()
+        }
+    );
+}
@@ -0,0 +1,192 @@
+use crate::dem::Dem;
+use crate::graph::{EdgeKind, Graph};
+use crate::ski_area::{Difficulty, PisteStatus};
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{
+    create_ski_area, init, lift, save_hgt, simple_piste, Init,
+};
+
+use rstest::rstest;
+
+use std::fs;
+
+fn describe(graph: &Graph) -> Vec<String> {
+    let mut result: Vec<String> = graph
+        .edges
+        .iter()
+        .map(|e| {
+            let kind = match &e.kind {
+                EdgeKind::Lift { lift_id, .. } => format!("lift {}", lift_id),
+                EdgeKind::Piste { piste_id } => format!("piste {}", piste_id),
+                EdgeKind::Connection => "connection".to_string(),
+            };
+            format!("{} {}->{}", kind, e.from, e.to)
+        })
+        .collect();
+    result.sort();
+    result
+}
+
+#[rstest]
+fn lifts_and_pistes(_init: Init) {
+    let p2 = simple_piste(
+        "Red",
+        Difficulty::Intermediate,
+        &[(6.002, 45.005), (6.006, 45.0)],
+    );
+    let ski_area = create_ski_area(
+        vec![("l1", lift("Lift", &[(6.0, 45.0), (6.0, 45.01)]))],
+        vec![
+            (
+                "p1",
+                simple_piste(
+                    "Blue",
+                    Difficulty::Easy,
+                    &[(6.0, 45.01), (6.002, 45.005), (6.0, 45.0)],
+                ),
+            ),
+            // Starts in the middle of p1.
+            ("p2", p2),
+            // Starts about 30 meters from the top station.
+            (
+                "p3",
+                simple_piste(
+                    "Far",
+                    Difficulty::Easy,
+                    &[(6.0004, 45.01), (6.01, 45.008)],
+                ),
+            ),
+        ],
+    );
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+
+    assert_eq!(graph.nodes.len(), 6);
+    assert_eq!(
+        describe(&graph),
+        vec![
+            "connection 1->4",
+            "connection 4->1",
+            "lift l1 0->1",
+            "piste p1 1->2",
+            "piste p1 2->0",
+            "piste p2 2->3",
+            "piste p3 4->5",
+        ]
+    );
+
+    let p1_top = graph
+        .edges
+        .iter()
+        .find(|e| e.from == 1 && e.to == 2)
+        .unwrap();
+    assert_eq!(p1_top.line.0.len(), 2);
+    assert!(
+        (p1_top.length - 578.0).abs() < 10.0,
+        "length={}",
+        p1_top.length
+    );
+
+    let outgoing = graph.outgoing_edges();
+    let mut from_top: Vec<_> =
+        outgoing[1].iter().map(|e| graph.edges[*e].to).collect();
+    from_top.sort();
+    assert_eq!(from_top, vec![2, 4]);
+}
+
+#[rstest]
+fn reverse_lift(_init: Init) {
+    let mut l1 = lift("Lift", &[(6.0, 45.0), (6.0, 45.005), (6.0, 45.01)]);
+    l1.can_go_reverse = true;
+    let ski_area = create_ski_area(vec![("l1", l1)], vec![]);
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    assert_eq!(describe(&graph), vec!["lift l1 0->1", "lift l1 1->0"]);
+    assert_eq!(
+        graph.edges[0].kind,
+        EdgeKind::Lift {
+            lift_id: "l1".to_string(),
+            begin_station: 1,
+            end_station: 0,
+        }
+    );
+    assert_eq!(graph.edges[0].line.0.len(), 3);
+}
+
+#[rstest]
+fn nearby_lifts(_init: Init) {
+    let mut abandoned =
+        simple_piste("Old", Difficulty::Easy, &[(6.0, 45.02), (6.0, 45.01)]);
+    abandoned.data.attributes.status = Some(PisteStatus::Abandoned);
+    let ski_area = create_ski_area(
+        vec![
+            ("l1", lift("Lower", &[(6.0, 45.0), (6.0, 45.01)])),
+            ("l2", lift("Upper", &[(6.0003, 45.01), (6.0, 45.02)])),
+        ],
+        vec![("p1", abandoned)],
+    );
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    assert_eq!(
+        describe(&graph),
+        vec![
+            "connection 1->2",
+            "connection 2->1",
+            "lift l1 0->1",
+            "lift l2 2->3",
+        ]
+    );
+}
+
+#[rstest]
+#[case::no_dem(false, 45.0)]
+#[case::dem(true, 45.01)]
+fn piste_direction(
+    _init: Init,
+    #[case] use_dem: bool,
+    #[case] expected_top: f64,
+) {
+    let dir = format!("test_output/graph_test/piste_direction_{}", use_dem);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    save_hgt(
+        &[&[2000, 2000], &[1000, 1000]],
+        &format!("{}/N45E006.hgt", dir),
+    );
+    let dem = Dem::open(&dir).unwrap();
+
+    // Drawn uphill.
+    let ski_area = create_ski_area(
+        vec![],
+        vec![(
+            "p1",
+            simple_piste("Up", Difficulty::Easy, &[(6.0, 45.0), (6.0, 45.01)]),
+        )],
+    );
+    let graph = Graph::new(
+        &CancellationToken::new(),
+        &ski_area,
+        Some(&dem).filter(|_| use_dem),
+    )
+    .unwrap();
+    assert_eq!(describe(&graph), vec!["piste p1 0->1"]);
+    assert_eq!(graph.nodes[0].point.y(), expected_top);
+    assert_eq!(graph.nodes[0].elevation.is_some(), use_dem);
+}
+
+#[rstest]
+fn serialize(_init: Init) {
+    let ski_area = create_ski_area(
+        vec![("l1", lift("Lift", &[(6.0, 45.0), (6.0, 45.01)]))],
+        vec![(
+            "p1",
+            simple_piste(
+                "Blue",
+                Difficulty::Easy,
+                &[(6.0, 45.01), (6.0, 45.0)],
+            ),
+        )],
+    );
+    let graph = Graph::new(&CancellationToken::new(), &ski_area, None).unwrap();
+    let json = serde_json::to_string(&graph).unwrap();
+    let actual: Graph = serde_json::from_str(&json).unwrap();
+    assert_eq!(actual.nodes, graph.nodes);
+    assert_eq!(actual.edges, graph.edges);
+}
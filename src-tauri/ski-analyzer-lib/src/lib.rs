@@ -1,6 +1,10 @@
 pub mod config;
+pub mod dem;
 pub mod error;
+pub mod geojson_export;
 pub mod gpx_analyzer;
+pub mod graph;
+pub mod osm_file;
 pub mod osm_query;
 pub mod osm_reader;
 pub mod ski_area;
@@ -11,7 +15,17 @@ mod multipolygon;
 #[cfg(feature = "specta")]
 pub mod typescript_gen;
 
+#[cfg(test)]
+mod dem_test;
+#[cfg(test)]
+mod geojson_export_test;
+#[cfg(test)]
+mod graph_test;
 #[cfg(test)]
 mod multipolygon_test;
 #[cfg(test)]
+mod osm_file_test;
+#[cfg(test)]
+mod osm_query_test;
+#[cfg(test)]
 mod osm_reader_test;
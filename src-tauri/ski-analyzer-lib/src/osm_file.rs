@@ -0,0 +1,398 @@
+use geo::{Intersects, LineString, Point, Polygon};
+use time::OffsetDateTime;
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::config::get_config;
+use crate::error::{Error, ErrorType, Result};
+use crate::osm_reader::{
+    get_tag, Document, Elements, Node, Osm3s, Relation, Tags, Way,
+};
+use crate::ski_area::is_downhill_mtb;
+use crate::utils::cancel::CancellationToken;
+
+mod pbf;
+mod xml;
+
+const COPYRIGHT: &str = "The data included in this document is from \
+    www.openstreetmap.org. The data is made available under ODbL.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Xml,
+    Pbf,
+}
+
+impl Format {
+    /// Detects whether a file is OSM XML or PBF by looking at its content.
+    pub fn detect(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let buf = reader.fill_buf()?;
+        let buf = buf.strip_prefix(b"\xef\xbb\xbf").unwrap_or(buf);
+        match buf.iter().find(|b| !b.is_ascii_whitespace()) {
+            None => Err(Error::new(
+                ErrorType::FormatError,
+                format!("empty OSM file: {}", path.display()),
+            )),
+            Some(b'<') => Ok(Format::Xml),
+            Some(_) => Ok(Format::Pbf),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ElementType {
+    Node,
+    Way,
+    Relation,
+}
+
+enum Element {
+    Node(u64, Node),
+    Way(u64, Way),
+    Relation(u64, Relation),
+}
+
+impl Element {
+    fn tags_mut(&mut self) -> &mut Tags {
+        match self {
+            Element::Node(_, node) => &mut node.tags,
+            Element::Way(_, way) => &mut way.tags,
+            Element::Relation(_, relation) => &mut relation.tags,
+        }
+    }
+}
+
+/// Calls `callback` with every element of the given type in the file.
+/// Returns the timestamp of the data if the file header contains it.
+fn read_elements<F>(
+    cancel: &CancellationToken,
+    path: &Path,
+    format: Format,
+    type_: ElementType,
+    mut callback: F,
+) -> Result<Option<OffsetDateTime>>
+where
+    F: FnMut(Element) -> Result<()>,
+{
+    let callback = |element| {
+        cancel.check()?;
+        callback(element)
+    };
+    match format {
+        Format::Xml => xml::read_elements(path, type_, callback),
+        Format::Pbf => pbf::read_elements(path, type_, callback),
+    }
+}
+
+fn read_ways<F>(
+    cancel: &CancellationToken,
+    path: &Path,
+    format: Format,
+    mut filter: F,
+) -> Result<(HashMap<u64, Way>, Option<OffsetDateTime>)>
+where
+    F: FnMut(u64, &Way) -> bool,
+{
+    let mut ways = HashMap::new();
+    let timestamp =
+        read_elements(cancel, path, format, ElementType::Way, |element| {
+            if let Element::Way(id, way) = element {
+                if filter(id, &way) {
+                    ways.insert(id, way);
+                }
+            }
+            Ok(())
+        })?;
+    Ok((ways, timestamp))
+}
+
+fn read_nodes(
+    cancel: &CancellationToken,
+    path: &Path,
+    format: Format,
+    ids: &HashSet<u64>,
+) -> Result<HashMap<u64, Node>> {
+    let mut nodes = HashMap::new();
+    read_elements(cancel, path, format, ElementType::Node, |element| {
+        if let Element::Node(id, node) = element {
+            if ids.contains(&id) {
+                nodes.insert(id, node);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(nodes)
+}
+
+fn create_document(
+    path: &Path,
+    timestamp: Option<OffsetDateTime>,
+    elements: Elements,
+) -> Result<Document> {
+    let timestamp_osm_base = match timestamp {
+        Some(t) => t,
+        None => File::open(path)?.metadata()?.modified()?.into(),
+    };
+    Ok(Document {
+        osm3s: Osm3s {
+            timestamp_osm_base,
+            copyright: COPYRIGHT.to_string(),
+        },
+        elements,
+    })
+}
+
+fn is_ski_area(tags: &Tags) -> bool {
+    get_tag(tags, "landuse") == "winter_sports"
+}
+
+/// `name` must be lowercase.
+fn is_ski_area_named(tags: &Tags, name: &str) -> bool {
+    is_ski_area(tags) && get_tag(tags, "name").to_lowercase().contains(name)
+}
+
+// These must select the same elements as query_ski_area_details_by_id().
+fn is_ski_area_way(tags: &Tags) -> bool {
+    tags.contains_key("aerialway")
+        || ["funicular", "rack", "subway"].contains(&get_tag(tags, "railway"))
+        || (tags.contains_key("railway") && tags.contains_key("rack"))
+        || is_piste(tags)
+        || is_downhill_mtb(tags)
+}
+
+fn is_ski_area_relation(tags: &Tags) -> bool {
+    is_piste(tags) || (get_tag(tags, "route") == "mtb" && is_downhill_mtb(tags))
+}
+
+fn is_piste(tags: &Tags) -> bool {
+    ["downhill", "nordic", "sled", "skitour"]
+        .contains(&get_tag(tags, "piste:type"))
+}
+
+fn to_line_string(nodes: &HashMap<u64, Node>, way: &Way) -> LineString {
+    LineString::new(
+        way.nodes
+            .iter()
+            .filter_map(|id| nodes.get(id))
+            .map(|node| node.coordinate.to_coord())
+            .collect(),
+    )
+}
+
+/// Loads all ski areas from an OSM file whose name contains `name`, case
+/// insensitively. Like query_ski_areas_by_name(), the result contains only
+/// the outlines with their geometry filled in.
+pub fn load_ski_areas_by_name(
+    cancel: &CancellationToken,
+    path: &Path,
+    name: &str,
+) -> Result<Document> {
+    let format = Format::detect(path)?;
+    let name = name.to_lowercase();
+    let (mut ways, timestamp) = read_ways(cancel, path, format, |_id, way| {
+        is_ski_area_named(&way.tags, &name)
+    })?;
+
+    let node_ids = ways.values().flat_map(|w| w.nodes.iter()).copied();
+    let nodes = read_nodes(cancel, path, format, &node_ids.collect())?;
+    for way in ways.values_mut() {
+        way.geometry = way
+            .nodes
+            .iter()
+            .filter_map(|id| nodes.get(id))
+            .map(|node| node.coordinate)
+            .collect();
+    }
+
+    if get_config().is_v() {
+        eprintln!("Found {} ski areas.", ways.len());
+    }
+
+    create_document(
+        path,
+        timestamp,
+        Elements {
+            ways,
+            ..Default::default()
+        },
+    )
+}
+
+/// Loads the outline, lifts and pistes of a ski area from an OSM file. The
+/// result is the same as what query_ski_area_details_by_id() would return.
+pub fn load_ski_area_details_by_id(
+    cancel: &CancellationToken,
+    path: &Path,
+    id: u64,
+) -> Result<Document> {
+    load_ski_area_details(cancel, path, |way_id, _way| way_id == id)
+}
+
+/// Like load_ski_area_details_by_id(), but the ski area is the only one whose
+/// name contains `name`, case insensitively. The file is only read once.
+pub fn load_ski_area_details_by_name(
+    cancel: &CancellationToken,
+    path: &Path,
+    name: &str,
+) -> Result<Document> {
+    let name = name.to_lowercase();
+    load_ski_area_details(cancel, path, |_id, way| {
+        is_ski_area_named(&way.tags, &name)
+    })
+}
+
+/// The outline of the ski area is the single way selected by `is_outline`.
+/// It is found in the same pass as the lifts and pistes.
+fn load_ski_area_details<F>(
+    cancel: &CancellationToken,
+    path: &Path,
+    is_outline: F,
+) -> Result<Document>
+where
+    F: Fn(u64, &Way) -> bool,
+{
+    let format = Format::detect(path)?;
+    let config = get_config();
+
+    let mut relations = HashMap::new();
+    let timestamp = read_elements(
+        cancel,
+        path,
+        format,
+        ElementType::Relation,
+        |element| {
+            if let Element::Relation(id, relation) = element {
+                if is_ski_area_relation(&relation.tags) {
+                    relations.insert(id, relation);
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    let member_ways: HashSet<u64> = relations
+        .values()
+        .flat_map(|r| r.members.ways.iter().map(|m| m.ref_))
+        .collect();
+    let mut outline_ids = Vec::new();
+    let (mut ways, _) = read_ways(cancel, path, format, |way_id, way| {
+        if is_outline(way_id, way) {
+            outline_ids.push(way_id);
+            return true;
+        }
+        is_ski_area_way(&way.tags) || member_ways.contains(&way_id)
+    })?;
+
+    let id = match outline_ids.as_slice() {
+        [id] => *id,
+        [] => {
+            return Err(Error::new_s(
+                ErrorType::InputError,
+                "ski area entity not found",
+            ))
+        }
+        _ => {
+            let names: Vec<_> = outline_ids
+                .iter()
+                .map(|id| get_tag(&ways[id].tags, "name"))
+                .collect();
+            return Err(Error::new(
+                ErrorType::InputError,
+                format!("ambiguous ski area: {:?}", names),
+            ));
+        }
+    };
+    let outline_way = &ways[&id];
+    if !is_ski_area(&outline_way.tags) {
+        return Err(Error::new(
+            ErrorType::InputError,
+            format!("way {} is not a ski area", id),
+        ));
+    }
+
+    let node_ids: HashSet<u64> = ways
+        .values()
+        .flat_map(|w| w.nodes.iter())
+        .chain(
+            relations
+                .values()
+                .flat_map(|r| r.members.nodes.iter().map(|m| &m.ref_)),
+        )
+        .copied()
+        .collect();
+    let mut nodes = read_nodes(cancel, path, format, &node_ids)?;
+
+    let outline = Polygon::new(to_line_string(&nodes, &ways[&id]), vec![]);
+    let is_inside_way = |way: &Way| {
+        let line = to_line_string(&nodes, way);
+        match line.0.as_slice() {
+            [] => false,
+            [coord] => outline.intersects(&Point::from(*coord)),
+            _ => outline.intersects(&line),
+        }
+    };
+
+    relations.retain(|_id, relation| {
+        relation
+            .members
+            .ways
+            .iter()
+            .filter_map(|m| ways.get(&m.ref_))
+            .any(is_inside_way)
+            || relation
+                .members
+                .nodes
+                .iter()
+                .filter_map(|m| nodes.get(&m.ref_))
+                .any(|n| outline.intersects(&n.coordinate.to_coord()))
+    });
+
+    let mut kept_ways: HashSet<u64> = relations
+        .values()
+        .flat_map(|r| r.members.ways.iter().map(|m| m.ref_))
+        .collect();
+    kept_ways.insert(id);
+    kept_ways.extend(
+        ways.iter()
+            .filter(|(_id, way)| {
+                is_ski_area_way(&way.tags) && is_inside_way(way)
+            })
+            .map(|(id, _way)| *id),
+    );
+    ways.retain(|id, _way| kept_ways.contains(id));
+
+    let kept_nodes: HashSet<u64> = ways
+        .values()
+        .flat_map(|w| w.nodes.iter())
+        .chain(
+            relations
+                .values()
+                .flat_map(|r| r.members.nodes.iter().map(|m| &m.ref_)),
+        )
+        .copied()
+        .collect();
+    nodes.retain(|id, _node| kept_nodes.contains(id));
+
+    if config.is_v() {
+        eprintln!(
+            "Loaded {} nodes, {} ways and {} relations.",
+            nodes.len(),
+            ways.len(),
+            relations.len()
+        );
+    }
+
+    create_document(
+        path,
+        timestamp,
+        Elements {
+            nodes,
+            ways,
+            relations,
+        },
+    )
+}
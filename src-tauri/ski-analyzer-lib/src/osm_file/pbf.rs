@@ -0,0 +1,485 @@
+use flate2::read::ZlibDecoder;
+use time::OffsetDateTime;
+
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+
+use super::{Element, ElementType};
+use crate::error::{Error, ErrorType, Result};
+use crate::osm_reader::{
+    Coordinate, Node, Relation, RelationMember, RelationMembers, Tags, Way,
+};
+
+const SUPPORTED_FEATURES: &[&str] = &["OsmSchema-V0.6", "DenseNodes"];
+
+fn format_error(msg: &'static str) -> Error {
+    Error::new_s(ErrorType::FormatError, msg)
+}
+
+// A minimal protobuf decoder that supports what the OSM PBF format needs.
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+impl<'a> Value<'a> {
+    fn as_varint(&self) -> Result<u64> {
+        match self {
+            Value::Varint(value) => Ok(*value),
+            _ => Err(format_error("expected varint field")),
+        }
+    }
+
+    fn as_bytes(&self) -> Result<&'a [u8]> {
+        match self {
+            Value::Bytes(value) => Ok(value),
+            _ => Err(format_error("expected length delimited field")),
+        }
+    }
+
+    fn as_string(&self) -> Result<String> {
+        Ok(String::from_utf8_lossy(self.as_bytes()?).into_owned())
+    }
+
+    fn as_packed(&self) -> Result<Vec<u64>> {
+        match self {
+            Value::Varint(value) => Ok(vec![*value]),
+            Value::Bytes(mut data) => {
+                let mut result = Vec::new();
+                while !data.is_empty() {
+                    result.push(read_varint(&mut data)?);
+                }
+                Ok(result)
+            }
+            Value::Fixed => Err(format_error("expected packed field")),
+        }
+    }
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64> {
+    let mut result = 0u64;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = data
+            .split_first()
+            .ok_or_else(|| format_error("truncated varint"))?;
+        *data = rest;
+        if shift >= 64 {
+            return Err(format_error("varint too long"));
+        }
+        result |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+    }
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(format_error("truncated field"));
+    }
+    let (result, rest) = data.split_at(len);
+    *data = rest;
+    Ok(result)
+}
+
+fn zigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Fields { data }
+    }
+
+    fn read_field(&mut self) -> Result<(u32, Value<'a>)> {
+        let key = read_varint(&mut self.data)?;
+        let value = match key & 7 {
+            0 => Value::Varint(read_varint(&mut self.data)?),
+            1 => {
+                take(&mut self.data, 8)?;
+                Value::Fixed
+            }
+            2 => {
+                let len = read_varint(&mut self.data)? as usize;
+                Value::Bytes(take(&mut self.data, len)?)
+            }
+            5 => {
+                take(&mut self.data, 4)?;
+                Value::Fixed
+            }
+            _ => return Err(format_error("invalid wire type")),
+        };
+        Ok(((key >> 3) as u32, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u32, Value<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            None
+        } else {
+            Some(self.read_field())
+        }
+    }
+}
+
+fn read_blob(reader: &mut impl Read) -> Result<Option<(String, Vec<u8>)>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => (),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+
+    let mut header = vec![0u8; u32::from_be_bytes(len) as usize];
+    reader.read_exact(&mut header)?;
+
+    let mut type_ = String::new();
+    let mut data_size = None;
+    for field in Fields::new(&header) {
+        match field? {
+            (1, value) => type_ = value.as_string()?,
+            (3, value) => data_size = Some(value.as_varint()? as usize),
+            _ => (),
+        }
+    }
+
+    let data_size =
+        data_size.ok_or_else(|| format_error("missing blob data size"))?;
+    let mut blob = vec![0u8; data_size];
+    reader.read_exact(&mut blob)?;
+    Ok(Some((type_, blob)))
+}
+
+fn decompress(blob: &[u8]) -> Result<Vec<u8>> {
+    let mut raw_size = 0;
+    for field in Fields::new(blob) {
+        match field? {
+            (1, value) => return Ok(value.as_bytes()?.to_vec()),
+            (2, value) => raw_size = value.as_varint()? as usize,
+            (3, value) => {
+                let mut result = Vec::with_capacity(raw_size);
+                ZlibDecoder::new(value.as_bytes()?).read_to_end(&mut result)?;
+                return Ok(result);
+            }
+            (4..=7, _) => {
+                return Err(format_error("unsupported blob compression"))
+            }
+            _ => (),
+        }
+    }
+    Err(format_error("empty blob"))
+}
+
+fn parse_header(data: &[u8]) -> Result<Option<OffsetDateTime>> {
+    let mut timestamp = None;
+    for field in Fields::new(data) {
+        match field? {
+            (4, value) => {
+                let feature = value.as_string()?;
+                if !SUPPORTED_FEATURES.contains(&feature.as_str()) {
+                    return Err(Error::new(
+                        ErrorType::FormatError,
+                        format!("unsupported required feature: {}", feature),
+                    ));
+                }
+            }
+            (32, value) => {
+                timestamp = Some(
+                    OffsetDateTime::from_unix_timestamp(
+                        value.as_varint()? as i64
+                    )
+                    .map_err(|err| {
+                        Error::convert(
+                            ErrorType::FormatError,
+                            "invalid replication timestamp",
+                            &err,
+                        )
+                    })?,
+                );
+            }
+            _ => (),
+        }
+    }
+    Ok(timestamp)
+}
+
+struct Block {
+    strings: Vec<String>,
+    granularity: i64,
+    lat_offset: i64,
+    lon_offset: i64,
+}
+
+impl Block {
+    fn string(&self, index: u64) -> Result<String> {
+        self.strings
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| format_error("invalid string table index"))
+    }
+
+    fn tags(&self, keys: &[u64], values: &[u64]) -> Result<Tags> {
+        if keys.len() != values.len() {
+            return Err(format_error("tag key and value count mismatch"));
+        }
+        keys.iter()
+            .zip(values)
+            .map(|(k, v)| Ok((self.string(*k)?, self.string(*v)?)))
+            .collect()
+    }
+
+    fn coordinate(&self, lat: i64, lon: i64) -> Coordinate {
+        Coordinate {
+            lat: 1e-9 * (self.lat_offset + self.granularity * lat) as f64,
+            lon: 1e-9 * (self.lon_offset + self.granularity * lon) as f64,
+        }
+    }
+
+    fn parse_node(&self, data: &[u8]) -> Result<Element> {
+        let mut id = 0;
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        let mut lat = 0;
+        let mut lon = 0;
+        for field in Fields::new(data) {
+            match field? {
+                (1, value) => id = zigzag(value.as_varint()?),
+                (2, value) => keys.extend(value.as_packed()?),
+                (3, value) => values.extend(value.as_packed()?),
+                (8, value) => lat = zigzag(value.as_varint()?),
+                (9, value) => lon = zigzag(value.as_varint()?),
+                _ => (),
+            }
+        }
+        Ok(Element::Node(
+            id as u64,
+            Node {
+                coordinate: self.coordinate(lat, lon),
+                tags: self.tags(&keys, &values)?,
+            },
+        ))
+    }
+
+    fn parse_dense_nodes<F>(&self, data: &[u8], callback: &mut F) -> Result<()>
+    where
+        F: FnMut(Element) -> Result<()>,
+    {
+        let mut ids = Vec::new();
+        let mut lats = Vec::new();
+        let mut lons = Vec::new();
+        let mut keys_vals = Vec::new();
+        for field in Fields::new(data) {
+            match field? {
+                (1, value) => ids.extend(value.as_packed()?),
+                (8, value) => lats.extend(value.as_packed()?),
+                (9, value) => lons.extend(value.as_packed()?),
+                (10, value) => keys_vals.extend(value.as_packed()?),
+                _ => (),
+            }
+        }
+        if lats.len() != ids.len() || lons.len() != ids.len() {
+            return Err(format_error("dense node field count mismatch"));
+        }
+
+        let mut keys_vals = keys_vals.into_iter();
+        let mut id = 0;
+        let mut lat = 0;
+        let mut lon = 0;
+        for ((id_delta, lat_delta), lon_delta) in ids.iter().zip(lats).zip(lons)
+        {
+            id += zigzag(*id_delta);
+            lat += zigzag(lat_delta);
+            lon += zigzag(lon_delta);
+
+            let mut tags = Tags::new();
+            while let Some(key) = keys_vals.next() {
+                if key == 0 {
+                    break;
+                }
+                let value = keys_vals
+                    .next()
+                    .ok_or_else(|| format_error("truncated dense node tags"))?;
+                tags.insert(self.string(key)?, self.string(value)?);
+            }
+
+            callback(Element::Node(
+                id as u64,
+                Node {
+                    coordinate: self.coordinate(lat, lon),
+                    tags,
+                },
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn parse_way(&self, data: &[u8]) -> Result<Element> {
+        let mut id = 0;
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        let mut refs = Vec::new();
+        for field in Fields::new(data) {
+            match field? {
+                (1, value) => id = value.as_varint()?,
+                (2, value) => keys.extend(value.as_packed()?),
+                (3, value) => values.extend(value.as_packed()?),
+                (8, value) => refs.extend(value.as_packed()?),
+                _ => (),
+            }
+        }
+
+        let mut node = 0;
+        let nodes = refs
+            .into_iter()
+            .map(|delta| {
+                node += zigzag(delta);
+                node as u64
+            })
+            .collect();
+        Ok(Element::Way(
+            id,
+            Way {
+                nodes,
+                geometry: Vec::new(),
+                tags: self.tags(&keys, &values)?,
+            },
+        ))
+    }
+
+    fn parse_relation(&self, data: &[u8]) -> Result<Element> {
+        let mut id = 0;
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        let mut roles = Vec::new();
+        let mut member_ids = Vec::new();
+        let mut types = Vec::new();
+        for field in Fields::new(data) {
+            match field? {
+                (1, value) => id = value.as_varint()?,
+                (2, value) => keys.extend(value.as_packed()?),
+                (3, value) => values.extend(value.as_packed()?),
+                (8, value) => roles.extend(value.as_packed()?),
+                (9, value) => member_ids.extend(value.as_packed()?),
+                (10, value) => types.extend(value.as_packed()?),
+                _ => (),
+            }
+        }
+        if roles.len() != member_ids.len() || types.len() != member_ids.len() {
+            return Err(format_error("relation member field count mismatch"));
+        }
+
+        let mut members = RelationMembers {
+            nodes: Vec::new(),
+            ways: Vec::new(),
+        };
+        let mut member_id = 0;
+        for ((role, delta), type_) in
+            roles.into_iter().zip(member_ids).zip(types)
+        {
+            member_id += zigzag(delta);
+            let member = RelationMember {
+                ref_: member_id as u64,
+                role: self.string(role)?,
+            };
+            match type_ {
+                0 => members.nodes.push(member),
+                1 => members.ways.push(member),
+                _ => (),
+            }
+        }
+        Ok(Element::Relation(
+            id,
+            Relation {
+                members,
+                tags: self.tags(&keys, &values)?,
+            },
+        ))
+    }
+}
+
+fn parse_primitive_block<F>(
+    data: &[u8],
+    type_: ElementType,
+    callback: &mut F,
+) -> Result<()>
+where
+    F: FnMut(Element) -> Result<()>,
+{
+    let mut block = Block {
+        strings: Vec::new(),
+        granularity: 100,
+        lat_offset: 0,
+        lon_offset: 0,
+    };
+    let mut groups = Vec::new();
+    for field in Fields::new(data) {
+        match field? {
+            (1, value) => {
+                for string in Fields::new(value.as_bytes()?) {
+                    if let (1, value) = string? {
+                        block.strings.push(value.as_string()?);
+                    }
+                }
+            }
+            (2, value) => groups.push(value.as_bytes()?),
+            (17, value) => block.granularity = value.as_varint()? as i64,
+            (19, value) => block.lat_offset = value.as_varint()? as i64,
+            (20, value) => block.lon_offset = value.as_varint()? as i64,
+            _ => (),
+        }
+    }
+
+    for group in groups {
+        for field in Fields::new(group) {
+            match (field?, type_) {
+                ((1, value), ElementType::Node) => {
+                    callback(block.parse_node(value.as_bytes()?)?)?
+                }
+                ((2, value), ElementType::Node) => {
+                    block.parse_dense_nodes(value.as_bytes()?, callback)?
+                }
+                ((3, value), ElementType::Way) => {
+                    callback(block.parse_way(value.as_bytes()?)?)?
+                }
+                ((4, value), ElementType::Relation) => {
+                    callback(block.parse_relation(value.as_bytes()?)?)?
+                }
+                _ => (),
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn read_elements<F>(
+    path: &Path,
+    type_: ElementType,
+    mut callback: F,
+) -> Result<Option<OffsetDateTime>>
+where
+    F: FnMut(Element) -> Result<()>,
+{
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut timestamp = None;
+    while let Some((blob_type, blob)) = read_blob(&mut reader)? {
+        let data = decompress(&blob)?;
+        match blob_type.as_str() {
+            "OSMHeader" => timestamp = parse_header(&data)?,
+            "OSMData" => parse_primitive_block(&data, type_, &mut callback)?,
+            // Unknown blob types must be skipped according to the spec.
+            _ => (),
+        }
+    }
+    Ok(timestamp)
+}
@@ -0,0 +1,196 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use time::format_description::well_known::Iso8601;
+use time::OffsetDateTime;
+
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{Element, ElementType};
+use crate::error::{Error, ErrorType, Result};
+use crate::osm_reader::{
+    Coordinate, Node, Relation, RelationMember, RelationMembers, Tags, Way,
+};
+
+fn get_attribute(e: &BytesStart, name: &str) -> Result<Option<String>> {
+    for attribute in e.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        if attribute.key.as_ref() == name.as_bytes() {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn get_required_attribute(e: &BytesStart, name: &str) -> Result<String> {
+    get_attribute(e, name)?.ok_or_else(|| {
+        Error::new(
+            ErrorType::FormatError,
+            format!(
+                "missing attribute {} of element {}",
+                name,
+                String::from_utf8_lossy(e.name().as_ref())
+            ),
+        )
+    })
+}
+
+fn parse_attribute<T>(e: &BytesStart, name: &str) -> Result<T>
+where
+    T: FromStr,
+{
+    let value = get_required_attribute(e, name)?;
+    value.parse().map_err(|_| {
+        Error::new(
+            ErrorType::FormatError,
+            format!("invalid value of attribute {}: {}", name, value),
+        )
+    })
+}
+
+fn parse_timestamp(e: &BytesStart) -> Result<Option<OffsetDateTime>> {
+    get_attribute(e, "timestamp")?
+        .map(|value| {
+            OffsetDateTime::parse(&value, &Iso8601::DEFAULT).map_err(|err| {
+                Error::convert(
+                    ErrorType::FormatError,
+                    "invalid timestamp",
+                    &err,
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Processes the start of an element. Returns true if the element is an OSM
+/// entity (node, way or relation).
+fn start_element(
+    e: &BytesStart,
+    type_: ElementType,
+    current: &mut Option<Element>,
+    timestamp: &mut Option<OffsetDateTime>,
+) -> Result<bool> {
+    match e.name().as_ref() {
+        b"osm" => {
+            *timestamp = parse_timestamp(e)?;
+            Ok(false)
+        }
+        b"node" => {
+            if type_ == ElementType::Node {
+                *current = Some(Element::Node(
+                    parse_attribute(e, "id")?,
+                    Node {
+                        coordinate: Coordinate {
+                            lat: parse_attribute(e, "lat")?,
+                            lon: parse_attribute(e, "lon")?,
+                        },
+                        tags: Tags::new(),
+                    },
+                ));
+            }
+            Ok(true)
+        }
+        b"way" => {
+            if type_ == ElementType::Way {
+                *current = Some(Element::Way(
+                    parse_attribute(e, "id")?,
+                    Way {
+                        nodes: Vec::new(),
+                        geometry: Vec::new(),
+                        tags: Tags::new(),
+                    },
+                ));
+            }
+            Ok(true)
+        }
+        b"relation" => {
+            if type_ == ElementType::Relation {
+                *current = Some(Element::Relation(
+                    parse_attribute(e, "id")?,
+                    Relation {
+                        members: RelationMembers {
+                            nodes: Vec::new(),
+                            ways: Vec::new(),
+                        },
+                        tags: Tags::new(),
+                    },
+                ));
+            }
+            Ok(true)
+        }
+        b"tag" => {
+            if let Some(element) = current {
+                element.tags_mut().insert(
+                    get_required_attribute(e, "k")?,
+                    get_required_attribute(e, "v")?,
+                );
+            }
+            Ok(false)
+        }
+        b"nd" => {
+            if let Some(Element::Way(_, way)) = current {
+                way.nodes.push(parse_attribute(e, "ref")?);
+            }
+            Ok(false)
+        }
+        b"member" => {
+            if let Some(Element::Relation(_, relation)) = current {
+                let member = RelationMember {
+                    ref_: parse_attribute(e, "ref")?,
+                    role: get_attribute(e, "role")?.unwrap_or_default(),
+                };
+                match get_required_attribute(e, "type")?.as_str() {
+                    "node" => relation.members.nodes.push(member),
+                    "way" => relation.members.ways.push(member),
+                    _ => (),
+                }
+            }
+            Ok(false)
+        }
+        _ => Ok(false),
+    }
+}
+
+pub fn read_elements<F>(
+    path: &Path,
+    type_: ElementType,
+    mut callback: F,
+) -> Result<Option<OffsetDateTime>>
+where
+    F: FnMut(Element) -> Result<()>,
+{
+    let mut reader = Reader::from_file(path)?;
+    let mut buf = Vec::new();
+    let mut current = None;
+    let mut timestamp = None;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                start_element(&e, type_, &mut current, &mut timestamp)?;
+            }
+            Event::Empty(e) => {
+                let is_entity =
+                    start_element(&e, type_, &mut current, &mut timestamp)?;
+                if let Some(element) =
+                    is_entity.then(|| current.take()).flatten()
+                {
+                    callback(element)?;
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"node" | b"way" | b"relation" => {
+                    if let Some(element) = current.take() {
+                        callback(element)?;
+                    }
+                }
+                _ => (),
+            },
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(timestamp)
+}
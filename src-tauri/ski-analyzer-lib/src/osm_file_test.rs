@@ -0,0 +1,590 @@
+use super::osm_file::{
+    load_ski_area_details_by_id, load_ski_area_details_by_name,
+    load_ski_areas_by_name, Format,
+};
+use super::osm_reader::Document;
+use super::ski_area::{SkiArea, SkiAreaMetadata};
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{init, Init};
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use rstest::rstest;
+use time::OffsetDateTime;
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+struct TestNode {
+    id: u64,
+    lat: f64,
+    lon: f64,
+    tags: &'static [(&'static str, &'static str)],
+}
+
+struct TestWay {
+    id: u64,
+    nodes: &'static [u64],
+    tags: &'static [(&'static str, &'static str)],
+}
+
+struct TestRelation {
+    id: u64,
+    ways: &'static [u64],
+    tags: &'static [(&'static str, &'static str)],
+}
+
+const fn n(id: u64, lat: f64, lon: f64) -> TestNode {
+    TestNode {
+        id,
+        lat,
+        lon,
+        tags: &[],
+    }
+}
+
+const NODES: &[TestNode] = &[
+    n(1, 45.0, 6.0),
+    n(2, 45.0, 6.1),
+    n(3, 45.1, 6.1),
+    n(4, 45.1, 6.0),
+    n(5, 45.02, 6.02),
+    n(6, 45.05, 6.05),
+    n(7, 45.03, 6.03),
+    n(8, 45.04, 6.08),
+    n(9, 45.06, 6.06),
+    n(10, 45.08, 6.02),
+    n(11, 45.05, 6.09),
+    n(12, 45.05, 6.2),
+    n(13, 46.0, 7.0),
+    n(14, 46.1, 7.1),
+    n(15, 45.07, 6.07),
+    n(16, 45.09, 6.09),
+    n(17, 46.0, 7.1),
+    n(18, 46.1, 7.0),
+    n(20, 45.08, 6.06),
+    TestNode {
+        id: 19,
+        lat: 45.05,
+        lon: 6.04,
+        tags: &[("amenity", "restaurant")],
+    },
+];
+
+const WAYS: &[TestWay] = &[
+    TestWay {
+        id: 100,
+        nodes: &[1, 2, 3, 4, 1],
+        tags: &[("landuse", "winter_sports"), ("name", "Test Area")],
+    },
+    TestWay {
+        id: 101,
+        nodes: &[5, 6],
+        tags: &[("aerialway", "chair_lift"), ("name", "Inside Lift")],
+    },
+    TestWay {
+        id: 102,
+        nodes: &[7, 8],
+        tags: &[
+            ("piste:type", "downhill"),
+            ("piste:difficulty", "easy"),
+            ("name", "Inside Piste"),
+        ],
+    },
+    TestWay {
+        id: 103,
+        nodes: &[9, 10, 20, 9],
+        tags: &[],
+    },
+    TestWay {
+        id: 104,
+        nodes: &[11, 12],
+        tags: &[
+            ("piste:type", "downhill"),
+            ("piste:difficulty", "advanced"),
+            ("name", "Crossing Piste"),
+        ],
+    },
+    TestWay {
+        id: 105,
+        nodes: &[13, 14],
+        tags: &[
+            ("piste:type", "downhill"),
+            ("piste:difficulty", "easy"),
+            ("name", "Outside Piste"),
+        ],
+    },
+    TestWay {
+        id: 106,
+        nodes: &[17, 18],
+        tags: &[("aerialway", "drag_lift"), ("name", "Outside Lift")],
+    },
+    TestWay {
+        id: 107,
+        nodes: &[15, 16],
+        tags: &[("highway", "track")],
+    },
+    TestWay {
+        id: 108,
+        nodes: &[13, 14, 17, 13],
+        tags: &[("landuse", "winter_sports"), ("name", "Other Area")],
+    },
+    TestWay {
+        id: 109,
+        nodes: &[13, 17, 18, 13],
+        tags: &[],
+    },
+    TestWay {
+        id: 111,
+        nodes: &[15, 16],
+        tags: &[("piste:type", "nordic"), ("name", "Nordic Trail")],
+    },
+    TestWay {
+        id: 112,
+        nodes: &[5, 7],
+        tags: &[("piste:type", "sled"), ("name", "Sled Run")],
+    },
+    TestWay {
+        id: 113,
+        nodes: &[6, 9],
+        tags: &[],
+    },
+    TestWay {
+        id: 110,
+        nodes: &[16, 20],
+        tags: &[
+            ("mtb:type", "downhill"),
+            ("mtb:scale", "1"),
+            ("name", "Bike Trail"),
+        ],
+    },
+    TestWay {
+        id: 114,
+        nodes: &[16, 20],
+        tags: &[("mtb:scale", "1"), ("name", "Hiking Trail")],
+    },
+];
+
+const RELATIONS: &[TestRelation] = &[
+    TestRelation {
+        id: 200,
+        ways: &[103],
+        tags: &[
+            ("type", "multipolygon"),
+            ("piste:type", "downhill"),
+            ("piste:difficulty", "intermediate"),
+            ("name", "Relation Piste"),
+        ],
+    },
+    TestRelation {
+        id: 201,
+        ways: &[109],
+        tags: &[
+            ("type", "multipolygon"),
+            ("piste:type", "downhill"),
+            ("piste:difficulty", "novice"),
+            ("name", "Outside Relation Piste"),
+        ],
+    },
+    TestRelation {
+        id: 202,
+        ways: &[113],
+        tags: &[
+            ("type", "route"),
+            ("route", "ski"),
+            ("piste:type", "skitour"),
+            ("name", "Skitour Route"),
+        ],
+    },
+];
+
+// 2024-10-02T18:07:14Z
+const TIMESTAMP: i64 = 1727892434;
+
+fn timestamp() -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(TIMESTAMP).unwrap()
+}
+
+fn output_path(name: &str) -> PathBuf {
+    let dir = PathBuf::from("test_output/osm_file_test");
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+fn write_xml_tags(out: &mut String, tags: &[(&str, &str)]) {
+    for (k, v) in tags {
+        out.push_str(&format!("    <tag k=\"{}\" v=\"{}\"/>\n", k, v));
+    }
+}
+
+fn write_xml(name: &str) -> PathBuf {
+    let mut out = String::from(
+        "<?xml version='1.0' encoding='UTF-8'?>\n\
+         <osm version=\"0.6\" generator=\"test\" \
+         timestamp=\"2024-10-02T18:07:14Z\">\n",
+    );
+    for node in NODES {
+        if node.tags.is_empty() {
+            out.push_str(&format!(
+                "  <node id=\"{}\" lat=\"{}\" lon=\"{}\"/>\n",
+                node.id, node.lat, node.lon
+            ));
+        } else {
+            out.push_str(&format!(
+                "  <node id=\"{}\" lat=\"{}\" lon=\"{}\">\n",
+                node.id, node.lat, node.lon
+            ));
+            write_xml_tags(&mut out, node.tags);
+            out.push_str("  </node>\n");
+        }
+    }
+    for way in WAYS {
+        out.push_str(&format!("  <way id=\"{}\">\n", way.id));
+        for node in way.nodes {
+            out.push_str(&format!("    <nd ref=\"{}\"/>\n", node));
+        }
+        write_xml_tags(&mut out, way.tags);
+        out.push_str("  </way>\n");
+    }
+    for relation in RELATIONS {
+        out.push_str(&format!("  <relation id=\"{}\">\n", relation.id));
+        for way in relation.ways {
+            out.push_str(&format!(
+                "    <member type=\"way\" ref=\"{}\" role=\"outer\"/>\n",
+                way
+            ));
+        }
+        write_xml_tags(&mut out, relation.tags);
+        out.push_str("  </relation>\n");
+    }
+    out.push_str("</osm>\n");
+
+    let path = output_path(name);
+    fs::write(&path, out).unwrap();
+    path
+}
+
+fn varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn field_varint(out: &mut Vec<u8>, field: u64, value: u64) {
+    varint(out, field << 3);
+    varint(out, value);
+}
+
+fn field_bytes(out: &mut Vec<u8>, field: u64, data: &[u8]) {
+    varint(out, (field << 3) | 2);
+    varint(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+fn packed(values: impl Iterator<Item = u64>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for value in values {
+        varint(&mut out, value);
+    }
+    out
+}
+
+fn delta(values: impl Iterator<Item = i64>) -> impl Iterator<Item = u64> {
+    let mut previous = 0;
+    values.map(move |value| {
+        let result = zigzag(value - previous);
+        previous = value;
+        result
+    })
+}
+
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    indexes: HashMap<String, u64>,
+}
+
+impl StringTable {
+    fn new() -> Self {
+        let mut result = StringTable::default();
+        result.get("");
+        result
+    }
+
+    fn get(&mut self, s: &str) -> u64 {
+        if let Some(index) = self.indexes.get(s) {
+            return *index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.push(s.to_string());
+        self.indexes.insert(s.to_string(), index);
+        index
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for s in &self.strings {
+            field_bytes(&mut out, 1, s.as_bytes());
+        }
+        out
+    }
+}
+
+fn write_tags(
+    out: &mut Vec<u8>,
+    strings: &mut StringTable,
+    tags: &[(&str, &str)],
+) {
+    let keys: Vec<u64> = tags.iter().map(|(k, _)| strings.get(k)).collect();
+    let values: Vec<u64> = tags.iter().map(|(_, v)| strings.get(v)).collect();
+    field_bytes(out, 2, &packed(keys.into_iter()));
+    field_bytes(out, 3, &packed(values.into_iter()));
+}
+
+fn write_blob(out: &mut Vec<u8>, type_: &str, data: &[u8], compress: bool) {
+    let mut blob = Vec::new();
+    if compress {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        field_varint(&mut blob, 2, data.len() as u64);
+        field_bytes(&mut blob, 3, &encoder.finish().unwrap());
+    } else {
+        field_bytes(&mut blob, 1, data);
+    }
+
+    let mut header = Vec::new();
+    field_bytes(&mut header, 1, type_.as_bytes());
+    field_varint(&mut header, 3, blob.len() as u64);
+
+    out.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    out.extend_from_slice(&header);
+    out.extend_from_slice(&blob);
+}
+
+fn write_pbf(name: &str) -> PathBuf {
+    let mut header_block = Vec::new();
+    field_bytes(&mut header_block, 4, b"OsmSchema-V0.6");
+    field_bytes(&mut header_block, 4, b"DenseNodes");
+    field_bytes(&mut header_block, 16, b"test");
+    field_varint(&mut header_block, 32, TIMESTAMP as u64);
+
+    let mut strings = StringTable::new();
+    let to_raw = |value: f64| (value * 1e7).round() as i64;
+
+    let mut dense = Vec::new();
+    field_bytes(
+        &mut dense,
+        1,
+        &packed(delta(NODES.iter().map(|n| n.id as i64))),
+    );
+    field_bytes(
+        &mut dense,
+        8,
+        &packed(delta(NODES.iter().map(|n| to_raw(n.lat)))),
+    );
+    field_bytes(
+        &mut dense,
+        9,
+        &packed(delta(NODES.iter().map(|n| to_raw(n.lon)))),
+    );
+    let mut keys_vals = Vec::new();
+    for node in NODES {
+        for (k, v) in node.tags {
+            keys_vals.push(strings.get(k));
+            keys_vals.push(strings.get(v));
+        }
+        keys_vals.push(0);
+    }
+    field_bytes(&mut dense, 10, &packed(keys_vals.into_iter()));
+    let mut node_group = Vec::new();
+    field_bytes(&mut node_group, 2, &dense);
+
+    let mut way_group = Vec::new();
+    for way in WAYS {
+        let mut data = Vec::new();
+        field_varint(&mut data, 1, way.id);
+        write_tags(&mut data, &mut strings, way.tags);
+        field_bytes(
+            &mut data,
+            8,
+            &packed(delta(way.nodes.iter().map(|n| *n as i64))),
+        );
+        field_bytes(&mut way_group, 3, &data);
+    }
+
+    let mut relation_group = Vec::new();
+    for relation in RELATIONS {
+        let mut data = Vec::new();
+        field_varint(&mut data, 1, relation.id);
+        write_tags(&mut data, &mut strings, relation.tags);
+        let role = strings.get("outer");
+        field_bytes(&mut data, 8, &packed(relation.ways.iter().map(|_| role)));
+        field_bytes(
+            &mut data,
+            9,
+            &packed(delta(relation.ways.iter().map(|w| *w as i64))),
+        );
+        field_bytes(&mut data, 10, &packed(relation.ways.iter().map(|_| 1)));
+        field_bytes(&mut relation_group, 4, &data);
+    }
+
+    let mut block = Vec::new();
+    field_bytes(&mut block, 1, &strings.encode());
+    field_bytes(&mut block, 2, &node_group);
+    field_bytes(&mut block, 2, &way_group);
+    field_bytes(&mut block, 2, &relation_group);
+
+    let mut out = Vec::new();
+    write_blob(&mut out, "OSMHeader", &header_block, false);
+    write_blob(&mut out, "OSMData", &block, true);
+
+    let path = output_path(name);
+    fs::write(&path, out).unwrap();
+    path
+}
+
+fn write_file(format: Format, name: &str) -> PathBuf {
+    match format {
+        Format::Xml => write_xml(&format!("{}.osm", name)),
+        Format::Pbf => write_pbf(&format!("{}.osm.pbf", name)),
+    }
+}
+
+fn keys<T>(map: &HashMap<u64, T>) -> BTreeSet<u64> {
+    map.keys().copied().collect()
+}
+
+#[rstest]
+fn detect_format(
+    _init: Init,
+    #[values(Format::Xml, Format::Pbf)] format: Format,
+) {
+    let path = write_file(format, "detect_format");
+    assert_eq!(Format::detect(&path).unwrap(), format);
+}
+
+#[rstest]
+fn details(_init: Init, #[values(Format::Xml, Format::Pbf)] format: Format) {
+    let path = write_file(format, "details");
+    let doc =
+        load_ski_area_details_by_id(&CancellationToken::new(), &path, 100)
+            .unwrap();
+
+    assert_eq!(doc.osm3s.timestamp_osm_base, timestamp());
+    assert_eq!(
+        keys(&doc.elements.ways),
+        BTreeSet::from([100, 101, 102, 103, 104, 110, 111, 112, 113])
+    );
+    assert_eq!(keys(&doc.elements.relations), BTreeSet::from([200, 202]));
+    assert_eq!(
+        keys(&doc.elements.nodes),
+        BTreeSet::from([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 15, 16, 20])
+    );
+
+    let node = &doc.elements.nodes[&8];
+    assert!((node.coordinate.lat - 45.04).abs() < 1e-7);
+    assert!((node.coordinate.lon - 6.08).abs() < 1e-7);
+    assert_eq!(doc.elements.ways[&102].nodes, vec![7, 8]);
+    assert_eq!(doc.elements.relations[&200].members.ways[0].ref_, 103);
+
+    let ski_area =
+        SkiArea::parse(&CancellationToken::new(), &doc, None).unwrap();
+    assert_eq!(ski_area.metadata.id, 100);
+    assert_eq!(ski_area.metadata.name, "Test Area");
+    assert_eq!(ski_area.lifts().len(), 1);
+    assert_eq!(ski_area.lifts()["101"].name, "Inside Lift");
+    let piste_names: BTreeSet<&str> = ski_area
+        .pistes()
+        .values()
+        .map(|p| p.metadata.name.as_str())
+        .collect();
+    assert_eq!(
+        piste_names,
+        BTreeSet::from(["Crossing Piste", "Inside Piste", "Relation Piste"])
+    );
+    assert_eq!(ski_area.nordic_trails().len(), 1);
+    assert_eq!(
+        ski_area.nordic_trails()["w111"].metadata.name,
+        "Nordic Trail"
+    );
+    assert_eq!(ski_area.sled_runs().len(), 1);
+    assert_eq!(ski_area.sled_runs()["w112"].metadata.name, "Sled Run");
+    assert_eq!(ski_area.skitour_routes().len(), 1);
+    assert_eq!(
+        ski_area.skitour_routes()["r202"].metadata.name,
+        "Skitour Route"
+    );
+    assert_eq!(ski_area.bike_trails().len(), 1);
+    assert_eq!(ski_area.bike_trails()["w110"].metadata.name, "Bike Trail");
+}
+
+#[rstest]
+fn details_not_ski_area(
+    _init: Init,
+    #[values(Format::Xml, Format::Pbf)] format: Format,
+) {
+    let path = write_file(format, "details_not_ski_area");
+    assert!(
+        load_ski_area_details_by_id(&CancellationToken::new(), &path, 101)
+            .is_err()
+    );
+    assert!(
+        load_ski_area_details_by_id(&CancellationToken::new(), &path, 999)
+            .is_err()
+    );
+}
+
+#[rstest]
+fn details_by_name(
+    _init: Init,
+    #[values(Format::Xml, Format::Pbf)] format: Format,
+) {
+    let path = write_file(format, "details_by_name");
+    let cancel = CancellationToken::new();
+    let by_id = load_ski_area_details_by_id(&cancel, &path, 100).unwrap();
+    let by_name =
+        load_ski_area_details_by_name(&cancel, &path, "TEST").unwrap();
+    assert_eq!(keys(&by_name.elements.ways), keys(&by_id.elements.ways));
+    assert_eq!(
+        keys(&by_name.elements.relations),
+        keys(&by_id.elements.relations)
+    );
+    assert_eq!(keys(&by_name.elements.nodes), keys(&by_id.elements.nodes));
+
+    // Both ski areas match.
+    assert!(load_ski_area_details_by_name(&cancel, &path, "area").is_err());
+    assert!(load_ski_area_details_by_name(&cancel, &path, "nothing").is_err());
+}
+
+fn find_names(doc: &Document) -> Vec<String> {
+    SkiAreaMetadata::find(doc)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.name)
+        .collect()
+}
+
+#[rstest]
+fn by_name(_init: Init, #[values(Format::Xml, Format::Pbf)] format: Format) {
+    let path = write_file(format, "by_name");
+    let cancel = CancellationToken::new();
+
+    let doc = load_ski_areas_by_name(&cancel, &path, "area").unwrap();
+    assert!(doc.elements.nodes.is_empty());
+    assert_eq!(doc.elements.ways[&100].geometry.len(), 5);
+    assert_eq!(find_names(&doc), vec!["Other Area", "Test Area"]);
+
+    let doc = load_ski_areas_by_name(&cancel, &path, "TEST").unwrap();
+    assert_eq!(find_names(&doc), vec!["Test Area"]);
+
+    let doc = load_ski_areas_by_name(&cancel, &path, "nothing").unwrap();
+    assert!(find_names(&doc).is_empty());
+}
@@ -1,26 +1,108 @@
-use crate::config::get_config;
-use crate::error::Result;
+use crate::config::{get_config, Config};
+use crate::error::{Error, ErrorType, Result};
 use geo::Rect;
-use url::form_urlencoded;
+use url::{form_urlencoded, ParseError, Url};
 
-pub async fn query(query: &str) -> Result<Vec<u8>> {
-    let mut input: String =
-        form_urlencoded::byte_serialize(&query.as_bytes()).collect();
-    input.insert_str(0, "data=");
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+pub enum Backend {
+    Http {
+        endpoint: Url,
+        timeout: Duration,
+        user_agent: String,
+    },
+    File(PathBuf),
+}
+
+/// Whether the endpoint starts with a drive letter, like `C:\data`. The URL
+/// parser would take the drive letter for a scheme.
+fn has_drive_letter(endpoint: &str) -> bool {
+    match endpoint.as_bytes() {
+        [letter, b':'] => letter.is_ascii_alphabetic(),
+        [letter, b':', b'\\' | b'/', ..] => letter.is_ascii_alphabetic(),
+        _ => false,
+    }
+}
+
+impl Backend {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let endpoint = config.overpass_endpoint.as_str();
+        if has_drive_letter(endpoint) {
+            return Ok(Backend::File(PathBuf::from(endpoint)));
+        }
+        let url = match Url::parse(endpoint) {
+            Ok(url) => url,
+            Err(ParseError::RelativeUrlWithoutBase) => {
+                return Ok(Backend::File(PathBuf::from(endpoint)));
+            }
+            Err(err) => {
+                return Err(Error::convert(
+                    ErrorType::InputError,
+                    "invalid Overpass endpoint",
+                    &err,
+                ));
+            }
+        };
 
-    if get_config().is_vv() {
-        eprintln!("{}", input);
+        match url.scheme() {
+            "http" | "https" => Ok(Backend::Http {
+                endpoint: url,
+                timeout: Duration::from_secs(config.overpass_timeout),
+                user_agent: config.user_agent.clone(),
+            }),
+            "file" => {
+                let path = url.to_file_path().map_err(|_| {
+                    Error::new(
+                        ErrorType::InputError,
+                        format!("invalid file URL: {}", endpoint),
+                    )
+                })?;
+                Ok(Backend::File(path))
+            }
+            scheme => Err(Error::new(
+                ErrorType::InputError,
+                format!("unsupported Overpass endpoint scheme: {}", scheme),
+            )),
+        }
     }
 
-    let client = reqwest::Client::new();
-    Ok(client
-        .post("https://overpass-api.de/api/interpreter")
-        .body(input)
-        .send()
-        .await?
-        .bytes()
-        .await?
-        .into())
+    pub async fn query(&self, query: &str) -> Result<Vec<u8>> {
+        match self {
+            Backend::Http {
+                endpoint,
+                timeout,
+                user_agent,
+            } => {
+                let mut input: String =
+                    form_urlencoded::byte_serialize(query.as_bytes()).collect();
+                input.insert_str(0, "data=");
+
+                if get_config().is_vv() {
+                    eprintln!("{}", input);
+                }
+
+                let client = reqwest::Client::builder()
+                    .timeout(*timeout)
+                    .user_agent(user_agent.as_str())
+                    .build()?;
+                Ok(client
+                    .post(endpoint.clone())
+                    .body(input)
+                    .send()
+                    .await?
+                    .bytes()
+                    .await?
+                    .into())
+            }
+            Backend::File(path) => Ok(std::fs::read(path)?),
+        }
+    }
+}
+
+pub async fn query(query: &str) -> Result<Vec<u8>> {
+    Backend::from_config(get_config())?.query(query).await
 }
 
 pub async fn query_ski_area_details_by_id(id: u64) -> Result<Vec<u8>> {
@@ -30,8 +112,12 @@ pub async fn query_ski_area_details_by_id(id: u64) -> Result<Vec<u8>> {
     (
         way({})->.a;
         way(area.a)["aerialway"];
-        way(area.a)["piste:type"="downhill"];
-        rel(area.a)["piste:type"="downhill"];
+        way(area.a)["railway"~"^(funicular|rack|subway)$"];
+        way(area.a)["railway"]["rack"];
+        way(area.a)["piste:type"~"^(downhill|nordic|sled|skitour)$"];
+        rel(area.a)["piste:type"~"^(downhill|nordic|sled|skitour)$"];
+        way(area.a)["mtb:type"~"^(downhill|freeride)$"];
+        rel(area.a)["route"="mtb"]["mtb:type"~"^(downhill|freeride)$"];
     );
     >;
 );
@@ -0,0 +1,130 @@
+use super::config::Config;
+use super::osm_query::Backend;
+use super::osm_reader::Document;
+use super::ski_area::SkiArea;
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{init, Init};
+
+use rstest::rstest;
+use tokio::runtime::Builder;
+use url::Url;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn config(endpoint: &str) -> Config {
+    Config {
+        overpass_endpoint: endpoint.to_string(),
+        overpass_timeout: 20,
+        user_agent: "test agent".to_string(),
+        ..Config::default()
+    }
+}
+
+fn run_query(backend: &Backend, query: &str) -> Vec<u8> {
+    let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(backend.query(query)).unwrap()
+}
+
+fn write_canned_response(name: &str, content: &str) -> PathBuf {
+    let dir = PathBuf::from("test_output/osm_query_test");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn http_backend() {
+    let actual =
+        Backend::from_config(&config("https://example.com/api/interpreter"))
+            .unwrap();
+    let expected = Backend::Http {
+        endpoint: Url::parse("https://example.com/api/interpreter").unwrap(),
+        timeout: Duration::from_secs(20),
+        user_agent: "test agent".to_string(),
+    };
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn default_backend() {
+    let actual = Backend::from_config(&Config::default()).unwrap();
+    match actual {
+        Backend::Http { endpoint, .. } => assert_eq!(
+            endpoint.as_str(),
+            "https://overpass-api.de/api/interpreter"
+        ),
+        Backend::File(_) => panic!("Expected HTTP backend"),
+    }
+}
+
+#[test]
+fn file_url_backend() {
+    let actual =
+        Backend::from_config(&config("file:///tmp/overpass.json")).unwrap();
+    assert_eq!(actual, Backend::File(PathBuf::from("/tmp/overpass.json")));
+}
+
+#[test]
+fn path_backend() {
+    let actual = Backend::from_config(&config("canned/overpass.json")).unwrap();
+    assert_eq!(actual, Backend::File(PathBuf::from("canned/overpass.json")));
+}
+
+#[test]
+fn windows_path_backend() {
+    let actual = Backend::from_config(&config("C:\\data\\x.json")).unwrap();
+    assert_eq!(actual, Backend::File(PathBuf::from("C:\\data\\x.json")));
+    let actual = Backend::from_config(&config("d:/data/x.json")).unwrap();
+    assert_eq!(actual, Backend::File(PathBuf::from("d:/data/x.json")));
+}
+
+#[test]
+fn unsupported_scheme() {
+    assert!(Backend::from_config(&config("ftp://example.com/api")).is_err());
+}
+
+#[rstest]
+fn file_backend_ski_area(_init: Init) {
+    let json = r###"{
+    "osm3s": {
+      "timestamp_osm_base": "2024-10-02T18:07:14Z",
+      "copyright": "Whatever"
+    },
+    "elements": [
+      { "type": "node", "id": 1, "lat": 45.0, "lon": 6.0 },
+      { "type": "node", "id": 2, "lat": 45.0, "lon": 6.1 },
+      { "type": "node", "id": 3, "lat": 45.1, "lon": 6.1 },
+      { "type": "node", "id": 4, "lat": 45.1, "lon": 6.0 },
+      { "type": "node", "id": 5, "lat": 45.02, "lon": 6.02 },
+      { "type": "node", "id": 6, "lat": 45.05, "lon": 6.05 },
+      {
+        "type": "way",
+        "id": 10,
+        "nodes": [1, 2, 3, 4, 1],
+        "tags": { "landuse": "winter_sports", "name": "Canned Area" }
+      },
+      {
+        "type": "way",
+        "id": 11,
+        "nodes": [5, 6],
+        "tags": { "aerialway": "chair_lift", "name": "Canned Lift" }
+      }
+    ]
+}"###;
+    let path = write_canned_response("ski_area.json", json);
+    let backend =
+        Backend::from_config(&config(path.to_str().unwrap())).unwrap();
+
+    let response = run_query(&backend, "[out:json];way(10);out;");
+    let doc = Document::parse(&response).unwrap();
+    let ski_area =
+        SkiArea::parse(&CancellationToken::new(), &doc, None).unwrap();
+
+    assert_eq!(ski_area.metadata.id, 10);
+    assert_eq!(ski_area.metadata.name, "Canned Area");
+    assert_eq!(ski_area.lifts().len(), 1);
+    assert_eq!(ski_area.lifts()["11"].name, "Canned Lift");
+}
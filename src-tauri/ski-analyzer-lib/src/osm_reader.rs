@@ -328,9 +328,19 @@ pub fn parse_way(doc: &Document, nodes: &Vec<u64>) -> Result<Vec<Coord>> {
     Ok(coords)
 }
 
-pub fn parse_ele(tags: &Tags) -> u32 {
-    match tags.get("ele") {
-        None => 0,
-        Some(ele) => ele.parse().unwrap_or(0),
+/// Parses the ele tag in meters. The unit is optional. Returns None if the tag
+/// is missing.
+pub fn parse_ele(tags: &Tags) -> Result<Option<f64>> {
+    let Some(ele) = tags.get("ele") else {
+        return Ok(None);
+    };
+    let value = ele.trim();
+    let value = value.strip_suffix('m').unwrap_or(value).trim_end();
+    match value.parse::<f64>() {
+        Ok(e) if e.is_finite() => Ok(Some(e)),
+        _ => Err(Error::new(
+            ErrorType::OSMError,
+            format!("invalid elevation: {}", ele),
+        )),
     }
 }
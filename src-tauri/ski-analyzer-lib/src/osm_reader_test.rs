@@ -299,3 +299,17 @@ fn parse_way_with_geometry() {
         expected_line
     );
 }
+
+#[test]
+fn parse_ele() {
+    let tags = |value: &str| -> r::Tags {
+        HashMap::from([("ele".to_string(), value.to_string())])
+    };
+    assert_eq!(r::parse_ele(&r::Tags::new()).unwrap(), None);
+    assert_eq!(r::parse_ele(&tags("1200")).unwrap(), Some(1200.0));
+    assert_eq!(r::parse_ele(&tags("0")).unwrap(), Some(0.0));
+    assert_eq!(r::parse_ele(&tags("-3.5")).unwrap(), Some(-3.5));
+    assert_eq!(r::parse_ele(&tags("1850.4 m")).unwrap(), Some(1850.4));
+    assert!(r::parse_ele(&tags("high")).is_err());
+    assert!(r::parse_ele(&tags("")).is_err());
+}
@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 
 use geo::{Intersects, LineString, Point, Polygon, Rect};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use time::OffsetDateTime;
 
-use lift::parse_lift;
+use lift::{is_railway_lift, join_railways, parse_lift};
 use piste::parse_pistes;
 
 use crate::config::get_config;
+use crate::dem::Dem;
 use crate::error::{convert_err, Error, ErrorType, Result};
 use crate::osm_reader::{get_tag, parse_way, Document};
 use crate::utils::bounded_geometry::BoundedGeometry;
@@ -17,6 +18,8 @@ use crate::utils::time_ser;
 
 mod lift;
 mod piste;
+mod spatial_index;
+mod trail;
 
 #[cfg(test)]
 mod geo_test;
@@ -24,9 +27,20 @@ mod geo_test;
 mod lift_test;
 #[cfg(test)]
 mod piste_test;
+#[cfg(test)]
+mod spatial_index_test;
+#[cfg(test)]
+mod trail_test;
 
-pub use lift::Lift;
-pub use piste::{Difficulty, Piste, PisteData, PisteMetadata};
+pub use lift::{Lift, LiftAttributes};
+pub use piste::{
+    Difficulty, Grooming, Piste, PisteAttributes, PisteData, PisteMetadata,
+    PisteStatus, PisteType,
+};
+use spatial_index::LazySpatialIndex;
+pub use spatial_index::SpatialIndex;
+pub(crate) use trail::is_downhill_mtb;
+pub use trail::{Trail, Trails};
 
 #[cfg(feature = "specta")]
 use crate::typescript_gen::geo::{PointDef, PolygonDef, RectDef};
@@ -36,25 +50,65 @@ use crate::typescript_gen::geo::{PointDef, PolygonDef, RectDef};
 pub struct PointWithElevation {
     #[cfg_attr(feature = "specta", specta(type = PointDef))]
     pub point: Point,
-    pub elevation: u32,
+    /// In meters. None if unknown.
+    #[serde(default, deserialize_with = "deserialize_elevation")]
+    pub elevation: Option<f64>,
 }
 
 impl PointWithElevation {
-    pub fn new(point: Point, elevation: u32) -> Self {
+    pub fn new(point: Point, elevation: Option<f64>) -> Self {
         Self { point, elevation }
     }
 }
 
+/// Older versions stored the elevation as an integer, with 0 for unknown
+/// elevations. Floats are always written with a fraction, so an integer 0
+/// can only come from an old file.
+fn deserialize_elevation<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Elevation {
+        Integer(u64),
+        Float(f64),
+    }
+
+    Ok(match Option::<Elevation>::deserialize(deserializer)? {
+        None | Some(Elevation::Integer(0)) => None,
+        Some(Elevation::Integer(e)) => Some(e as f64),
+        Some(Elevation::Float(e)) => Some(e),
+    })
+}
+
 #[cfg_attr(feature = "specta", derive(specta::Type))]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkiArea {
     pub metadata: SkiAreaMetadata,
-    pub lifts: HashMap<String, Lift>,
-    pub pistes: HashMap<String, Piste>,
+    lifts: HashMap<String, Lift>,
+    pistes: HashMap<String, Piste>,
+    /// Cross-country skiing trails.
+    #[serde(default)]
+    nordic_trails: HashMap<String, Trail>,
+    /// Sledding and tobogganing runs.
+    #[serde(default)]
+    sled_runs: HashMap<String, Trail>,
+    /// Marked ski touring and skin track routes.
+    #[serde(default)]
+    skitour_routes: HashMap<String, Trail>,
+    /// Downhill mountain bike trails, used in summer.
+    #[serde(default)]
+    bike_trails: HashMap<String, Trail>,
     #[cfg_attr(feature = "specta", specta(type = RectDef))]
     pub bounding_rect: Rect,
     #[serde(with = "time_ser")]
     pub date: OffsetDateTime,
+    #[serde(skip)]
+    #[cfg_attr(feature = "specta", specta(skip))]
+    index: LazySpatialIndex,
 }
 
 #[cfg_attr(feature = "specta", derive(specta::Type))]
@@ -110,12 +164,20 @@ impl SkiAreaMetadata {
 fn find_lifts(
     cancel: &CancellationToken,
     doc: &Document,
+    dem: Option<&Dem>,
 ) -> Result<HashMap<String, Lift>> {
     let mut result = HashMap::new();
+    let railways = join_railways(doc);
+    let ways = doc
+        .elements
+        .ways
+        .iter()
+        .filter(|(_, way)| !is_railway_lift(&way.tags))
+        .chain(railways.iter().map(|(id, way)| (id, way)));
 
-    for (id, way) in &doc.elements.ways {
+    for (id, way) in ways {
         cancel.check()?;
-        match parse_lift(&doc, id, way) {
+        match parse_lift(doc, id, way, dem) {
             Ok(Some(lift)) => {
                 result.insert(id.to_string(), lift);
             }
@@ -133,14 +195,20 @@ fn find_lifts(
 }
 
 impl SkiArea {
-    pub fn parse(cancel: &CancellationToken, doc: &Document) -> Result<Self> {
+    /// Parses the ski area from OSM data. The elevation model is used for lift
+    /// stations without elevation.
+    pub fn parse(
+        cancel: &CancellationToken,
+        doc: &Document,
+        dem: Option<&Dem>,
+    ) -> Result<Self> {
         let metadatas = SkiAreaMetadata::find(doc)?;
         let metadata = metadatas.into_iter().next().ok_or_else(|| {
             Error::new_s(ErrorType::InputError, "ski area entity not found")
         })?;
 
         let config = get_config();
-        let lifts = find_lifts(cancel, doc)?;
+        let lifts = find_lifts(cancel, doc, dem)?;
 
         if config.is_v() {
             eprintln!("Found {} lifts.", lifts.len());
@@ -152,7 +220,26 @@ impl SkiArea {
             eprintln!("Found {} pistes.", pistes.len());
         }
 
-        SkiArea::new(metadata, lifts, pistes, doc.osm3s.timestamp_osm_base)
+        let trails = Trails::parse(cancel, doc)?;
+
+        if config.is_v() {
+            eprintln!(
+                "Found {} nordic trails, {} sled runs, {} ski touring routes \
+                and {} bike trails.",
+                trails.nordic.len(),
+                trails.sled.len(),
+                trails.skitour.len(),
+                trails.bike.len()
+            );
+        }
+
+        SkiArea::with_trails(
+            metadata,
+            lifts,
+            pistes,
+            trails,
+            doc.osm3s.timestamp_osm_base,
+        )
     }
 
     pub fn new(
@@ -160,12 +247,23 @@ impl SkiArea {
         lifts: HashMap<String, Lift>,
         pistes: HashMap<String, Piste>,
         date: OffsetDateTime,
+    ) -> Result<Self> {
+        Self::with_trails(metadata, lifts, pistes, Trails::default(), date)
+    }
+
+    pub fn with_trails(
+        metadata: SkiAreaMetadata,
+        lifts: HashMap<String, Lift>,
+        pistes: HashMap<String, Piste>,
+        trails: Trails,
+        date: OffsetDateTime,
     ) -> Result<Self> {
         let bounding_rect = union_rects_all(
             lifts
                 .values()
                 .map(|l| l.line.bounding_rect)
-                .chain(pistes.values().map(|p| p.data.bounding_rect)),
+                .chain(pistes.values().map(|p| p.data.bounding_rect))
+                .chain(trails.iter().map(|t| t.line.bounding_rect)),
         )
         .ok_or_else(|| Error::new_s(ErrorType::OSMError, "Empty ski area"))?;
 
@@ -173,13 +271,100 @@ impl SkiArea {
             metadata,
             lifts,
             pistes,
+            nordic_trails: trails.nordic,
+            sled_runs: trails.sled,
+            skitour_routes: trails.skitour,
+            bike_trails: trails.bike,
             bounding_rect,
             date,
+            index: LazySpatialIndex::default(),
         })
     }
 
+    /// A ski area for analyzing summer routes. Bike trails take the place of
+    /// the pistes, and only the lifts that run in summer are kept.
+    pub fn bike_park(&self) -> Result<Self> {
+        SkiArea::new(
+            self.metadata.clone(),
+            self.lifts
+                .iter()
+                .filter(|(_, l)| l.runs_in_summer())
+                .map(|(id, l)| (id.clone(), l.clone()))
+                .collect(),
+            self.bike_trails
+                .iter()
+                .map(|(id, t)| (id.clone(), t.to_piste()))
+                .collect(),
+            self.date,
+        )
+    }
+
     pub fn clip_piste_lines(&mut self) {
-        self.pistes.values_mut().for_each(|p| p.clip_lines());
+        self.pistes_mut().values_mut().for_each(|p| p.clip_lines());
+    }
+
+    /// The spatial index is built on first use, and reset by the methods
+    /// that give mutable access to the entities.
+    pub fn index(&self) -> &SpatialIndex {
+        self.index.get(self)
+    }
+
+    pub fn lifts(&self) -> &HashMap<String, Lift> {
+        &self.lifts
+    }
+
+    pub fn lifts_mut(&mut self) -> &mut HashMap<String, Lift> {
+        self.index.reset();
+        &mut self.lifts
+    }
+
+    pub fn pistes(&self) -> &HashMap<String, Piste> {
+        &self.pistes
+    }
+
+    pub fn pistes_mut(&mut self) -> &mut HashMap<String, Piste> {
+        self.index.reset();
+        &mut self.pistes
+    }
+
+    /// Cross-country skiing trails.
+    pub fn nordic_trails(&self) -> &HashMap<String, Trail> {
+        &self.nordic_trails
+    }
+
+    pub fn nordic_trails_mut(&mut self) -> &mut HashMap<String, Trail> {
+        self.index.reset();
+        &mut self.nordic_trails
+    }
+
+    /// Sledding and tobogganing runs.
+    pub fn sled_runs(&self) -> &HashMap<String, Trail> {
+        &self.sled_runs
+    }
+
+    pub fn sled_runs_mut(&mut self) -> &mut HashMap<String, Trail> {
+        self.index.reset();
+        &mut self.sled_runs
+    }
+
+    /// Marked ski touring and skin track routes.
+    pub fn skitour_routes(&self) -> &HashMap<String, Trail> {
+        &self.skitour_routes
+    }
+
+    pub fn skitour_routes_mut(&mut self) -> &mut HashMap<String, Trail> {
+        self.index.reset();
+        &mut self.skitour_routes
+    }
+
+    /// Downhill mountain bike trails, used in summer.
+    pub fn bike_trails(&self) -> &HashMap<String, Trail> {
+        &self.bike_trails
+    }
+
+    pub fn bike_trails_mut(&mut self) -> &mut HashMap<String, Trail> {
+        self.index.reset();
+        &mut self.bike_trails
     }
 
     pub fn get_closest_lift<'a>(
@@ -188,8 +373,9 @@ impl SkiArea {
         limit: f64,
     ) -> Option<(&'a str, f64)> {
         let (lift_id, c) = self
-            .lifts
-            .iter()
+            .index()
+            .lifts_near(p, limit)
+            .filter_map(|id| Some((id, self.lifts.get(id)?)))
             .filter(|(_, l)| l.line.expanded_rect(limit).intersects(&p))
             .filter_map(|(id, l)| Some((id, l.get_closest_point(p)?)))
             .min_by(|(_, c1), (_, c2)| c1.distance.total_cmp(&c2.distance))?;
@@ -5,13 +5,15 @@ use geo::{
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumString;
 
+use std::collections::HashMap;
 use std::str::FromStr;
 
 use super::PointWithElevation;
 use crate::config::get_config;
+use crate::dem::Dem;
 use crate::error::{Error, ErrorType, Result};
 use crate::osm_reader::{
-    get_tag, parse_ele, parse_way, parse_yesno, Document, Node, Way,
+    get_tag, parse_ele, parse_way, parse_yesno, Document, Node, Tags, Way,
 };
 use crate::utils::bounded_geometry::BoundedGeometry;
 
@@ -25,6 +27,27 @@ pub struct LiftClosestPoint {
     pub distance: f64,
 }
 
+/// Properties of the lift from the `aerialway:*` and `opening_hours` tags.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct LiftAttributes {
+    /// Persons per hour.
+    pub capacity: Option<u32>,
+    /// Persons per chair or cabin.
+    pub occupancy: Option<u32>,
+    /// Nominal ride time from the first to the last station, in seconds.
+    pub duration: Option<f64>,
+    pub heating: Option<bool>,
+    pub bubble: Option<bool>,
+    pub detachable: Option<bool>,
+    /// Unparsed, in the OSM opening hours syntax.
+    pub opening_hours: Option<String>,
+    /// The lift runs in summer, from `aerialway:summer:access` or
+    /// `aerialway:bicycle`.
+    pub summer: Option<bool>,
+}
+
 #[cfg_attr(feature = "specta", derive(specta::Type))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Lift {
@@ -39,6 +62,11 @@ pub struct Lift {
     pub can_go_reverse: bool,
     pub can_disembark: bool,
     pub lengths: Vec<f64>,
+    /// At least part of the lift runs in a tunnel, so GPS is unreliable.
+    #[serde(default)]
+    pub underground: bool,
+    #[serde(default)]
+    pub attributes: LiftAttributes,
 }
 
 impl Lift {
@@ -67,6 +95,37 @@ impl Lift {
             })
             .min_by(|d1, d2| d1.distance.total_cmp(&d2.distance))
     }
+
+    /// The nominal ride time between two stations, in seconds, assuming
+    /// constant speed. A missing station means the end of the lift.
+    pub fn nominal_duration(
+        &self,
+        begin_station: Option<usize>,
+        end_station: Option<usize>,
+    ) -> Option<f64> {
+        let duration = self.attributes.duration?;
+        let total: f64 = self.lengths.iter().sum();
+        if total <= 0.0 {
+            return Some(duration);
+        }
+        let begin = begin_station.unwrap_or(0);
+        let end = end_station.unwrap_or(self.lengths.len());
+        let (from, to) = (begin.min(end), begin.max(end));
+        let length: f64 = self
+            .lengths
+            .get(from..to.min(self.lengths.len()))
+            .map_or(0.0, |l| l.iter().sum());
+        Some(duration * length / total)
+    }
+
+    /// Surface lifts are assumed to be closed in summer unless tagged
+    /// otherwise, other lifts to be open.
+    pub fn runs_in_summer(&self) -> bool {
+        self.attributes.summer.unwrap_or_else(|| {
+            !DRAGLIFT_TYPES.contains(&self.type_.as_str())
+                && !["magic_carpet", "zip_line"].contains(&self.type_.as_str())
+        })
+    }
 }
 
 impl PartialEq for Lift {
@@ -77,6 +136,8 @@ impl PartialEq for Lift {
             && self.line == other.line
             && self.can_go_reverse == other.can_go_reverse
             && self.can_disembark == other.can_disembark
+            && self.underground == other.underground
+            && self.attributes == other.attributes
     }
 }
 
@@ -92,6 +153,8 @@ enum AccessType {
 
 fn is_station(node: &Node) -> bool {
     get_tag(&node.tags, "aerialway") == "station"
+        || RAILWAY_STATION_TYPES.contains(&get_tag(&node.tags, "railway"))
+        || get_tag(&node.tags, "public_transport") == "stop_position"
 }
 
 fn get_access(node: &Node) -> AccessType {
@@ -127,18 +190,27 @@ const IGNORED_TYPES: &[&str] =
 const DRAGLIFT_TYPES: &[&str] =
     &["drag_lift", "t-bar", "j-bar", "platter", "rope_tow"];
 
-// TODO: handle funiculars
-pub fn parse_lift<'d>(
-    doc: &'d Document,
-    id: &u64,
-    way: &Way,
-) -> Result<Option<Lift>> {
-    if get_tag(&way.tags, "area") == "yes" {
-        return Ok(None);
+const RAILWAY_TYPES: &[&str] = &["funicular", "rack", "subway"];
+// These are only lifts if they have a rack.
+const RACK_RAILWAY_TYPES: &[&str] = &["rail", "narrow_gauge", "light_rail"];
+const RAILWAY_STATION_TYPES: &[&str] = &["station", "halt", "stop"];
+
+fn get_railway_type(tags: &Tags) -> Option<&str> {
+    let railway_type = get_tag(tags, "railway");
+    if RAILWAY_TYPES.contains(&railway_type) {
+        Some(railway_type)
+    } else if RACK_RAILWAY_TYPES.contains(&railway_type)
+        && !["", "no"].contains(&get_tag(tags, "rack"))
+    {
+        Some("rack")
+    } else {
+        None
     }
+}
 
-    let Some(aerialway_type) = way.tags.get("aerialway") else {
-        return Ok(None);
+fn get_lift_type(tags: &Tags) -> Result<Option<&str>> {
+    let Some(aerialway_type) = tags.get("aerialway") else {
+        return Ok(get_railway_type(tags));
     };
 
     if IGNORED_TYPES.contains(&aerialway_type.as_str()) {
@@ -150,6 +222,199 @@ pub fn parse_lift<'d>(
             format!("invalid lift type: {}", aerialway_type),
         ));
     }
+    Ok(Some(aerialway_type))
+}
+
+fn is_underground(tags: &Tags) -> bool {
+    !["", "no"].contains(&get_tag(tags, "tunnel"))
+        || get_tag(tags, "location") == "underground"
+        || get_tag(tags, "layer").parse::<i32>().is_ok_and(|l| l < 0)
+}
+
+/// Durations are in minutes, or in mm:ss or hh:mm:ss format.
+fn parse_duration(value: &str) -> Option<f64> {
+    let parts = value
+        .split(':')
+        .map(|p| p.trim().parse::<f64>().ok().filter(|n| *n >= 0.0))
+        .collect::<Option<Vec<_>>>()?;
+    match parts.as_slice() {
+        [minutes] => Some(minutes * 60.0),
+        [minutes, seconds] => Some(minutes * 60.0 + seconds),
+        [hours, minutes, seconds] => {
+            Some(hours * 3600.0 + minutes * 60.0 + seconds)
+        }
+        _ => None,
+    }
+}
+
+fn parse_attributes(id: &u64, tags: &Tags) -> LiftAttributes {
+    let warn = |name: &str, value: &str| {
+        if get_config().is_vv() {
+            eprintln!("{}: invalid {}: {}", id, name, value);
+        }
+    };
+    let number = |name: &str| {
+        let value = get_tag(tags, name);
+        if value.is_empty() {
+            return None;
+        }
+        let result = value.trim().parse::<u32>().ok();
+        if result.is_none() {
+            warn(name, value);
+        }
+        result
+    };
+    let yesno = |name: &str| {
+        let value = get_tag(tags, name);
+        parse_yesno(value).unwrap_or_else(|_| {
+            warn(name, value);
+            None
+        })
+    };
+
+    let duration_str = get_tag(tags, "aerialway:duration");
+    let duration = if duration_str.is_empty() {
+        None
+    } else {
+        let duration = parse_duration(duration_str);
+        if duration.is_none() {
+            warn("aerialway:duration", duration_str);
+        }
+        duration
+    };
+
+    let summer = match get_tag(tags, "aerialway:summer:access") {
+        "" => match get_tag(tags, "aerialway:bicycle") {
+            "" => None,
+            "no" => Some(false),
+            _ => Some(true),
+        },
+        "no" => Some(false),
+        _ => Some(true),
+    };
+
+    LiftAttributes {
+        capacity: number("aerialway:capacity"),
+        occupancy: number("aerialway:occupancy"),
+        duration,
+        heating: yesno("aerialway:heating"),
+        bubble: yesno("aerialway:bubble"),
+        detachable: yesno("aerialway:detachable"),
+        opening_hours: tags.get("opening_hours").cloned(),
+        summer,
+    }
+}
+
+pub fn is_railway_lift(tags: &Tags) -> bool {
+    !tags.contains_key("aerialway") && get_railway_type(tags).is_some()
+}
+
+/// Railways are often split into several ways, for example at tunnels and
+/// bridges. Join the parts of the same railway lift into one way, identified
+/// by the id of its first part.
+pub fn join_railways(doc: &Document) -> Vec<(u64, Way)> {
+    let mut railways: Vec<(u64, &Way)> = doc
+        .elements
+        .ways
+        .iter()
+        .filter(|(_, way)| {
+            is_railway_lift(&way.tags) && get_tag(&way.tags, "area") != "yes"
+        })
+        .map(|(id, way)| (*id, way))
+        .collect();
+    railways.sort_by_key(|(id, _)| *id);
+
+    let mut endpoints: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, (_, way)) in railways.iter().enumerate() {
+        if let (Some(first), Some(last)) = (way.nodes.first(), way.nodes.last())
+        {
+            endpoints.entry(*first).or_default().push(i);
+            endpoints.entry(*last).or_default().push(i);
+        }
+    }
+
+    let is_same_lift = |lhs: &Tags, rhs: &Tags| {
+        get_railway_type(lhs) == get_railway_type(rhs)
+            && get_tag(lhs, "name") == get_tag(rhs, "name")
+            && get_tag(lhs, "ref") == get_tag(rhs, "ref")
+    };
+
+    let mut used = vec![false; railways.len()];
+    let mut result = Vec::new();
+    for i in 0..railways.len() {
+        if used[i] || railways[i].1.nodes.is_empty() {
+            continue;
+        }
+        used[i] = true;
+        let (id, first_way) = railways[i];
+        let mut nodes = first_way.nodes.clone();
+        let mut underground = is_underground(&first_way.tags);
+
+        for forward in [true, false] {
+            loop {
+                let end = if forward {
+                    *nodes.last().unwrap()
+                } else {
+                    nodes[0]
+                };
+                // Don't join at junctions.
+                let Some([a, b]) = endpoints.get(&end).map(Vec::as_slice)
+                else {
+                    break;
+                };
+                let j = if used[*a] { *b } else { *a };
+                let way = railways[j].1;
+                if used[j] || !is_same_lift(&first_way.tags, &way.tags) {
+                    break;
+                }
+                used[j] = true;
+                underground |= is_underground(&way.tags);
+
+                let mut part = way.nodes.clone();
+                if (part[0] == end) != forward {
+                    part.reverse();
+                }
+                if forward {
+                    nodes.extend_from_slice(&part[1..]);
+                } else {
+                    part.pop();
+                    part.append(&mut nodes);
+                    nodes = part;
+                }
+            }
+        }
+
+        let mut tags = first_way.tags.clone();
+        if underground && !is_underground(&tags) {
+            tags.insert("tunnel".to_string(), "yes".to_string());
+        }
+        result.push((
+            id,
+            Way {
+                nodes,
+                geometry: Vec::new(),
+                tags,
+            },
+        ));
+    }
+
+    result
+}
+
+pub fn parse_lift<'d>(
+    doc: &'d Document,
+    id: &u64,
+    way: &Way,
+    dem: Option<&Dem>,
+) -> Result<Option<Lift>> {
+    if get_tag(&way.tags, "area") == "yes" {
+        return Ok(None);
+    }
+
+    let Some(lift_type) = get_lift_type(&way.tags)? else {
+        return Ok(None);
+    };
+    let is_railway = RAILWAY_TYPES.contains(&lift_type);
 
     if way.nodes.len() < 2 {
         return Err(Error::new_s(
@@ -176,12 +441,19 @@ pub fn parse_lift<'d>(
     struct StationInfos<'a>(Vec<StationInfo<'a>>);
 
     impl<'a> StationInfos<'a> {
-        fn add(&mut self, num: usize, node: &'a Node) {
+        fn add(&mut self, num: usize, node: &'a Node, dem: Option<&Dem>) {
+            let point = node.coordinate.to_point();
+            let ele = parse_ele(&node.tags).unwrap_or_else(|err| {
+                if get_config().is_vv() {
+                    eprintln!("Station at {:?}: {}", point, err);
+                }
+                None
+            });
+            // Prefer the elevation from OSM, the elevation model is less
+            // accurate on steep terrain.
+            let elevation = ele.or_else(|| dem?.elevation(point));
             self.0.push(StationInfo {
-                station: PointWithElevation::new(
-                    node.coordinate.to_point(),
-                    parse_ele(&node.tags),
-                ),
+                station: PointWithElevation::new(point, elevation),
                 node,
                 num,
             });
@@ -191,26 +463,26 @@ pub fn parse_lift<'d>(
     let mut station_infos = StationInfos::default();
     let config = get_config();
 
-    station_infos.add(0, begin_node);
+    station_infos.add(0, begin_node, dem);
     for (i, n) in doc
         .elements
         .iterate_nodes(midpoints.iter())
         .enumerate()
         .filter(|(_, r)| r.as_ref().map_or(true, |n| is_station(n)))
     {
-        station_infos.add(i + 1, n?);
+        station_infos.add(i + 1, n?, dem);
     }
-    station_infos.add(way.nodes.len() - 1, end_node);
+    station_infos.add(way.nodes.len() - 1, end_node, dem);
 
     let mut name = get_tag(&way.tags, "name").to_string();
     let ref_ = get_tag(&way.tags, "ref").to_string();
 
     if name == "" {
         if config.is_vv() {
-            eprintln!("{} {}: {} lift has no name", id, ref_, aerialway_type);
+            eprintln!("{} {}: {} lift has no name", id, ref_, lift_type);
         }
         name = if ref_ == "" {
-            format!("<unnamed {}>", aerialway_type)
+            format!("<unnamed {}>", lift_type)
         } else {
             ref_.clone()
         };
@@ -229,8 +501,10 @@ pub fn parse_lift<'d>(
             AccessType::Unknown => {
                 let can_go_reverse = match oneway {
                     Some(val) => !val,
-                    None => ["cable_car", "gondola"]
-                        .contains(&aerialway_type.as_str()),
+                    None => {
+                        is_railway
+                            || ["cable_car", "gondola"].contains(&lift_type)
+                    }
                 };
                 (false, can_go_reverse, false)
             }
@@ -318,16 +592,18 @@ pub fn parse_lift<'d>(
     }
 
     let line = BoundedGeometry::new(LineString::new(line_points))?;
-    let can_disembark = DRAGLIFT_TYPES.contains(&aerialway_type.as_str());
+    let can_disembark = DRAGLIFT_TYPES.contains(&lift_type);
 
     Ok(Some(Lift {
         ref_,
         name,
-        type_: aerialway_type.clone(),
+        type_: lift_type.to_string(),
         line,
         stations,
         can_go_reverse,
         can_disembark,
         lengths,
+        underground: lift_type == "subway" || is_underground(&way.tags),
+        attributes: parse_attributes(id, &way.tags),
     }))
 }
@@ -1,14 +1,16 @@
-use super::lift::parse_lift;
-use super::{Lift, PointWithElevation};
+use super::lift::{join_railways, parse_lift};
+use super::{Lift, LiftAttributes, PointWithElevation};
+use crate::dem::Dem;
 use crate::osm_reader::{self as r, Osm3s};
 use crate::utils::bounded_geometry::BoundedGeometry;
 use crate::utils::test_util::{
-    assert_eq_pretty, init, line, node, node_tags, way_tags, Init,
+    assert_eq_pretty, init, line, node, node_tags, save_hgt, way_tags, Init,
 };
 
 use geo::point;
 use rstest::rstest;
 use std::collections::HashMap;
+use std::fs;
 
 #[rstest]
 fn simple_minimal_info(_init: Init) {
@@ -30,7 +32,8 @@ fn simple_minimal_info(_init: Init) {
     };
 
     let actual =
-        parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap()).unwrap();
+        parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap(), None)
+            .unwrap();
     let expected = Some(Lift {
         ref_: String::new(),
         name: "<unnamed chair_lift>".to_string(),
@@ -43,12 +46,14 @@ fn simple_minimal_info(_init: Init) {
         ]))
         .unwrap(),
         stations: vec![
-            PointWithElevation::new(point! {x: 0.0, y: 0.0}, 0),
-            PointWithElevation::new(point! {x: 3.0, y: 0.0}, 0),
+            PointWithElevation::new(point! {x: 0.0, y: 0.0}, None),
+            PointWithElevation::new(point! {x: 3.0, y: 0.0}, None),
         ],
         can_go_reverse: false,
         can_disembark: false,
         lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
     });
     assert_eq_pretty!(actual, expected);
 }
@@ -90,7 +95,8 @@ fn simple_more_info(_init: Init) {
     };
 
     let actual =
-        parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap()).unwrap();
+        parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap(), None)
+            .unwrap();
     let expected = Some(Lift {
         ref_: "A".to_string(),
         name: "Lift 1".to_string(),
@@ -103,12 +109,14 @@ fn simple_more_info(_init: Init) {
         ]))
         .unwrap(),
         stations: vec![
-            PointWithElevation::new(point! {x: 0.0, y: 1.0}, 1000),
-            PointWithElevation::new(point! {x: 3.0, y: 1.0}, 1400),
+            PointWithElevation::new(point! {x: 0.0, y: 1.0}, Some(1000.0)),
+            PointWithElevation::new(point! {x: 3.0, y: 1.0}, Some(1400.0)),
         ],
         can_go_reverse: false,
         can_disembark: true,
         lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
     });
     assert_eq_pretty!(actual, expected);
 }
@@ -178,7 +186,8 @@ fn multiple_stations(_init: Init) {
     };
 
     let actual =
-        parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap()).unwrap();
+        parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap(), None)
+            .unwrap();
     let expected = Some(Lift {
         ref_: String::new(),
         name: "Lift 2".to_string(),
@@ -198,15 +207,375 @@ fn multiple_stations(_init: Init) {
         ]))
         .unwrap(),
         stations: vec![
-            PointWithElevation::new(point! {x: 0.0, y: 2.0}, 1000),
-            PointWithElevation::new(point! {x: 3.0, y: 2.0}, 1200),
-            PointWithElevation::new(point! {x: 5.0, y: 2.0}, 1400),
-            PointWithElevation::new(point! {x: 6.0, y: 2.0}, 1600),
-            PointWithElevation::new(point! {x: 10.0, y: 2.0}, 1800),
+            PointWithElevation::new(point! {x: 0.0, y: 2.0}, Some(1000.0)),
+            PointWithElevation::new(point! {x: 3.0, y: 2.0}, Some(1200.0)),
+            PointWithElevation::new(point! {x: 5.0, y: 2.0}, Some(1400.0)),
+            PointWithElevation::new(point! {x: 6.0, y: 2.0}, Some(1600.0)),
+            PointWithElevation::new(point! {x: 10.0, y: 2.0}, Some(1800.0)),
         ],
         can_go_reverse: true,
         can_disembark: false,
         lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
     });
     assert_eq_pretty!(actual, expected);
 }
+
+#[rstest]
+fn funicular(_init: Init) {
+    let doc = r::Document {
+        osm3s: Osm3s::default(),
+        elements: r::Elements {
+            nodes: HashMap::from([
+                (
+                    0,
+                    node_tags(
+                        0.0,
+                        3.0,
+                        &[("railway", "station"), ("ele", "1000")],
+                    ),
+                ),
+                (1, node(1.0, 3.0)),
+                (
+                    2,
+                    node_tags(
+                        2.0,
+                        3.0,
+                        &[
+                            ("public_transport", "stop_position"),
+                            ("ele", "1200"),
+                        ],
+                    ),
+                ),
+                (3, node_tags(3.0, 3.0, &[("railway", "halt")])),
+            ]),
+            ways: HashMap::from([(
+                101,
+                way_tags(
+                    &[0, 1, 2, 3],
+                    &[("railway", "funicular"), ("name", "Funicular 1")],
+                ),
+            )]),
+            relations: HashMap::new(),
+        },
+    };
+
+    let actual =
+        parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap(), None)
+            .unwrap()
+            .unwrap();
+    let expected = Lift {
+        ref_: String::new(),
+        name: "Funicular 1".to_string(),
+        type_: "funicular".to_string(),
+        line: BoundedGeometry::new(line(&[
+            (0.0, 3.0),
+            (1.0, 3.0),
+            (2.0, 3.0),
+            (3.0, 3.0),
+        ]))
+        .unwrap(),
+        stations: vec![
+            PointWithElevation::new(point! {x: 0.0, y: 3.0}, Some(1000.0)),
+            PointWithElevation::new(point! {x: 2.0, y: 3.0}, Some(1200.0)),
+            PointWithElevation::new(point! {x: 3.0, y: 3.0}, None),
+        ],
+        can_go_reverse: true,
+        can_disembark: false,
+        lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
+    };
+    assert_eq_pretty!(actual, expected);
+    assert_eq_pretty!(actual.stations, expected.stations);
+    assert_eq!(actual.lengths.len(), 2);
+}
+
+#[rstest]
+#[case(&[("railway", "funicular")], Some("funicular"), false)]
+#[case(&[("railway", "subway")], Some("subway"), true)]
+#[case(&[("railway", "rack")], Some("rack"), false)]
+#[case(&[("railway", "rail"), ("rack", "riggenbach")], Some("rack"), false)]
+#[case(&[("railway", "narrow_gauge"), ("rack", "no")], None, false)]
+#[case(&[("railway", "rail")], None, false)]
+#[case(&[("railway", "funicular"), ("tunnel", "yes")], Some("funicular"), true)]
+#[case(&[("railway", "funicular"), ("tunnel", "no")], Some("funicular"), false)]
+#[case(&[("railway", "funicular"), ("layer", "-1")], Some("funicular"), true)]
+#[case(&[("railway", "funicular"), ("layer", "1")], Some("funicular"), false)]
+#[case(
+    &[("railway", "funicular"), ("location", "underground")],
+    Some("funicular"),
+    true
+)]
+#[case(&[("aerialway", "gondola"), ("tunnel", "yes")], Some("gondola"), true)]
+fn railway_type(
+    _init: Init,
+    #[case] tags: &[(&str, &str)],
+    #[case] type_: Option<&str>,
+    #[case] underground: bool,
+) {
+    let doc = r::Document {
+        osm3s: Osm3s::default(),
+        elements: r::Elements {
+            nodes: HashMap::from([(0, node(0.0, 4.0)), (1, node(1.0, 4.0))]),
+            ways: HashMap::from([(101, way_tags(&[0, 1], tags))]),
+            relations: HashMap::new(),
+        },
+    };
+
+    let actual =
+        parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap(), None)
+            .unwrap();
+    assert_eq!(actual.as_ref().map(|l| l.type_.as_str()), type_);
+    if let Some(lift) = actual {
+        assert_eq!(lift.underground, underground);
+    }
+}
+
+#[rstest]
+fn join_railway_parts(_init: Init) {
+    let funicular = [("railway", "funicular"), ("name", "Funicular 2")];
+    let doc = r::Document {
+        osm3s: Osm3s::default(),
+        elements: r::Elements {
+            nodes: HashMap::from([
+                (0, node(0.0, 5.0)),
+                (1, node(1.0, 5.0)),
+                (2, node(2.0, 5.0)),
+                (3, node(3.0, 5.0)),
+                (4, node(4.0, 5.0)),
+                (5, node(5.0, 5.0)),
+                (6, node(6.0, 5.0)),
+            ]),
+            ways: HashMap::from([
+                (101, way_tags(&[1, 2], &funicular)),
+                (102, way_tags(&[0, 1], &funicular)),
+                (
+                    103,
+                    way_tags(
+                        &[3, 2],
+                        &[
+                            ("railway", "funicular"),
+                            ("name", "Funicular 2"),
+                            ("tunnel", "yes"),
+                        ],
+                    ),
+                ),
+                (104, way_tags(&[3, 4], &funicular)),
+                (
+                    105,
+                    way_tags(
+                        &[4, 5],
+                        &[("railway", "funicular"), ("name", "Other")],
+                    ),
+                ),
+                (106, way_tags(&[4, 6], &funicular)),
+                (107, way_tags(&[5, 6], &[("aerialway", "chair_lift")])),
+            ]),
+            relations: HashMap::new(),
+        },
+    };
+
+    let actual: Vec<(u64, Vec<u64>, bool)> = join_railways(&doc)
+        .into_iter()
+        .map(|(id, way)| {
+            let lift = parse_lift(&doc, &id, &way, None).unwrap().unwrap();
+            (id, way.nodes, lift.underground)
+        })
+        .collect();
+    let expected = vec![
+        (101, vec![0, 1, 2, 3, 4], true),
+        (105, vec![4, 5], false),
+        (106, vec![4, 6], false),
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+fn station_elevation_from_dem(_init: Init) {
+    let dir = "test_output/lift_test/station_elevation_from_dem";
+    fs::create_dir_all(dir).unwrap();
+    save_hgt(
+        &[
+            &[2000, 2000, 2000],
+            &[1500, 1500, 1500],
+            &[1000, 1000, 1000],
+        ],
+        &format!("{}/N45E006.hgt", dir),
+    );
+    let dem = Dem::open(dir).unwrap();
+    let doc = r::Document {
+        osm3s: Osm3s::default(),
+        elements: r::Elements {
+            nodes: HashMap::from([
+                (0, node(6.5, 45.0)),
+                (1, node_tags(6.5, 45.75, &[("ele", "1900")])),
+            ]),
+            ways: HashMap::from([(
+                101,
+                way_tags(&[0, 1], &[("aerialway", "chair_lift")]),
+            )]),
+            relations: HashMap::new(),
+        },
+    };
+
+    let actual = parse_lift(
+        &doc,
+        &101,
+        doc.elements.ways.get(&101).unwrap(),
+        Some(&dem),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        actual.stations,
+        vec![
+            PointWithElevation::new(point! {x: 6.5, y: 45.0}, Some(1000.0)),
+            PointWithElevation::new(point! {x: 6.5, y: 45.75}, Some(1900.0)),
+        ]
+    );
+}
+
+#[rstest]
+#[case::legacy_unknown(r#"{"point":{"x":1.0,"y":2.0},"elevation":0}"#, None)]
+#[case::legacy(r#"{"point":{"x":1.0,"y":2.0},"elevation":1200}"#, Some(1200.0))]
+#[case::zero(r#"{"point":{"x":1.0,"y":2.0},"elevation":0.0}"#, Some(0.0))]
+#[case::negative(r#"{"point":{"x":1.0,"y":2.0},"elevation":-3.5}"#, Some(-3.5))]
+#[case::null(r#"{"point":{"x":1.0,"y":2.0},"elevation":null}"#, None)]
+#[case::missing(r#"{"point":{"x":1.0,"y":2.0}}"#, None)]
+fn station_elevation_deserialize(
+    _init: Init,
+    #[case] json: &str,
+    #[case] expected: Option<f64>,
+) {
+    let station: PointWithElevation = serde_json::from_str(json).unwrap();
+    assert_eq!(station.elevation, expected);
+}
+
+#[rstest]
+#[case(Some(0.0))]
+#[case(Some(1850.5))]
+#[case(None)]
+fn station_elevation_round_trip(_init: Init, #[case] elevation: Option<f64>) {
+    let station = PointWithElevation::new(point! {x: 1.0, y: 2.0}, elevation);
+    let json = serde_json::to_string(&station).unwrap();
+    let actual: PointWithElevation = serde_json::from_str(&json).unwrap();
+    assert_eq!(actual, station);
+}
+
+fn parse_simple_lift(tags: &[(&str, &str)]) -> Lift {
+    let doc = r::Document {
+        osm3s: Osm3s::default(),
+        elements: r::Elements {
+            nodes: HashMap::from([(0, node(0.0, 0.0)), (1, node(1.0, 0.0))]),
+            ways: HashMap::from([(101, way_tags(&[0, 1], tags))]),
+            relations: HashMap::new(),
+        },
+    };
+    parse_lift(&doc, &101, doc.elements.ways.get(&101).unwrap(), None)
+        .unwrap()
+        .unwrap()
+}
+
+#[rstest]
+#[case::none(&[], LiftAttributes::default())]
+#[case::all(
+    &[
+        ("aerialway:capacity", "2400"),
+        ("aerialway:occupancy", "6"),
+        ("aerialway:duration", "7"),
+        ("aerialway:heating", "yes"),
+        ("aerialway:bubble", "yes"),
+        ("aerialway:detachable", "no"),
+        ("opening_hours", "Dec-Apr 08:30-16:30"),
+        ("aerialway:summer:access", "entry;exit"),
+    ],
+    LiftAttributes {
+        capacity: Some(2400),
+        occupancy: Some(6),
+        duration: Some(420.0),
+        heating: Some(true),
+        bubble: Some(true),
+        detachable: Some(false),
+        opening_hours: Some("Dec-Apr 08:30-16:30".to_string()),
+        summer: Some(true),
+    }
+)]
+#[case::fractional_minutes(
+    &[("aerialway:duration", "2.5")],
+    LiftAttributes { duration: Some(150.0), ..LiftAttributes::default() }
+)]
+#[case::minutes_seconds(
+    &[("aerialway:duration", "04:30")],
+    LiftAttributes { duration: Some(270.0), ..LiftAttributes::default() }
+)]
+#[case::hours_minutes_seconds(
+    &[("aerialway:duration", "0:12:05")],
+    LiftAttributes { duration: Some(725.0), ..LiftAttributes::default() }
+)]
+#[case::invalid(
+    &[
+        ("aerialway:capacity", "many"),
+        ("aerialway:duration", "5 min"),
+        ("aerialway:heating", "sometimes"),
+    ],
+    LiftAttributes::default()
+)]
+fn attributes(
+    _init: Init,
+    #[case] tags: &[(&str, &str)],
+    #[case] expected: LiftAttributes,
+) {
+    let mut tags = tags.to_vec();
+    tags.push(("aerialway", "gondola"));
+    assert_eq!(parse_simple_lift(&tags).attributes, expected);
+}
+
+#[rstest]
+#[case::whole(None, None, Some(400.0))]
+#[case::first_section(Some(0), Some(1), Some(100.0))]
+#[case::reverse(Some(2), Some(1), Some(300.0))]
+#[case::to_end(Some(1), None, Some(300.0))]
+fn nominal_duration(
+    _init: Init,
+    #[case] begin: Option<usize>,
+    #[case] end: Option<usize>,
+    #[case] expected: Option<f64>,
+) {
+    let mut lift = parse_simple_lift(&[
+        ("aerialway", "gondola"),
+        ("aerialway:duration", "6:40"),
+    ]);
+    lift.lengths = vec![100.0, 300.0];
+    assert_eq!(lift.nominal_duration(begin, end), expected);
+}
+
+#[rstest]
+fn nominal_duration_unknown(_init: Init) {
+    let lift = parse_simple_lift(&[("aerialway", "gondola")]);
+    assert_eq!(lift.nominal_duration(None, None), None);
+}
+
+#[rstest]
+#[case::gondola(&[("aerialway", "gondola")], true)]
+#[case::drag_lift(&[("aerialway", "t-bar")], false)]
+#[case::summer_access(
+    &[("aerialway", "t-bar"), ("aerialway:summer:access", "entry")],
+    true
+)]
+#[case::closed(
+    &[("aerialway", "chair_lift"), ("aerialway:summer:access", "no")],
+    false
+)]
+#[case::no_bicycle(
+    &[("aerialway", "chair_lift"), ("aerialway:bicycle", "no")],
+    false
+)]
+fn runs_in_summer(
+    _init: Init,
+    #[case] tags: &[(&str, &str)],
+    #[case] expected: bool,
+) {
+    assert_eq!(parse_simple_lift(tags).runs_in_summer(), expected);
+}
@@ -12,7 +12,7 @@ use std::str::FromStr;
 use crate::config::get_config;
 use crate::error::Result;
 use crate::multipolygon::parse_multipolygon;
-use crate::osm_reader::{get_tag, parse_way, Document, Tags, Way};
+use crate::osm_reader::{get_tag, parse_way, Document, Relation, Tags, Way};
 use crate::utils::bounded_geometry::BoundedGeometry;
 use crate::utils::cancel::CancellationToken;
 use crate::utils::collection::max_if;
@@ -58,6 +58,111 @@ pub struct PisteMetadata {
     pub difficulty: Difficulty,
 }
 
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    EnumString,
+    strum_macros::Display,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum PisteType {
+    #[default]
+    Downhill,
+    Nordic,
+    Skitour,
+    Sled,
+    Hike,
+    Sleigh,
+    SnowPark,
+    Connection,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    EnumString,
+    strum_macros::Display,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum Grooming {
+    Classic,
+    Skating,
+    #[strum(serialize = "classic+skating")]
+    ClassicAndSkating,
+    Mogul,
+    Scooter,
+    /// Not groomed at all.
+    Backcountry,
+}
+
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    EnumString,
+    strum_macros::Display,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum PisteStatus {
+    Open,
+    Closed,
+    Abandoned,
+}
+
+/// Tags of the piste that are not used for telling pistes apart. When pieces
+/// of a piste are merged, the first known value of each tag is kept, in the
+/// order of their OSM IDs. The status is kept for each piece instead: pieces
+/// with a different status are never merged.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PisteAttributes {
+    pub piste_type: PisteType,
+    pub grooming: Option<Grooming>,
+    pub oneway: Option<bool>,
+    pub lit: Option<bool>,
+    pub status: Option<PisteStatus>,
+    pub snowmaking: Option<bool>,
+    /// Localized names from the `name:*` tags, by language code.
+    pub names: HashMap<String, String>,
+}
+
+impl PisteAttributes {
+    pub fn is_abandoned(&self) -> bool {
+        self.status == Some(PisteStatus::Abandoned)
+    }
+
+    pub(super) fn merge(&mut self, other: &PisteAttributes) {
+        self.grooming = self.grooming.or(other.grooming);
+        self.oneway = self.oneway.or(other.oneway);
+        self.lit = self.lit.or(other.lit);
+        self.snowmaking = self.snowmaking.or(other.snowmaking);
+        for (lang, name) in &other.names {
+            if !self.names.contains_key(lang) {
+                self.names.insert(lang.clone(), name.clone());
+            }
+        }
+    }
+}
+
 #[cfg_attr(feature = "specta", derive(specta::Type))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PisteData {
@@ -67,6 +172,8 @@ pub struct PisteData {
     pub areas: MultiPolygon,
     #[cfg_attr(feature = "specta", specta(type = MultiLineStringDef))]
     pub lines: MultiLineString,
+    #[serde(default)]
+    pub attributes: PisteAttributes,
 }
 
 impl geo::Intersects for PisteData {
@@ -94,7 +201,7 @@ impl Piste {
     }
 }
 
-fn parse_metadata(tags: &Tags) -> PisteMetadata {
+pub(super) fn parse_metadata(tags: &Tags) -> PisteMetadata {
     let mut name = get_tag(&tags, "piste:name");
     if name == "" {
         name = get_tag(&tags, "name");
@@ -127,10 +234,83 @@ fn parse_metadata(tags: &Tags) -> PisteMetadata {
     }
 }
 
+fn parse_bool_tag(tags: &Tags, name: &str) -> Option<bool> {
+    match get_tag(tags, name) {
+        "" => None,
+        "yes" => Some(true),
+        "no" => Some(false),
+        value => {
+            if get_config().is_vv() {
+                eprintln!("invalid value for {}: {}", name, value);
+            }
+            None
+        }
+    }
+}
+
+fn parse_enum_tag<T: FromStr>(tags: &Tags, name: &str) -> Option<T> {
+    let value = get_tag(tags, name);
+    if value.is_empty() {
+        return None;
+    }
+    // Multiple values are sometimes separated with ';' instead of '+'.
+    let result = T::from_str(&value.replace(';', "+")).ok();
+    if result.is_none() && get_config().is_vv() {
+        eprintln!("invalid value for {}: {}", name, value);
+    }
+    result
+}
+
+pub(super) fn parse_attributes(tags: &Tags) -> PisteAttributes {
+    let mut status = parse_enum_tag(tags, "piste:status");
+    if get_tag(tags, "piste:abandoned") == "yes" {
+        status = Some(PisteStatus::Abandoned);
+    }
+
+    let mut names = HashMap::new();
+    for (key, value) in tags {
+        if let Some(lang) = key.strip_prefix("name:") {
+            names
+                .entry(lang.to_string())
+                .or_insert_with(|| value.clone());
+        }
+    }
+    // Piste specific names take precedence.
+    for (key, value) in tags {
+        if let Some(lang) = key.strip_prefix("piste:name:") {
+            names.insert(lang.to_string(), value.clone());
+        }
+    }
+
+    PisteAttributes {
+        piste_type: parse_enum_tag(tags, "piste:type").unwrap_or_default(),
+        grooming: parse_enum_tag(tags, "piste:grooming"),
+        oneway: parse_bool_tag(tags, "piste:oneway"),
+        lit: parse_bool_tag(tags, "piste:lit")
+            .or_else(|| parse_bool_tag(tags, "lit")),
+        status,
+        snowmaking: parse_bool_tag(tags, "piste:snowmaking")
+            .or_else(|| parse_bool_tag(tags, "snowmaking")),
+        names,
+    }
+}
+
 #[derive(Default, Debug)]
 struct PartialPistes {
     line_entities: Vec<WithId<BoundedGeometry<LineString>>>,
     area_entities: Vec<WithId<BoundedGeometry<MultiPolygon>>>,
+    attributes: PisteAttributes,
+    /// The status of the entities that have one, by entity ID.
+    statuses: HashMap<String, PisteStatus>,
+}
+
+impl PartialPistes {
+    fn add_attributes(&mut self, id: &str, attributes: &PisteAttributes) {
+        self.attributes.merge(attributes);
+        if let Some(status) = attributes.status {
+            self.statuses.insert(id.to_string(), status);
+        }
+    }
 }
 
 struct UnnamedPiste<T>
@@ -138,6 +318,7 @@ where
     T: BoundingRect<f64>,
 {
     difficulty: Difficulty,
+    attributes: PisteAttributes,
     geometry: WithId<BoundedGeometry<T>>,
 }
 
@@ -149,6 +330,7 @@ enum PisteGeometry {
 fn add_piste(
     id: String,
     metadata: PisteMetadata,
+    attributes: PisteAttributes,
     geometry: PisteGeometry,
     result: &mut HashMap<PisteMetadata, PartialPistes>,
     unnamed_lines: &mut Vec<UnnamedPiste<LineString>>,
@@ -159,12 +341,14 @@ fn add_piste(
             PisteGeometry::Area(a) => {
                 unnamed_areas.push(UnnamedPiste {
                     difficulty: metadata.difficulty,
+                    attributes,
                     geometry: WithId::new(id, a),
                 });
             }
             PisteGeometry::Line(l) => {
                 unnamed_lines.push(UnnamedPiste {
                     difficulty: metadata.difficulty,
+                    attributes,
                     geometry: WithId::new(id, l),
                 });
             }
@@ -173,6 +357,7 @@ fn add_piste(
     }
 
     let partial_piste = result.entry(metadata).or_default();
+    partial_piste.add_attributes(&id, &attributes);
 
     match geometry {
         PisteGeometry::Area(a) => {
@@ -272,7 +457,13 @@ fn parse_partial_pistes(
 
     let config = get_config();
 
-    for (id, relation) in &doc.elements.relations {
+    let mut relations: Vec<(&u64, &Relation)> =
+        doc.elements.relations.iter().collect();
+    relations.sort_by_key(|(id, _)| **id);
+    let mut ways: Vec<(&u64, &Way)> = doc.elements.ways.iter().collect();
+    ways.sort_by_key(|(id, _)| **id);
+
+    for (id, relation) in &relations {
         if get_tag(&relation.tags, "type") != "route"
             || get_tag(&relation.tags, "route") != "piste"
             || get_tag(&relation.tags, "piste:type") != "downhill"
@@ -296,7 +487,7 @@ fn parse_partial_pistes(
         }
     }
 
-    for (id, way) in &doc.elements.ways {
+    for (id, way) in &ways {
         if get_tag(&way.tags, "piste:type") != "downhill" {
             continue;
         }
@@ -304,7 +495,8 @@ fn parse_partial_pistes(
         match parse_partial_piste(&doc, &way) {
             Ok(geometry) => add_piste(
                 id.to_string(),
-                merge_route_metadata(*id, &way.tags, &route_index),
+                merge_route_metadata(**id, &way.tags, &route_index),
+                parse_attributes(&way.tags),
                 geometry,
                 &mut result,
                 &mut unnamed_lines,
@@ -314,7 +506,7 @@ fn parse_partial_pistes(
         };
     }
 
-    for (id, relation) in &doc.elements.relations {
+    for (id, relation) in &relations {
         if get_tag(&relation.tags, "type") != "multipolygon"
             || get_tag(&relation.tags, "piste:type") != "downhill"
         {
@@ -324,11 +516,13 @@ fn parse_partial_pistes(
         match parse_multipolygon(&doc, &relation) {
             Ok(mp) => {
                 let metadata = parse_metadata(&relation.tags);
+                let attributes = parse_attributes(&relation.tags);
                 for (i, p) in mp.0.into_iter().enumerate() {
                     match BoundedGeometry::new(MultiPolygon::new(vec![p])) {
                         Ok(geometry) => add_piste(
                             format!("{}_{}", id, i),
                             metadata.clone(),
+                            attributes.clone(),
                             PisteGeometry::Area(geometry),
                             &mut result,
                             &mut unnamed_lines,
@@ -449,30 +643,46 @@ fn find_anomalies(pistes: &Vec<WithId<Piste>>) {
 
 fn line_to_piste(
     line: WithId<BoundedGeometry<LineString>>,
+    mut attributes: PisteAttributes,
+    status: Option<PisteStatus>,
 ) -> WithId<PisteData> {
+    attributes.status = status;
     WithId::new(
         line.id,
         PisteData {
             bounding_rect: line.obj.bounding_rect,
             areas: MultiPolygon::new(Vec::new()),
             lines: MultiLineString::new(vec![line.obj.item]),
+            attributes,
         },
     )
 }
 
 fn area_to_piste(
     area: WithId<BoundedGeometry<MultiPolygon>>,
+    mut attributes: PisteAttributes,
+    status: Option<PisteStatus>,
 ) -> WithId<PisteData> {
+    attributes.status = status;
     WithId::new(
         area.id,
         PisteData {
             bounding_rect: area.obj.bounding_rect,
             areas: area.obj.item,
             lines: MultiLineString::new(Vec::new()),
+            attributes,
         },
     )
 }
 
+fn can_merge_pistes(
+    target: &WithId<PisteData>,
+    source: &WithId<PisteData>,
+) -> bool {
+    target.obj.attributes.status == source.obj.attributes.status
+        && target.obj.intersects(&source.obj)
+}
+
 fn merge_pistes(
     target: &mut WithId<PisteData>,
     source: &mut WithId<PisteData>,
@@ -481,6 +691,7 @@ fn merge_pistes(
     target.obj.areas.0.append(&mut source.obj.areas.0);
     target.obj.bounding_rect =
         union_rects(target.obj.bounding_rect, source.obj.bounding_rect);
+    target.obj.attributes.merge(&source.obj.attributes);
     target.id.push('_');
     target.id.extend([std::mem::take(&mut source.id)]);
 }
@@ -491,7 +702,7 @@ fn merge_intersecting_pistes(pistes: &mut Vec<WithId<PisteData>>) {
         let mut changed = false;
         let mut j = i + 1;
         while j < pistes.len() {
-            if pistes[i].obj.intersects(&pistes[j].obj) {
+            if can_merge_pistes(&pistes[i], &pistes[j]) {
                 let mut item = pistes.remove(j);
                 merge_pistes(&mut pistes[i], &mut item);
                 changed = true;
@@ -527,16 +738,20 @@ fn merge_partial_pistes(
             continue;
         }
 
+        let attributes = &partial_piste.attributes;
+        let statuses = &partial_piste.statuses;
+        let status = |id: &str| statuses.get(id).copied();
         let mut datas: Vec<WithId<PisteData>> = partial_piste
             .line_entities
             .into_iter()
-            .map(|line| line_to_piste(line))
-            .chain(
-                partial_piste
-                    .area_entities
-                    .into_iter()
-                    .map(|area| area_to_piste(area)),
-            )
+            .map(|line| {
+                let status = status(&line.id);
+                line_to_piste(line, attributes.clone(), status)
+            })
+            .chain(partial_piste.area_entities.into_iter().map(|area| {
+                let status = status(&area.id);
+                area_to_piste(area, attributes.clone(), status)
+            }))
             .collect();
 
         match &mut refless {
@@ -555,7 +770,7 @@ fn merge_partial_pistes(
                     {
                         for data in datas.iter_mut() {
                             for refless_piste in refless_datas.iter_mut() {
-                                if data.obj.intersects(&refless_piste.obj) {
+                                if can_merge_pistes(data, refless_piste) {
                                     merge_pistes(data, refless_piste);
                                     changed = true;
                                 }
@@ -650,16 +865,18 @@ fn merge_unnamed_pistes(
     let mut pistes: HashMap<Difficulty, Vec<WithId<PisteData>>> =
         HashMap::new();
     for line in unnamed_lines {
+        let status = line.attributes.status;
         pistes
             .entry(line.difficulty)
             .or_default()
-            .push(line_to_piste(line.geometry));
+            .push(line_to_piste(line.geometry, line.attributes, status));
     }
     for area in unnamed_areas {
+        let status = area.attributes.status;
         pistes
             .entry(area.difficulty)
             .or_default()
-            .push(area_to_piste(area.geometry));
+            .push(area_to_piste(area.geometry, area.attributes, status));
     }
 
     for mut datas in pistes.values_mut() {
@@ -714,6 +931,7 @@ where
         );
         match target {
             Some((_, piste)) => {
+                piste.add_attributes(&g1.geometry.id, &g1.attributes);
                 get_entity(piste).push(g1.geometry);
                 changed = true;
             }
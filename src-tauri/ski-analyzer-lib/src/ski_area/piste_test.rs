@@ -1,5 +1,8 @@
 use super::piste::parse_pistes;
-use super::{Difficulty, Piste, PisteData, PisteMetadata, SkiArea};
+use super::{
+    Difficulty, Grooming, Piste, PisteAttributes, PisteData, PisteMetadata,
+    PisteStatus, PisteType, SkiArea,
+};
 use crate::osm_reader::{
     Coordinate, Document, Node, Relation, RelationMember, RelationMembers,
     Tags, Way,
@@ -261,6 +264,7 @@ impl PisteOut {
                 areas,
                 lines,
                 bounding_rect,
+                attributes: PisteAttributes::default(),
             },
         }
     }
@@ -649,6 +653,175 @@ fn metadata_alternate_naming(_init: Init, line0: Line) {
     assert_eq!(piste.metadata.difficulty, Difficulty::Easy);
 }
 
+#[rstest]
+fn attributes(_init: Init, line0: Line) {
+    let document = create_document(vec![WayDef {
+        line: line0,
+        tags: vec![
+            ("piste:type", "downhill"),
+            ("piste:difficulty", "expert"),
+            ("name", "Buckelpiste"),
+            ("name:en", "Mogul Run"),
+            ("name:fr", "Piste de bosses"),
+            ("piste:name:fr", "Bosses"),
+            ("piste:grooming", "mogul"),
+            ("piste:oneway", "yes"),
+            ("piste:lit", "no"),
+            ("piste:status", "closed"),
+            ("snowmaking", "yes"),
+        ],
+    }]);
+
+    let pistes = parse_pistes(&CancellationToken::new(), &document).unwrap();
+
+    assert_eq!(pistes.len(), 1);
+    let piste = pistes.iter().next().unwrap().1;
+    assert_eq!(
+        piste.data.attributes,
+        PisteAttributes {
+            piste_type: PisteType::Downhill,
+            grooming: Some(Grooming::Mogul),
+            oneway: Some(true),
+            lit: Some(false),
+            status: Some(PisteStatus::Closed),
+            snowmaking: Some(true),
+            names: HashMap::from([
+                ("en".to_string(), "Mogul Run".to_string()),
+                ("fr".to_string(), "Bosses".to_string()),
+            ]),
+        }
+    );
+}
+
+#[rstest]
+#[case::none(vec![], PisteAttributes::default())]
+#[case::invalid(
+    vec![("piste:grooming", "foobar"), ("piste:lit", "maybe")],
+    PisteAttributes::default()
+)]
+#[case::multiple_groomings(
+    vec![("piste:grooming", "classic;skating")],
+    PisteAttributes {
+        grooming: Some(Grooming::ClassicAndSkating),
+        ..PisteAttributes::default()
+    }
+)]
+#[case::abandoned(
+    vec![("piste:abandoned", "yes")],
+    PisteAttributes {
+        status: Some(PisteStatus::Abandoned),
+        ..PisteAttributes::default()
+    }
+)]
+fn attributes_values(
+    _init: Init,
+    line0: Line,
+    #[case] extra_tags: TagsDef,
+    #[case] expected: PisteAttributes,
+) {
+    let mut tags = vec![("piste:type", "downhill"), ("name", "Piste 1")];
+    tags.extend(extra_tags);
+    let document = create_document(vec![WayDef { line: line0, tags }]);
+
+    let pistes = parse_pistes(&CancellationToken::new(), &document).unwrap();
+
+    assert_eq!(pistes.len(), 1);
+    assert_eq!(pistes.iter().next().unwrap().1.data.attributes, expected);
+}
+
+#[rstest]
+fn attributes_merged(_init: Init, line0: Line, line1: Line) {
+    let document = create_document(vec![
+        WayDef {
+            line: line0,
+            tags: vec![
+                ("piste:type", "downhill"),
+                ("name", "Piste 1"),
+                ("piste:grooming", "mogul"),
+            ],
+        },
+        WayDef {
+            line: line1,
+            tags: vec![
+                ("piste:type", "downhill"),
+                ("name", "Piste 1"),
+                ("piste:lit", "yes"),
+            ],
+        },
+    ]);
+
+    let pistes = parse_pistes(&CancellationToken::new(), &document).unwrap();
+
+    for piste in pistes.values() {
+        assert_eq!(piste.data.attributes.grooming, Some(Grooming::Mogul));
+        assert_eq!(piste.data.attributes.lit, Some(true));
+    }
+}
+
+#[rstest]
+fn attributes_merged_in_id_order(_init: Init, line0: Line, line1: Line) {
+    let document = create_document(vec![
+        WayDef {
+            line: line0,
+            tags: vec![
+                ("piste:type", "downhill"),
+                ("name", "Piste 1"),
+                ("piste:grooming", "mogul"),
+            ],
+        },
+        WayDef {
+            line: line1,
+            tags: vec![
+                ("piste:type", "downhill"),
+                ("name", "Piste 1"),
+                ("piste:grooming", "classic"),
+            ],
+        },
+    ]);
+
+    for _ in 0..10 {
+        let pistes =
+            parse_pistes(&CancellationToken::new(), &document).unwrap();
+        for piste in pistes.values() {
+            assert_eq!(piste.data.attributes.grooming, Some(Grooming::Mogul));
+        }
+    }
+}
+
+#[rstest]
+fn status_kept_per_way(_init: Init, line0: Line) {
+    let document = create_document(vec![
+        WayDef {
+            line: line0[..LINE0_MIDPOINT + 1].to_vec(),
+            tags: vec![
+                ("piste:type", "downhill"),
+                ("name", "Piste 1"),
+                ("piste:grooming", "mogul"),
+            ],
+        },
+        WayDef {
+            line: line0[LINE0_MIDPOINT..].to_vec(),
+            tags: vec![
+                ("piste:type", "downhill"),
+                ("name", "Piste 1"),
+                ("piste:abandoned", "yes"),
+            ],
+        },
+    ]);
+
+    let pistes = parse_pistes(&CancellationToken::new(), &document).unwrap();
+
+    let mut statuses: Vec<Option<PisteStatus>> = pistes
+        .values()
+        .map(|p| {
+            assert_eq!(p.data.attributes.grooming, Some(Grooming::Mogul));
+            p.data.attributes.status
+        })
+        .collect();
+    statuses.sort_by_key(|s| s.is_some());
+    assert_eq!(statuses, vec![None, Some(PisteStatus::Abandoned)]);
+}
+
 #[rstest]
 #[named]
 fn find_areas_to_line(_init: Init, line0: Line, area00: Line, area01: Line) {
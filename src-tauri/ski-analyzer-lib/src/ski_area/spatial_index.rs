@@ -0,0 +1,159 @@
+use geo::{Point, Rect};
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::{SkiArea, Trail};
+use crate::utils::rect::expand_rect;
+
+/// Expanding a rectangle by a distance gives slightly different results at
+/// different latitudes, so make the search area a bit larger to make sure no
+/// entity is missed.
+const SEARCH_MARGIN: f64 = 1.01;
+
+type Entry = GeomWithData<Rectangle<[f64; 2]>, String>;
+
+fn to_entry(id: &str, rect: Rect) -> Entry {
+    GeomWithData::new(
+        Rectangle::from_corners(
+            rect.min().x_y().into(),
+            rect.max().x_y().into(),
+        ),
+        id.to_string(),
+    )
+}
+
+fn query(
+    tree: &RTree<Entry>,
+    p: Point,
+    distance: f64,
+) -> impl Iterator<Item = &str> {
+    let mut rect = Rect::new(p.0, p.0);
+    expand_rect(&mut rect, distance * SEARCH_MARGIN);
+    let envelope =
+        AABB::from_corners(rect.min().x_y().into(), rect.max().x_y().into());
+    tree.locate_in_envelope_intersecting(&envelope)
+        .map(|e| e.data.as_str())
+}
+
+/// Spatial index of the bounding rectangles of lifts, pistes and trails.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    lifts: RTree<Entry>,
+    pistes: RTree<Entry>,
+    nordic_trails: RTree<Entry>,
+    sled_runs: RTree<Entry>,
+    skitour_routes: RTree<Entry>,
+    bike_trails: RTree<Entry>,
+}
+
+fn trail_tree(trails: &HashMap<String, Trail>) -> RTree<Entry> {
+    RTree::bulk_load(
+        trails
+            .iter()
+            .map(|(id, t)| to_entry(id, t.line.bounding_rect))
+            .collect(),
+    )
+}
+
+impl SpatialIndex {
+    pub fn new(ski_area: &SkiArea) -> Self {
+        SpatialIndex {
+            lifts: RTree::bulk_load(
+                ski_area
+                    .lifts
+                    .iter()
+                    .map(|(id, l)| to_entry(id, l.line.bounding_rect))
+                    .collect(),
+            ),
+            pistes: RTree::bulk_load(
+                ski_area
+                    .pistes
+                    .iter()
+                    .map(|(id, p)| to_entry(id, p.data.bounding_rect))
+                    .collect(),
+            ),
+            nordic_trails: trail_tree(&ski_area.nordic_trails),
+            sled_runs: trail_tree(&ski_area.sled_runs),
+            skitour_routes: trail_tree(&ski_area.skitour_routes),
+            bike_trails: trail_tree(&ski_area.bike_trails),
+        }
+    }
+
+    /// Returns the IDs of the lifts whose bounding rectangle is at most
+    /// `distance` meters away from `p`, in no particular order.
+    pub fn lifts_near(
+        &self,
+        p: Point,
+        distance: f64,
+    ) -> impl Iterator<Item = &str> {
+        query(&self.lifts, p, distance)
+    }
+
+    /// Returns the IDs of the pistes whose bounding rectangle is at most
+    /// `distance` meters away from `p`, in no particular order.
+    pub fn pistes_near(
+        &self,
+        p: Point,
+        distance: f64,
+    ) -> impl Iterator<Item = &str> {
+        query(&self.pistes, p, distance)
+    }
+
+    /// Returns the IDs of the nordic trails whose bounding rectangle is at
+    /// most `distance` meters away from `p`, in no particular order.
+    pub fn nordic_trails_near(
+        &self,
+        p: Point,
+        distance: f64,
+    ) -> impl Iterator<Item = &str> {
+        query(&self.nordic_trails, p, distance)
+    }
+
+    /// Returns the IDs of the sled runs whose bounding rectangle is at most
+    /// `distance` meters away from `p`, in no particular order.
+    pub fn sled_runs_near(
+        &self,
+        p: Point,
+        distance: f64,
+    ) -> impl Iterator<Item = &str> {
+        query(&self.sled_runs, p, distance)
+    }
+
+    /// Returns the IDs of the ski touring routes whose bounding rectangle is
+    /// at most `distance` meters away from `p`, in no particular order.
+    pub fn skitour_routes_near(
+        &self,
+        p: Point,
+        distance: f64,
+    ) -> impl Iterator<Item = &str> {
+        query(&self.skitour_routes, p, distance)
+    }
+
+    /// Returns the IDs of the bike trails whose bounding rectangle is at most
+    /// `distance` meters away from `p`, in no particular order.
+    pub fn bike_trails_near(
+        &self,
+        p: Point,
+        distance: f64,
+    ) -> impl Iterator<Item = &str> {
+        query(&self.bike_trails, p, distance)
+    }
+}
+
+/// Lazily built [`SpatialIndex`]. It is not serialized, and it is rebuilt on
+/// first use after deserialization.
+#[derive(Debug, Clone, Default)]
+pub struct LazySpatialIndex(OnceLock<SpatialIndex>);
+
+impl LazySpatialIndex {
+    pub fn get(&self, ski_area: &SkiArea) -> &SpatialIndex {
+        self.0.get_or_init(|| SpatialIndex::new(ski_area))
+    }
+
+    pub fn reset(&mut self) {
+        self.0 = OnceLock::new();
+    }
+}
@@ -0,0 +1,168 @@
+use super::{Lift, LiftAttributes, PointWithElevation, SkiArea};
+use crate::utils::bounded_geometry::BoundedGeometry;
+use crate::utils::test_util::{
+    create_ski_area_metadata, init, line, piste, trail, Init,
+};
+
+use geo::{point, Destination, Haversine, Point};
+use rstest::{fixture, rstest};
+use time::OffsetDateTime;
+
+use std::collections::HashMap;
+
+fn lift(x: f64) -> Lift {
+    let begin = point! { x: x, y: 45.0 };
+    let end = point! { x: x, y: 45.01 };
+    Lift {
+        ref_: String::new(),
+        name: format!("{x}"),
+        type_: "chair_lift".to_string(),
+        stations: vec![
+            PointWithElevation::new(begin, Some(1000.0)),
+            PointWithElevation::new(end, Some(1500.0)),
+        ],
+        line: BoundedGeometry::new(line(&[begin.x_y(), end.x_y()])).unwrap(),
+        lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
+        can_go_reverse: false,
+        can_disembark: false,
+    }
+}
+
+/// 100 lifts and 100 pistes next to each other, 0.001 degrees (about 79 m)
+/// apart.
+#[fixture]
+fn ski_area() -> SkiArea {
+    let lifts = (0..100)
+        .map(|i| (format!("l{i}"), lift(6.0 + i as f64 * 0.001)))
+        .collect();
+    let pistes = (0..100)
+        .map(|i| {
+            let x = 6.0 + i as f64 * 0.001;
+            let p = piste(
+                &format!("p{i}"),
+                vec![line(&[(x, 45.02), (x, 45.03)])],
+                vec![],
+            );
+            (format!("p{i}"), p)
+        })
+        .collect();
+    SkiArea::new(
+        create_ski_area_metadata("index".to_string()),
+        lifts,
+        pistes,
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+fn sorted<'a>(it: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut result: Vec<_> = it.collect();
+    result.sort();
+    result
+}
+
+fn east_of(p: Point, distance: f64) -> Point {
+    Haversine::destination(p, 90.0, distance)
+}
+
+#[rstest]
+fn lifts_near(_init: Init, ski_area: SkiArea) {
+    let index = ski_area.index();
+    let p = point! { x: 6.0105, y: 45.005 };
+    assert_eq!(sorted(index.lifts_near(p, 10.0)), Vec::<&str>::new());
+    assert_eq!(sorted(index.lifts_near(p, 50.0)), vec!["l10", "l11"]);
+    assert_eq!(
+        sorted(index.lifts_near(p, 150.0)),
+        vec!["l10", "l11", "l12", "l9"]
+    );
+    assert_eq!(sorted(index.pistes_near(p, 150.0)), Vec::<&str>::new());
+}
+
+#[rstest]
+fn pistes_near(_init: Init, ski_area: SkiArea) {
+    let index = ski_area.index();
+    let p = east_of(point! { x: 6.099, y: 45.025 }, 30.0);
+    assert_eq!(sorted(index.pistes_near(p, 20.0)), Vec::<&str>::new());
+    assert_eq!(sorted(index.pistes_near(p, 40.0)), vec!["p99"]);
+    assert_eq!(sorted(index.lifts_near(p, 40.0)), Vec::<&str>::new());
+}
+
+#[rstest]
+fn trails_near(_init: Init, mut ski_area: SkiArea) {
+    let trail_at = |x: f64| trail("", &[(x, 45.0), (x, 45.01)]);
+    ski_area
+        .sled_runs_mut()
+        .insert("s".to_string(), trail_at(6.2));
+    ski_area
+        .skitour_routes_mut()
+        .insert("t".to_string(), trail_at(6.201));
+    ski_area
+        .bike_trails_mut()
+        .insert("b".to_string(), trail_at(6.202));
+    let index = ski_area.index();
+    let p = east_of(point! { x: 6.201, y: 45.005 }, 20.0);
+    assert_eq!(sorted(index.sled_runs_near(p, 50.0)), Vec::<&str>::new());
+    assert_eq!(sorted(index.skitour_routes_near(p, 50.0)), vec!["t"]);
+    assert_eq!(sorted(index.bike_trails_near(p, 50.0)), Vec::<&str>::new());
+    assert_eq!(sorted(index.bike_trails_near(p, 100.0)), vec!["b"]);
+    assert_eq!(
+        sorted(index.nordic_trails_near(p, 100.0)),
+        Vec::<&str>::new()
+    );
+}
+
+#[rstest]
+fn closest_lift(_init: Init, ski_area: SkiArea) {
+    for i in 0..1000 {
+        let p = point! { x: 5.999 + i as f64 * 0.0001, y: 45.007 };
+        let expected = ski_area
+            .lifts
+            .iter()
+            .filter_map(|(id, l)| {
+                let d = l.get_closest_point(p)?.distance;
+                (d <= 30.0).then_some((id.as_str(), d))
+            })
+            .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2));
+        assert_eq!(ski_area.get_closest_lift(p, 30.0), expected, "{p:?}");
+    }
+}
+
+#[rstest]
+fn rebuilt_after_deserialization(_init: Init, ski_area: SkiArea) {
+    let p = point! { x: 6.0, y: 45.005 };
+    assert_eq!(sorted(ski_area.index().lifts_near(p, 10.0)), vec!["l0"]);
+
+    let json = serde_json::to_string(&ski_area).unwrap();
+    let deserialized: SkiArea = serde_json::from_str(&json).unwrap();
+    assert_eq!(sorted(deserialized.index().lifts_near(p, 10.0)), vec!["l0"]);
+}
+
+#[rstest]
+fn reset_after_change(_init: Init, mut ski_area: SkiArea) {
+    let p = point! { x: 6.0, y: 45.025 };
+    assert_eq!(sorted(ski_area.index().pistes_near(p, 10.0)), vec!["p0"]);
+
+    ski_area.pistes_mut().clear();
+    assert_eq!(
+        sorted(ski_area.index().pistes_near(p, 10.0)),
+        Vec::<&str>::new()
+    );
+}
+
+#[rstest]
+fn empty(_init: Init) {
+    let ski_area = SkiArea::new(
+        create_ski_area_metadata("empty".to_string()),
+        HashMap::from([("l".to_string(), lift(6.0))]),
+        HashMap::new(),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap();
+    let p = point! { x: 6.0, y: 45.0 };
+    assert_eq!(
+        sorted(ski_area.index().pistes_near(p, 1000.0)),
+        Vec::<&str>::new()
+    );
+}
@@ -0,0 +1,359 @@
+use geo::{
+    Closest, Distance, Haversine, HaversineClosestPoint, Length, LineString,
+    MultiLineString, MultiPolygon, Point,
+};
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+
+use super::piste::{parse_attributes, parse_metadata};
+use super::{
+    Difficulty, Piste, PisteAttributes, PisteData, PisteMetadata, PisteType,
+};
+use crate::error::Result;
+use crate::osm_reader::{get_tag, parse_way, Document, Tags};
+use crate::utils::bounded_geometry::BoundedGeometry;
+use crate::utils::cancel::CancellationToken;
+
+#[cfg(feature = "specta")]
+use crate::typescript_gen::geo::MultiLineStringDef;
+
+/// A linear route that is followed from one end to the other, such as a
+/// cross-country skiing trail. Unlike downhill pistes, trails are often
+/// loops and share their ways with other trails.
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Trail {
+    #[serde(flatten)]
+    pub metadata: PisteMetadata,
+    #[serde(default)]
+    pub attributes: PisteAttributes,
+    #[cfg_attr(feature = "specta", specta(type = BoundedGeometry<MultiLineStringDef>))]
+    pub line: BoundedGeometry<MultiLineString>,
+    /// In meters.
+    pub length: f64,
+    /// The trail ends where it starts.
+    pub is_loop: bool,
+}
+
+impl Trail {
+    /// The distance of the point from the closest part of the trail.
+    pub fn distance(&self, p: Point) -> Option<f64> {
+        match self.line.item.haversine_closest_point(&p) {
+            Closest::Intersection(c) | Closest::SinglePoint(c) => {
+                Some(Haversine::distance(p, c))
+            }
+            Closest::Indeterminate => None,
+        }
+    }
+
+    /// Converts the trail to a piste without areas, so that it can be used
+    /// for finding moves on it.
+    pub fn to_piste(&self) -> Piste {
+        Piste {
+            metadata: self.metadata.clone(),
+            data: PisteData {
+                lines: self.line.item.clone(),
+                areas: MultiPolygon::new(vec![]),
+                bounding_rect: self.line.bounding_rect,
+                attributes: self.attributes.clone(),
+            },
+        }
+    }
+}
+
+/// The trails of a ski area other than downhill pistes.
+#[derive(Debug, Default)]
+pub struct Trails {
+    pub nordic: HashMap<String, Trail>,
+    pub sled: HashMap<String, Trail>,
+    pub skitour: HashMap<String, Trail>,
+    pub bike: HashMap<String, Trail>,
+}
+
+impl Trails {
+    pub fn parse(cancel: &CancellationToken, doc: &Document) -> Result<Self> {
+        Ok(Trails {
+            nordic: parse_trails(
+                cancel,
+                doc,
+                TrailKind::Piste(PisteType::Nordic),
+            )?,
+            sled: parse_trails(cancel, doc, TrailKind::Piste(PisteType::Sled))?,
+            skitour: parse_trails(
+                cancel,
+                doc,
+                TrailKind::Piste(PisteType::Skitour),
+            )?,
+            bike: parse_trails(cancel, doc, TrailKind::Bike)?,
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Trail> {
+        self.nordic
+            .values()
+            .chain(self.sled.values())
+            .chain(self.skitour.values())
+            .chain(self.bike.values())
+    }
+}
+
+/// The kind of trails to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailKind {
+    /// Winter trails with the given `piste:type`.
+    Piste(PisteType),
+    /// Downhill mountain bike trails, see [`is_downhill_mtb`].
+    Bike,
+}
+
+/// Bike parks tag their lift served trails with `mtb:type=downhill` or
+/// `mtb:type=freeride`. Other trails with `mtb:scale`, such as uphill paths,
+/// hiking trails and cross-country routes, are not ridden in a bike park.
+/// Applies to both ways and `route=mtb` relations.
+pub(crate) fn is_downhill_mtb(tags: &Tags) -> bool {
+    ["downhill", "freeride"].contains(&get_tag(tags, "mtb:type"))
+}
+
+impl TrailKind {
+    fn is_trail(&self, tags: &Tags) -> bool {
+        match self {
+            TrailKind::Piste(piste_type) => {
+                get_tag(tags, "piste:type") == piste_type.to_string()
+            }
+            TrailKind::Bike => is_downhill_mtb(tags),
+        }
+    }
+
+    fn is_route(&self, tags: &Tags) -> bool {
+        if get_tag(tags, "type") != "route" {
+            return false;
+        }
+        match self {
+            TrailKind::Piste(_) => {
+                ["piste", "ski"].contains(&get_tag(tags, "route"))
+                    && self.is_trail(tags)
+            }
+            TrailKind::Bike => {
+                get_tag(tags, "route") == "mtb" && is_downhill_mtb(tags)
+            }
+        }
+    }
+
+    fn parse_metadata(&self, tags: &Tags) -> PisteMetadata {
+        match self {
+            TrailKind::Piste(_) => parse_metadata(tags),
+            TrailKind::Bike => PisteMetadata {
+                ref_: get_tag(tags, "ref").to_string(),
+                name: get_tag(tags, "name").to_string(),
+                difficulty: parse_mtb_difficulty(tags),
+            },
+        }
+    }
+
+    fn parse_attributes(&self, tags: &Tags) -> PisteAttributes {
+        match self {
+            TrailKind::Piste(_) => parse_attributes(tags),
+            TrailKind::Bike => PisteAttributes::default(),
+        }
+    }
+}
+
+/// Maps `mtb:scale` (0-6) or, if missing, `mtb:scale:imba` (0-4) to piste
+/// difficulties.
+fn parse_mtb_difficulty(tags: &Tags) -> Difficulty {
+    let (value, max) = match get_tag(tags, "mtb:scale") {
+        "" => (get_tag(tags, "mtb:scale:imba"), 4),
+        value => (value, 6),
+    };
+    // Values such as "2+" or "2-" are common.
+    let Some(level) = value.chars().next().and_then(|c| c.to_digit(10)) else {
+        return Difficulty::Unknown;
+    };
+    match level {
+        0 => Difficulty::Novice,
+        1 => Difficulty::Easy,
+        2 => Difficulty::Intermediate,
+        3 => Difficulty::Advanced,
+        l if l <= max => Difficulty::Expert,
+        _ => Difficulty::Unknown,
+    }
+}
+
+/// Joins ways that share an end node into as few chains as possible.
+fn join_ways(mut ways: Vec<Vec<u64>>) -> Vec<Vec<u64>> {
+    ways.retain(|w| w.len() >= 2);
+    ways.reverse();
+    let mut result = Vec::new();
+    while let Some(mut chain) = ways.pop() {
+        loop {
+            if chain.first() == chain.last() {
+                break;
+            }
+            let (first, last) = (chain[0], *chain.last().unwrap());
+            let Some(i) = ways.iter().rposition(|w| {
+                [first, last].contains(&w[0])
+                    || [first, last].contains(w.last().unwrap())
+            }) else {
+                break;
+            };
+            let mut way = ways.remove(i);
+            if way[0] == last {
+                chain.extend_from_slice(&way[1..]);
+            } else if *way.last().unwrap() == last {
+                way.reverse();
+                chain.extend_from_slice(&way[1..]);
+            } else {
+                if way[0] == first {
+                    way.reverse();
+                }
+                way.pop();
+                way.append(&mut chain);
+                chain = way;
+            }
+        }
+        result.push(chain);
+    }
+    result
+}
+
+fn create_trail(
+    doc: &Document,
+    metadata: PisteMetadata,
+    attributes: PisteAttributes,
+    ways: Vec<Vec<u64>>,
+) -> Result<Trail> {
+    let chains = join_ways(ways);
+    let is_loop = chains.len() == 1 && chains[0].first() == chains[0].last();
+    let lines = chains
+        .iter()
+        .map(|c| Ok(LineString::new(parse_way(doc, c)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let line = BoundedGeometry::new(MultiLineString::new(lines))?;
+    let length = line.item.length::<Haversine>();
+    Ok(Trail {
+        metadata,
+        attributes,
+        line,
+        length,
+        is_loop,
+    })
+}
+
+/// The ID and the node IDs of a way.
+type WayNodes<'d> = (u64, &'d [u64]);
+
+/// Groups ways that share at least one node.
+fn connected_ways(ways: Vec<WayNodes>) -> Vec<Vec<WayNodes>> {
+    let mut groups: Vec<(HashSet<u64>, Vec<WayNodes>)> = Vec::new();
+    for way in ways {
+        let mut nodes: HashSet<u64> = way.1.iter().copied().collect();
+        let mut members = vec![way];
+        let mut i = 0;
+        while i < groups.len() {
+            if groups[i].0.is_disjoint(&nodes) {
+                i += 1;
+            } else {
+                let (group_nodes, mut group_members) = groups.remove(i);
+                nodes.extend(group_nodes);
+                group_members.append(&mut members);
+                members = group_members;
+            }
+        }
+        groups.push((nodes, members));
+    }
+    groups.into_iter().map(|(_, members)| members).collect()
+}
+
+/// Parses the trails of the given kind. Route relations become one trail
+/// each. Ways that are not part of a route are grouped by their metadata and
+/// connectivity. Relations and ways have separate ID spaces in OSM, so the
+/// trail IDs are prefixed with `r` for relations and `w` for ways, for
+/// example `r123` and `w123_w124`.
+pub fn parse_trails(
+    cancel: &CancellationToken,
+    doc: &Document,
+    kind: TrailKind,
+) -> Result<HashMap<String, Trail>> {
+    let mut result = HashMap::new();
+    let mut route_ways: HashSet<u64> = HashSet::new();
+
+    let mut relation_ids: Vec<&u64> = doc
+        .elements
+        .relations
+        .iter()
+        .filter(|(_, r)| kind.is_route(&r.tags))
+        .map(|(id, _)| id)
+        .collect();
+    relation_ids.sort();
+
+    for id in relation_ids {
+        cancel.check()?;
+        let relation = &doc.elements.relations[id];
+        let ways = relation
+            .members
+            .ways
+            .iter()
+            .filter_map(|m| {
+                route_ways.insert(m.ref_);
+                doc.elements.ways.get(&m.ref_).map(|w| w.nodes.clone())
+            })
+            .collect();
+        match create_trail(
+            doc,
+            kind.parse_metadata(&relation.tags),
+            kind.parse_attributes(&relation.tags),
+            ways,
+        ) {
+            Ok(trail) => {
+                result.insert(format!("r{}", id), trail);
+            }
+            Err(err) => eprintln!("{}: error parsing trail: {}", id, err),
+        }
+    }
+
+    let mut groups: HashMap<PisteMetadata, Vec<WayNodes>> = HashMap::new();
+    let mut way_ids: Vec<&u64> = doc
+        .elements
+        .ways
+        .iter()
+        .filter(|(id, way)| {
+            kind.is_trail(&way.tags) && !route_ways.contains(id)
+        })
+        .map(|(id, _)| id)
+        .collect();
+    way_ids.sort();
+    for id in way_ids {
+        let way = &doc.elements.ways[id];
+        groups
+            .entry(kind.parse_metadata(&way.tags))
+            .or_default()
+            .push((*id, &way.nodes));
+    }
+
+    for (metadata, ways) in groups {
+        for mut component in connected_ways(ways) {
+            component.sort_by_key(|(id, _)| *id);
+            cancel.check()?;
+            let id = component
+                .iter()
+                .map(|(id, _)| format!("w{}", id))
+                .collect::<Vec<_>>()
+                .join("_");
+            let mut all_attributes = component.iter().map(|(way_id, _)| {
+                kind.parse_attributes(&doc.elements.ways[way_id].tags)
+            });
+            let mut attributes = all_attributes.next().unwrap_or_default();
+            all_attributes.for_each(|a| attributes.merge(&a));
+            let ways = component.iter().map(|(_, n)| n.to_vec()).collect();
+            match create_trail(doc, metadata.clone(), attributes, ways) {
+                Ok(trail) => {
+                    result.insert(id, trail);
+                }
+                Err(err) => eprintln!("{}: error parsing trail: {}", id, err),
+            }
+        }
+    }
+
+    Ok(result)
+}
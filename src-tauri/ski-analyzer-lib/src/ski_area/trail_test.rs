@@ -0,0 +1,263 @@
+use super::trail::{parse_trails, TrailKind};
+use super::{Difficulty, Grooming, PisteType, Trail};
+use crate::osm_reader::{self as r, Osm3s};
+use crate::utils::cancel::CancellationToken;
+use crate::utils::test_util::{init, node, way_tags, Init};
+
+use rstest::rstest;
+
+use std::collections::HashMap;
+
+const NORDIC: (&str, &str) = ("piste:type", "nordic");
+const DOWNHILL_MTB: (&str, &str) = ("mtb:type", "downhill");
+
+/// The corners of a square, starting from the south west.
+fn nodes() -> HashMap<u64, r::Node> {
+    HashMap::from([
+        (0, node(6.0, 45.0)),
+        (1, node(6.01, 45.0)),
+        (2, node(6.01, 45.01)),
+        (3, node(6.0, 45.01)),
+        (4, node(6.1, 45.1)),
+        (5, node(6.11, 45.1)),
+    ])
+}
+
+fn relation(ways: &[u64], tags: &[(&str, &str)]) -> r::Relation {
+    r::Relation {
+        members: r::RelationMembers {
+            nodes: vec![],
+            ways: ways
+                .iter()
+                .map(|id| r::RelationMember {
+                    ref_: *id,
+                    role: String::new(),
+                })
+                .collect(),
+        },
+        tags: tags
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    }
+}
+
+fn parse_type(
+    kind: TrailKind,
+    ways: Vec<(u64, r::Way)>,
+    relations: Vec<(u64, r::Relation)>,
+) -> HashMap<String, Trail> {
+    let doc = r::Document {
+        osm3s: Osm3s::default(),
+        elements: r::Elements {
+            nodes: nodes(),
+            ways: ways.into_iter().collect(),
+            relations: relations.into_iter().collect(),
+        },
+    };
+    parse_trails(&CancellationToken::new(), &doc, kind).unwrap()
+}
+
+fn parse(
+    ways: Vec<(u64, r::Way)>,
+    relations: Vec<(u64, r::Relation)>,
+) -> HashMap<String, Trail> {
+    parse_type(TrailKind::Piste(PisteType::Nordic), ways, relations)
+}
+
+fn sorted_ids(trails: &HashMap<String, Trail>) -> Vec<&str> {
+    let mut result: Vec<_> = trails.keys().map(|id| id.as_str()).collect();
+    result.sort();
+    result
+}
+
+#[rstest]
+fn route_relation_loop(_init: Init) {
+    let actual = parse(
+        vec![
+            (101, way_tags(&[0, 1, 2], &[NORDIC])),
+            // Reversed way.
+            (102, way_tags(&[0, 3, 2], &[NORDIC])),
+        ],
+        vec![(
+            201,
+            relation(
+                &[101, 102],
+                &[
+                    ("type", "route"),
+                    ("route", "ski"),
+                    NORDIC,
+                    ("name", "Loop"),
+                    ("piste:difficulty", "easy"),
+                ],
+            ),
+        )],
+    );
+    assert_eq!(sorted_ids(&actual), vec!["r201"]);
+    let trail = &actual["r201"];
+    assert_eq!(trail.metadata.name, "Loop");
+    assert!(trail.is_loop);
+    assert_eq!(trail.line.item.0.len(), 1);
+    assert_eq!(trail.line.item.0[0].0.len(), 5);
+    assert!(
+        (trail.length - 4.0 * 1000.0).abs() < 500.0,
+        "length={}",
+        trail.length
+    );
+}
+
+#[rstest]
+fn shared_way(_init: Init) {
+    let route = |name| {
+        vec![
+            ("type", "route"),
+            ("route", "piste"),
+            NORDIC,
+            ("name", name),
+        ]
+    };
+    let actual = parse(
+        vec![
+            (101, way_tags(&[0, 1], &[NORDIC])),
+            (102, way_tags(&[1, 2], &[NORDIC])),
+            (103, way_tags(&[1, 3], &[NORDIC])),
+        ],
+        vec![
+            (201, relation(&[101, 102], &route("Short"))),
+            (202, relation(&[101, 103], &route("Long"))),
+        ],
+    );
+    assert_eq!(sorted_ids(&actual), vec!["r201", "r202"]);
+    assert!(!actual["r201"].is_loop);
+    assert_eq!(actual["r201"].line.item.0[0].0.len(), 3);
+    assert_eq!(actual["r202"].line.item.0[0].0.len(), 3);
+}
+
+#[rstest]
+fn ways_grouped(_init: Init) {
+    let a = &[NORDIC, ("name", "A")];
+    let actual = parse(
+        vec![
+            (101, way_tags(&[0, 1], a)),
+            (102, way_tags(&[1, 2], a)),
+            // Same name but not connected.
+            (103, way_tags(&[4, 5], a)),
+            (104, way_tags(&[2, 3], &[NORDIC, ("name", "B")])),
+            (105, way_tags(&[3, 0], &[("piste:type", "downhill")])),
+        ],
+        vec![],
+    );
+    assert_eq!(sorted_ids(&actual), vec!["w101_w102", "w103", "w104"]);
+    assert_eq!(actual["w101_w102"].metadata.name, "A");
+    assert_eq!(actual["w101_w102"].line.item.0.len(), 1);
+    assert!(!actual["w101_w102"].is_loop);
+}
+
+#[rstest]
+fn way_loop(_init: Init) {
+    let actual = parse(
+        vec![
+            (
+                101,
+                way_tags(&[0, 1, 2], &[NORDIC, ("piste:grooming", "skating")]),
+            ),
+            (102, way_tags(&[2, 3, 0], &[NORDIC, ("lit", "yes")])),
+        ],
+        vec![],
+    );
+    assert_eq!(sorted_ids(&actual), vec!["w101_w102"]);
+    let trail = &actual["w101_w102"];
+    assert!(trail.is_loop);
+    assert_eq!(trail.attributes.piste_type, PisteType::Nordic);
+    assert_eq!(trail.attributes.grooming, Some(Grooming::Skating));
+    assert_eq!(trail.attributes.lit, Some(true));
+}
+
+#[rstest]
+#[case::sled(PisteType::Sled, vec!["w102"])]
+#[case::skitour(PisteType::Skitour, vec!["r201", "w103"])]
+fn other_types(
+    _init: Init,
+    #[case] piste_type: PisteType,
+    #[case] expected: Vec<&str>,
+) {
+    let actual = parse_type(
+        TrailKind::Piste(piste_type),
+        vec![
+            (101, way_tags(&[0, 1], &[NORDIC])),
+            (102, way_tags(&[1, 2], &[("piste:type", "sled")])),
+            (103, way_tags(&[2, 3], &[("piste:type", "skitour")])),
+            (104, way_tags(&[4, 5], &[("piste:type", "skitour")])),
+        ],
+        vec![(
+            201,
+            relation(
+                &[104],
+                &[
+                    ("type", "route"),
+                    ("route", "ski"),
+                    ("piste:type", "skitour"),
+                ],
+            ),
+        )],
+    );
+    assert_eq!(sorted_ids(&actual), expected);
+}
+
+#[rstest]
+fn bike(_init: Init) {
+    let actual = parse_type(
+        TrailKind::Bike,
+        vec![
+            (
+                101,
+                way_tags(
+                    &[0, 1],
+                    &[DOWNHILL_MTB, ("mtb:scale", "2+"), ("name", "A")],
+                ),
+            ),
+            (
+                102,
+                way_tags(
+                    &[1, 2],
+                    &[DOWNHILL_MTB, ("mtb:scale", "2+"), ("name", "A")],
+                ),
+            ),
+            (
+                103,
+                way_tags(
+                    &[2, 3],
+                    &[("mtb:type", "freeride"), ("mtb:scale:imba", "4")],
+                ),
+            ),
+            (104, way_tags(&[4, 5], &[("highway", "path")])),
+            (105, way_tags(&[3, 0], &[NORDIC])),
+            (106, way_tags(&[4, 5], &[("mtb:scale", "1")])),
+            (
+                107,
+                way_tags(&[4, 5], &[("mtb:type", "xc"), ("mtb:scale", "1")]),
+            ),
+        ],
+        vec![
+            (
+                201,
+                relation(
+                    &[104],
+                    &[("type", "route"), ("route", "mtb"), DOWNHILL_MTB],
+                ),
+            ),
+            (
+                202,
+                relation(&[106], &[("type", "route"), ("route", "mtb")]),
+            ),
+        ],
+    );
+    assert_eq!(sorted_ids(&actual), vec!["r201", "w101_w102", "w103"]);
+    assert_eq!(actual["w101_w102"].metadata.name, "A");
+    assert_eq!(
+        actual["w101_w102"].metadata.difficulty,
+        Difficulty::Intermediate
+    );
+    assert_eq!(actual["w103"].metadata.difficulty, Difficulty::Expert);
+    assert_eq!(actual["r201"].metadata.difficulty, Difficulty::Unknown);
+}
@@ -1,17 +1,21 @@
 pub mod bounded_geometry;
 pub mod cancel;
 pub mod collection;
+pub mod fit;
 pub mod gpx;
 pub mod json;
 pub mod option_time_ser;
 pub mod rect;
 pub mod result;
 pub mod time_ser;
+pub mod track;
 pub mod with_id;
 
 #[cfg(test)]
 mod cancel_test;
 #[cfg(test)]
+mod fit_test;
+#[cfg(test)]
 mod option_time_ser_test;
 #[cfg(test)]
 mod rect_test;
@@ -19,3 +23,5 @@ mod rect_test;
 pub mod test_util;
 #[cfg(test)]
 mod time_ser_test;
+#[cfg(test)]
+mod track_test;
@@ -0,0 +1,296 @@
+use time::{Duration, OffsetDateTime};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use super::track::{RecordedTrack, TrackBuilder, TrackPoint};
+use crate::error::{Error, ErrorType, Result};
+
+// Seconds between the Unix epoch and the FIT epoch (1989-12-31 00:00 UTC).
+const FIT_EPOCH: i64 = 631065600;
+
+const MESG_RECORD: u16 = 20;
+const MESG_EVENT: u16 = 21;
+
+const RECORD_POSITION_LAT: u8 = 0;
+const RECORD_POSITION_LONG: u8 = 1;
+const RECORD_ALTITUDE: u8 = 2;
+const RECORD_HEART_RATE: u8 = 3;
+const RECORD_SPEED: u8 = 6;
+const RECORD_GPS_ACCURACY: u8 = 31;
+const RECORD_ENHANCED_SPEED: u8 = 73;
+const RECORD_ENHANCED_ALTITUDE: u8 = 78;
+
+const EVENT_EVENT: u8 = 0;
+const EVENT_EVENT_TYPE: u8 = 1;
+const EVENT_TIMER: u64 = 0;
+const EVENT_TYPE_STOP: u64 = 1;
+const EVENT_TYPE_STOP_ALL: u64 = 4;
+
+const FIELD_TIMESTAMP: u8 = 253;
+
+fn format_error(msg: &'static str) -> Error {
+    Error::new_s(ErrorType::FormatError, msg)
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(format_error("truncated FIT file"));
+    }
+    let (result, rest) = data.split_at(len);
+    *data = rest;
+    Ok(result)
+}
+
+fn read_u8(data: &mut &[u8]) -> Result<u8> {
+    Ok(take(data, 1)?[0])
+}
+
+#[derive(Clone, Copy)]
+struct FieldDefinition {
+    number: u8,
+    size: usize,
+    base_type: u8,
+}
+
+impl FieldDefinition {
+    /// Decodes a single integer value. Returns `None` for the invalid value of
+    /// the base type and for types that are not integers.
+    fn decode(&self, bytes: &[u8], big_endian: bool) -> Option<u64> {
+        let type_size = match self.base_type & 0x1f {
+            0x00..=0x02 | 0x0a => 1,
+            0x03 | 0x04 | 0x0b => 2,
+            0x05 | 0x06 | 0x0c => 4,
+            0x0e..=0x10 => 8,
+            _ => return None,
+        };
+        if bytes.len() != type_size {
+            return None;
+        }
+        let fold = |v: u64, b: &u8| (v << 8) | u64::from(*b);
+        let value = if big_endian {
+            bytes.iter().fold(0, fold)
+        } else {
+            bytes.iter().rev().fold(0, fold)
+        };
+        let all_ones = u64::MAX >> (64 - 8 * type_size);
+        let invalid = match self.base_type & 0x1f {
+            // Signed types.
+            0x01 | 0x03 | 0x05 | 0x0e => all_ones >> 1,
+            // Unsigned types where zero is invalid.
+            0x0a | 0x0b | 0x0c | 0x10 => 0,
+            _ => all_ones,
+        };
+        if value == invalid {
+            return None;
+        }
+        let is_signed =
+            matches!(self.base_type & 0x1f, 0x01 | 0x03 | 0x05 | 0x0e);
+        if is_signed && type_size < 8 && value > all_ones >> 1 {
+            // Sign extend.
+            Some(value | !all_ones)
+        } else {
+            Some(value)
+        }
+    }
+}
+
+struct MessageDefinition {
+    big_endian: bool,
+    global_number: u16,
+    fields: Vec<FieldDefinition>,
+    developer_data_size: usize,
+}
+
+type Fields = HashMap<u8, u64>;
+
+struct Reader {
+    definitions: HashMap<u8, MessageDefinition>,
+    last_timestamp: Option<u32>,
+    track: TrackBuilder,
+}
+
+impl Reader {
+    fn new() -> Self {
+        Reader {
+            definitions: HashMap::new(),
+            last_timestamp: None,
+            track: TrackBuilder::default(),
+        }
+    }
+
+    fn read_file(&mut self, data: &mut &[u8]) -> Result<()> {
+        let header_size = usize::from(read_u8(data)?);
+        if header_size < 12 {
+            return Err(format_error("invalid FIT header"));
+        }
+        let header = take(data, header_size - 1)?;
+        if &header[7..11] != b".FIT" {
+            return Err(format_error("not a FIT file"));
+        }
+        let data_size =
+            u32::from_le_bytes(header[3..7].try_into().unwrap()) as usize;
+        let mut records = take(data, data_size)?;
+        // CRC
+        take(data, 2)?;
+
+        self.definitions.clear();
+        while !records.is_empty() {
+            self.read_record(&mut records)?;
+        }
+        Ok(())
+    }
+
+    fn read_record(&mut self, data: &mut &[u8]) -> Result<()> {
+        let header = read_u8(data)?;
+        if header & 0x80 != 0 {
+            // Compressed timestamp header.
+            let local_type = (header >> 5) & 0x03;
+            let offset = u32::from(header & 0x1f);
+            let last = self.last_timestamp.ok_or_else(|| {
+                format_error("compressed timestamp without a reference")
+            })?;
+            let mut timestamp = (last & !0x1f) + offset;
+            if offset < last & 0x1f {
+                timestamp += 0x20;
+            }
+            self.read_data(data, local_type, Some(timestamp))
+        } else if header & 0x40 != 0 {
+            self.read_definition(data, header & 0x0f, header & 0x20 != 0)
+        } else {
+            self.read_data(data, header & 0x0f, None)
+        }
+    }
+
+    fn read_definition(
+        &mut self,
+        data: &mut &[u8],
+        local_type: u8,
+        has_developer_data: bool,
+    ) -> Result<()> {
+        // Reserved
+        read_u8(data)?;
+        let big_endian = read_u8(data)? != 0;
+        let number = take(data, 2)?.try_into().unwrap();
+        let global_number = if big_endian {
+            u16::from_be_bytes(number)
+        } else {
+            u16::from_le_bytes(number)
+        };
+        let field_count = read_u8(data)?;
+        let fields = (0..field_count)
+            .map(|_| {
+                let field = take(data, 3)?;
+                Ok(FieldDefinition {
+                    number: field[0],
+                    size: usize::from(field[1]),
+                    base_type: field[2],
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut developer_data_size = 0;
+        if has_developer_data {
+            for _ in 0..read_u8(data)? {
+                developer_data_size += usize::from(take(data, 3)?[1]);
+            }
+        }
+        self.definitions.insert(
+            local_type,
+            MessageDefinition {
+                big_endian,
+                global_number,
+                fields,
+                developer_data_size,
+            },
+        );
+        Ok(())
+    }
+
+    fn read_data(
+        &mut self,
+        data: &mut &[u8],
+        local_type: u8,
+        timestamp: Option<u32>,
+    ) -> Result<()> {
+        let definition = self
+            .definitions
+            .get(&local_type)
+            .ok_or_else(|| format_error("undefined local message type"))?;
+        let mut fields = Fields::new();
+        for field in &definition.fields {
+            let bytes = take(data, field.size)?;
+            if let Some(value) = field.decode(bytes, definition.big_endian) {
+                fields.insert(field.number, value);
+            }
+        }
+        take(data, definition.developer_data_size)?;
+
+        if let Some(timestamp) = timestamp {
+            fields.insert(FIELD_TIMESTAMP, u64::from(timestamp));
+        }
+        if let Some(timestamp) = fields.get(&FIELD_TIMESTAMP) {
+            self.last_timestamp = Some(*timestamp as u32);
+        }
+
+        match definition.global_number {
+            MESG_RECORD => self.add_record(&fields),
+            MESG_EVENT => {
+                let is_timer = fields.get(&EVENT_EVENT) == Some(&EVENT_TIMER);
+                let is_stop = matches!(
+                    fields.get(&EVENT_EVENT_TYPE),
+                    Some(&EVENT_TYPE_STOP) | Some(&EVENT_TYPE_STOP_ALL)
+                );
+                if is_timer && is_stop {
+                    self.track.end_segment();
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn add_record(&mut self, fields: &Fields) {
+        let get = |number| fields.get(&number).copied();
+        let semicircles = |number| {
+            get(number).map(|v| v as i32 as f64 * (180.0 / 2147483648.0))
+        };
+        self.track.add_point(TrackPoint {
+            lon: semicircles(RECORD_POSITION_LONG),
+            lat: semicircles(RECORD_POSITION_LAT),
+            time: get(FIELD_TIMESTAMP).map(|t| {
+                OffsetDateTime::UNIX_EPOCH
+                    + Duration::seconds(FIT_EPOCH + t as i64)
+            }),
+            elevation: get(RECORD_ENHANCED_ALTITUDE)
+                .or_else(|| get(RECORD_ALTITUDE))
+                .map(|a| a as f64 / 5.0 - 500.0),
+            speed: get(RECORD_ENHANCED_SPEED)
+                .or_else(|| get(RECORD_SPEED))
+                .map(|s| s as f64 / 1000.0),
+            accuracy: get(RECORD_GPS_ACCURACY).map(|a| a as f64),
+            heart_rate: get(RECORD_HEART_RATE).map(|hr| hr as u8),
+        });
+    }
+}
+
+/// Reads a FIT activity file. Every timer stop event starts a new track
+/// segment. The heart rate and the GPS accuracy are kept in the sensor data.
+/// The altitude is barometric on devices that have a barometer.
+pub fn read<R: Read>(mut reader: R) -> Result<RecordedTrack> {
+    let mut buffer = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    let mut data = buffer.as_slice();
+
+    let mut fit_reader = Reader::new();
+    // A file may contain multiple chained FIT files.
+    while !data.is_empty() {
+        fit_reader.read_file(&mut data)?;
+    }
+    Ok(fit_reader.track.finish())
+}
+
+pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<RecordedTrack> {
+    read(BufReader::new(File::open(path)?))
+}
@@ -0,0 +1,268 @@
+use super::fit::read;
+use super::track::{RecordedTrack, SensorData};
+use crate::error::ErrorType;
+use crate::utils::test_util::{init, Init};
+
+use gpx::Waypoint;
+use rstest::rstest;
+use time::{Duration, OffsetDateTime};
+
+const UINT8: u8 = 0x02;
+const UINT16: u8 = 0x84;
+const SINT32: u8 = 0x85;
+const UINT32: u8 = 0x86;
+const ENUM: u8 = 0x00;
+
+// Seconds since the FIT epoch for 2024-01-01 00:00 UTC.
+const BASE_TIME: u32 = 1072915200;
+
+fn file(records: &[Vec<u8>]) -> Vec<u8> {
+    let data: Vec<u8> = records.concat();
+    let mut result = vec![14, 0x20, 0x08, 0x08];
+    result.extend((data.len() as u32).to_le_bytes());
+    result.extend(b".FIT");
+    result.extend([0, 0]);
+    result.extend(data);
+    result.extend([0, 0]);
+    result
+}
+
+fn definition(
+    local_type: u8,
+    global_number: u16,
+    fields: &[(u8, u8, u8)],
+) -> Vec<u8> {
+    let mut result = vec![0x40 | local_type, 0, 0];
+    result.extend(global_number.to_le_bytes());
+    result.push(fields.len() as u8);
+    for (number, size, base_type) in fields {
+        result.extend([*number, *size, *base_type]);
+    }
+    result
+}
+
+fn record_definition() -> Vec<u8> {
+    definition(
+        0,
+        20,
+        &[
+            (253, 4, UINT32),
+            (0, 4, SINT32),
+            (1, 4, SINT32),
+            (2, 2, UINT16),
+            (6, 2, UINT16),
+            (3, 1, UINT8),
+            (31, 1, UINT8),
+        ],
+    )
+}
+
+fn semicircles(degrees: f64) -> [u8; 4] {
+    ((degrees * 2147483648.0 / 180.0).round() as i32).to_le_bytes()
+}
+
+fn record(
+    time: u32,
+    lon: f64,
+    lat: f64,
+    altitude: u16,
+    speed: u16,
+    heart_rate: u8,
+    accuracy: u8,
+) -> Vec<u8> {
+    let mut result = vec![0];
+    result.extend(time.to_le_bytes());
+    result.extend(semicircles(lat));
+    result.extend(semicircles(lon));
+    result.extend(altitude.to_le_bytes());
+    result.extend(speed.to_le_bytes());
+    result.extend([heart_rate, accuracy]);
+    result
+}
+
+fn timer_stop(local_type: u8) -> Vec<u8> {
+    let mut result = definition(local_type, 21, &[(0, 1, ENUM), (1, 1, ENUM)]);
+    result.extend([local_type, 0, 4]);
+    result
+}
+
+fn time(seconds: u32) -> gpx::Time {
+    (OffsetDateTime::UNIX_EPOCH
+        + Duration::seconds(631065600 + i64::from(seconds)))
+    .into()
+}
+
+fn points(track: &RecordedTrack) -> Vec<Vec<&Waypoint>> {
+    track.gpx.tracks[0]
+        .segments
+        .iter()
+        .map(|s| s.points.iter().collect())
+        .collect()
+}
+
+fn assert_near(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "actual={} expected={}",
+        actual,
+        expected
+    );
+}
+
+#[rstest]
+fn records(_init: Init) {
+    let data = file(&[
+        record_definition(),
+        record(BASE_TIME, 6.5, 45.25, 12500, 5500, 120, 4),
+        record(BASE_TIME + 1, -6.5, -45.25, 0xffff, 0xffff, 0xff, 0xff),
+    ]);
+    let track = read(data.as_slice()).unwrap();
+    let points = points(&track);
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].len(), 2);
+
+    let wp = points[0][0];
+    assert_near(wp.point().x(), 6.5);
+    assert_near(wp.point().y(), 45.25);
+    assert_eq!(wp.time, Some(time(BASE_TIME)));
+    assert_eq!(wp.elevation, Some(2000.0));
+    assert_eq!(wp.speed, Some(5.5));
+    assert_eq!(wp.hdop, None);
+    assert_eq!(wp.description, None);
+
+    let wp = points[0][1];
+    assert_near(wp.point().x(), -6.5);
+    assert_near(wp.point().y(), -45.25);
+    assert_eq!(wp.time, Some(time(BASE_TIME + 1)));
+    assert_eq!(wp.elevation, None);
+    assert_eq!(wp.speed, None);
+    assert_eq!(wp.hdop, None);
+
+    assert_eq!(
+        track.sensor_data,
+        vec![
+            SensorData {
+                heart_rate: Some(120),
+                accuracy: Some(4.0),
+            },
+            SensorData::default(),
+        ]
+    );
+}
+
+#[rstest]
+fn enhanced_fields(_init: Init) {
+    let mut data = definition(
+        0,
+        20,
+        &[
+            (0, 4, SINT32),
+            (1, 4, SINT32),
+            (2, 2, UINT16),
+            (78, 4, UINT32),
+            (6, 2, UINT16),
+            (73, 4, UINT32),
+        ],
+    );
+    data.push(0);
+    data.extend(semicircles(45.0));
+    data.extend(semicircles(6.0));
+    data.extend(3000u16.to_le_bytes());
+    data.extend(12500u32.to_le_bytes());
+    data.extend(1000u16.to_le_bytes());
+    data.extend(2000u32.to_le_bytes());
+
+    let track = read(file(&[data]).as_slice()).unwrap();
+    let wp = points(&track)[0][0];
+    assert_eq!(wp.time, None);
+    assert_eq!(wp.elevation, Some(2000.0));
+    assert_eq!(wp.speed, Some(2.0));
+}
+
+#[rstest]
+fn big_endian(_init: Init) {
+    let mut data = vec![0x40, 0, 1];
+    data.extend(20u16.to_be_bytes());
+    data.extend([3, 253, 4, UINT32, 0, 4, SINT32, 1, 4, SINT32]);
+    data.push(0);
+    data.extend(BASE_TIME.to_be_bytes());
+    data.extend(((45.0 * 2147483648.0 / 180.0) as i32).to_be_bytes());
+    data.extend(((-6.0 * 2147483648.0 / 180.0) as i32).to_be_bytes());
+
+    let track = read(file(&[data]).as_slice()).unwrap();
+    let wp = points(&track)[0][0];
+    assert_eq!(wp.time, Some(time(BASE_TIME)));
+    assert_near(wp.point().x(), -6.0);
+    assert_near(wp.point().y(), 45.0);
+}
+
+#[rstest]
+fn compressed_timestamp(_init: Init) {
+    let mut data = vec![
+        record_definition(),
+        definition(1, 20, &[(0, 4, SINT32), (1, 4, SINT32)]),
+    ];
+    // The low 5 bits of BASE_TIME are 0.
+    data.push(record(BASE_TIME + 30, 6.0, 45.0, 0, 0, 0, 0));
+    for offset in [31u8, 3] {
+        let mut r = vec![0x80 | (1 << 5) | offset];
+        r.extend(semicircles(45.0));
+        r.extend(semicircles(6.0));
+        data.push(r);
+    }
+
+    let track = read(file(&data).as_slice()).unwrap();
+    let times: Vec<_> = points(&track)[0].iter().map(|wp| wp.time).collect();
+    assert_eq!(
+        times,
+        vec![
+            Some(time(BASE_TIME + 30)),
+            Some(time(BASE_TIME + 31)),
+            Some(time(BASE_TIME + 35)),
+        ]
+    );
+}
+
+#[rstest]
+fn timer_stop_starts_new_segment(_init: Init) {
+    let data = file(&[
+        record_definition(),
+        record(BASE_TIME, 6.0, 45.0, 0, 0, 0, 0),
+        record(BASE_TIME + 1, 6.0, 45.0, 0, 0, 0, 0),
+        timer_stop(1),
+        // A record without position is skipped.
+        definition(2, 20, &[(253, 4, UINT32), (3, 1, UINT8)]),
+        [vec![2], (BASE_TIME + 10).to_le_bytes().to_vec(), vec![100]].concat(),
+        record(BASE_TIME + 100, 6.0, 45.0, 0, 0, 0, 0),
+    ]);
+    let track = read(data.as_slice()).unwrap();
+    let lengths: Vec<_> = points(&track).iter().map(|s| s.len()).collect();
+    assert_eq!(lengths, vec![2, 1]);
+}
+
+#[rstest]
+fn chained_files(_init: Init) {
+    let data = [
+        file(&[
+            record_definition(),
+            record(BASE_TIME, 6.0, 45.0, 0, 0, 0, 0),
+        ]),
+        file(&[
+            record_definition(),
+            record(BASE_TIME + 1, 6.0, 45.0, 0, 0, 0, 0),
+        ]),
+    ]
+    .concat();
+    let track = read(data.as_slice()).unwrap();
+    let lengths: Vec<_> = points(&track).iter().map(|s| s.len()).collect();
+    assert_eq!(lengths, vec![2]);
+}
+
+#[rstest]
+#[case::not_fit(b"<gpx></gpx>\n".to_vec())]
+#[case::truncated(file(&[record_definition(), record(BASE_TIME, 6.0, 45.0, 0, 0, 0, 0)])[..40].to_vec())]
+#[case::undefined_message(file(&[vec![3, 0]]))]
+fn invalid(_init: Init, #[case] data: Vec<u8>) {
+    let err = read(data.as_slice()).unwrap_err();
+    assert_eq!(err.get_type(), ErrorType::FormatError);
+}
@@ -1,5 +1,5 @@
 use std::fs::OpenOptions;
-use std::io::BufReader;
+use std::io::{BufReader, BufWriter};
 use std::path::Path;
 
 use gpx::Gpx;
@@ -11,3 +11,13 @@ pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Gpx> {
     let reader = BufReader::new(file);
     Ok(gpx::read(reader)?)
 }
+
+pub fn save_to_file<P: AsRef<Path>>(gpx: &Gpx, path: P) -> Result<()> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    gpx::write(gpx, BufWriter::new(file))?;
+    Ok(())
+}
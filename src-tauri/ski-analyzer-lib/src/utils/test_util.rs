@@ -3,19 +3,22 @@ use crate::config::{set_config, Config};
 use crate::gpx_analyzer::Segments;
 use crate::osm_reader as r;
 use crate::ski_area::{
-    Difficulty, Piste, PisteData, PisteMetadata, SkiArea, SkiAreaMetadata,
+    Difficulty, Lift, LiftAttributes, Piste, PisteAttributes, PisteData,
+    PisteMetadata, PointWithElevation, SkiArea, SkiAreaMetadata, Trail,
 };
 use crate::utils::bounded_geometry::BoundedGeometry;
 use crate::utils::json::save_to_file;
 
 use geo::{
-    coord, point, BoundingRect, LineString, MultiLineString, MultiPolygon,
-    Polygon, Rect,
+    coord, point, BoundingRect, Haversine, Length, LineString, MultiLineString,
+    MultiPolygon, Polygon, Rect,
 };
 use gpx::{Gpx, Track, TrackSegment, Waypoint};
 use rstest::fixture;
+use time::OffsetDateTime;
 
 use std::collections::HashMap;
+use std::fs;
 
 pub fn node(x: f64, y: f64) -> r::Node {
     r::Node {
@@ -113,10 +116,78 @@ pub fn piste(name: &str, lines: Vec<LineString>, areas: Vec<Polygon>) -> Piste {
             lines,
             areas,
             bounding_rect,
+            attributes: PisteAttributes::default(),
         },
     }
 }
 
+/// A lift with a station at each end of the line.
+pub fn lift(name: &str, points: &[Coord]) -> Lift {
+    let line = BoundedGeometry::new(line(points)).unwrap();
+    let stations = [line.item.0[0], *line.item.0.last().unwrap()]
+        .iter()
+        .map(|c| PointWithElevation::new((*c).into(), None))
+        .collect();
+    Lift {
+        ref_: String::new(),
+        name: name.to_string(),
+        type_: "chair_lift".to_string(),
+        stations,
+        lengths: vec![line.item.length::<Haversine>()],
+        line,
+        can_go_reverse: false,
+        can_disembark: false,
+        underground: false,
+        attributes: LiftAttributes::default(),
+    }
+}
+
+/// A piste with a single line.
+pub fn simple_piste(
+    name: &str,
+    difficulty: Difficulty,
+    points: &[Coord],
+) -> Piste {
+    let mut result = piste(name, vec![line(points)], vec![]);
+    result.metadata.difficulty = difficulty;
+    result
+}
+
+pub fn create_ski_area(
+    lifts: Vec<(&str, Lift)>,
+    pistes: Vec<(&str, Piste)>,
+) -> SkiArea {
+    SkiArea::new(
+        create_ski_area_metadata("Test".to_string()),
+        lifts
+            .into_iter()
+            .map(|(id, l)| (id.to_string(), l))
+            .collect(),
+        pistes
+            .into_iter()
+            .map(|(id, p)| (id.to_string(), p))
+            .collect(),
+        OffsetDateTime::UNIX_EPOCH,
+    )
+    .unwrap()
+}
+
+pub fn trail(name: &str, points: &[Coord]) -> Trail {
+    let line =
+        BoundedGeometry::new(MultiLineString::new(vec![line(points)])).unwrap();
+    Trail {
+        metadata: PisteMetadata {
+            name: name.to_string(),
+            ref_: String::new(),
+            difficulty: Difficulty::Easy,
+        },
+        attributes: PisteAttributes::default(),
+        length: line.item.length::<Haversine>(),
+        line,
+        is_loop: false,
+    }
+}
+
 pub fn make_gpx(input: Vec<TrackSegment>) -> Gpx {
     let mut track = Track::new();
     track.segments = input;
@@ -135,11 +206,24 @@ pub fn get_segments(gpx: Gpx) -> Segments {
     )
 }
 
+/// Writes an SRTM tile. Rows start from the north.
+pub fn save_hgt(rows: &[&[i16]], filename: &str) {
+    let data: Vec<u8> = rows
+        .iter()
+        .flat_map(|r| r.iter())
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+    fs::write(filename, data).unwrap();
+}
+
 pub struct Init;
 
 #[fixture]
 pub fn init() -> Init {
-    match set_config(Config { verbose: 2 }) {
+    match set_config(Config {
+        verbose: 2,
+        ..Config::default()
+    }) {
         Ok(()) => (),
         Err(_) => (),
     }
@@ -0,0 +1,205 @@
+use geo::point;
+use gpx::{Gpx, GpxVersion, Track, TrackSegment, Waypoint};
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use time::format_description::well_known::Iso8601;
+use time::OffsetDateTime;
+
+use std::fs;
+use std::mem::take;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::fit;
+use crate::error::{Error, ErrorType, Result};
+
+mod csv;
+mod kml;
+mod tcx;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackFormat {
+    Gpx,
+    Fit,
+    Tcx,
+    Kml,
+    Kmz,
+    Csv,
+}
+
+impl TrackFormat {
+    /// Detects the format of a track file by looking at its content.
+    pub fn detect(data: &[u8]) -> Result<Self> {
+        if data.get(8..12) == Some(b".FIT") {
+            return Ok(TrackFormat::Fit);
+        }
+        if data.starts_with(b"PK\x03\x04") {
+            return Ok(TrackFormat::Kmz);
+        }
+        let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+        match data.iter().find(|b| !b.is_ascii_whitespace()) {
+            None => {
+                Err(Error::new_s(ErrorType::FormatError, "empty track file"))
+            }
+            Some(b'<') => Self::detect_xml(data),
+            Some(_) => Ok(TrackFormat::Csv),
+        }
+    }
+
+    fn detect_xml(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::from_reader(data);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) | Event::Empty(e) => {
+                    return match e.local_name().as_ref() {
+                        b"gpx" => Ok(TrackFormat::Gpx),
+                        b"TrainingCenterDatabase" => Ok(TrackFormat::Tcx),
+                        b"kml" => Ok(TrackFormat::Kml),
+                        name => Err(Error::new(
+                            ErrorType::FormatError,
+                            format!(
+                                "unknown XML track format: {}",
+                                String::from_utf8_lossy(name)
+                            ),
+                        )),
+                    };
+                }
+                Event::Eof => {
+                    return Err(Error::new_s(
+                        ErrorType::FormatError,
+                        "empty XML document",
+                    ))
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+}
+
+/// Recorded values of a waypoint that GPX has no field for.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SensorData {
+    /// In beats per minute.
+    pub heart_rate: Option<u8>,
+    /// GPS accuracy in meters. Unlike `hdop`, this is not a dilution of
+    /// precision.
+    pub accuracy: Option<f64>,
+}
+
+/// A track read from a file.
+#[derive(Debug, Default, Clone)]
+pub struct RecordedTrack {
+    pub gpx: Gpx,
+    /// The sensor data of the track points of `gpx`, in the order of the
+    /// tracks, segments and points. Empty if the format has no sensor data.
+    pub sensor_data: Vec<SensorData>,
+}
+
+impl From<Gpx> for RecordedTrack {
+    fn from(gpx: Gpx) -> Self {
+        RecordedTrack {
+            gpx,
+            sensor_data: Vec::new(),
+        }
+    }
+}
+
+/// Reads a track in the given format.
+pub fn read(format: TrackFormat, data: &[u8]) -> Result<RecordedTrack> {
+    match format {
+        TrackFormat::Gpx => Ok(gpx::read(data)?.into()),
+        TrackFormat::Fit => fit::read(data),
+        TrackFormat::Tcx => tcx::read(data),
+        TrackFormat::Kml => kml::read(data),
+        TrackFormat::Kmz => kml::read_kmz(data),
+        TrackFormat::Csv => csv::read(data),
+    }
+}
+
+/// Loads a track from GPX, FIT, TCX, KML, KMZ or CSV. The format is detected
+/// from the content of the file.
+pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<RecordedTrack> {
+    let data = fs::read(path)?;
+    read(TrackFormat::detect(&data)?, &data)
+}
+
+fn parse_number<T: FromStr>(name: &str, value: &str) -> Result<T> {
+    value.trim().parse().map_err(|_| {
+        Error::new(
+            ErrorType::FormatError,
+            format!("invalid {}: {}", name, value),
+        )
+    })
+}
+
+fn parse_time(value: &str) -> Result<OffsetDateTime> {
+    OffsetDateTime::parse(value.trim(), &Iso8601::DEFAULT).map_err(|err| {
+        Error::convert(ErrorType::FormatError, "invalid time", &err)
+    })
+}
+
+/// The fields of a waypoint that the track formats have in common.
+#[derive(Default)]
+pub(super) struct TrackPoint {
+    pub lon: Option<f64>,
+    pub lat: Option<f64>,
+    pub time: Option<OffsetDateTime>,
+    pub elevation: Option<f64>,
+    pub speed: Option<f64>,
+    /// GPS accuracy in meters.
+    pub accuracy: Option<f64>,
+    pub heart_rate: Option<u8>,
+}
+
+impl TrackPoint {
+    /// Returns `None` if the point has no position.
+    fn into_waypoint(self) -> Option<(Waypoint, SensorData)> {
+        let mut result = Waypoint::new(point! { x: self.lon?, y: self.lat? });
+        result.time = self.time.map(|t| t.into());
+        result.elevation = self.elevation;
+        result.speed = self.speed;
+        let sensor_data = SensorData {
+            heart_rate: self.heart_rate,
+            accuracy: self.accuracy,
+        };
+        Some((result, sensor_data))
+    }
+}
+
+#[derive(Default)]
+pub(super) struct TrackBuilder {
+    segments: Vec<TrackSegment>,
+    current: TrackSegment,
+    sensor_data: Vec<SensorData>,
+}
+
+impl TrackBuilder {
+    pub fn add_point(&mut self, point: TrackPoint) {
+        if let Some((waypoint, sensor_data)) = point.into_waypoint() {
+            self.current.points.push(waypoint);
+            self.sensor_data.push(sensor_data);
+        }
+    }
+
+    pub fn end_segment(&mut self) {
+        if !self.current.points.is_empty() {
+            self.segments.push(take(&mut self.current));
+        }
+    }
+
+    pub fn finish(mut self) -> RecordedTrack {
+        self.end_segment();
+        let mut track = Track::new();
+        track.segments = self.segments;
+        RecordedTrack {
+            gpx: Gpx {
+                version: GpxVersion::Gpx11,
+                tracks: vec![track],
+                ..Default::default()
+            },
+            sensor_data: self.sensor_data,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use ::csv::{ReaderBuilder, StringRecord};
+use time::{Duration, OffsetDateTime};
+
+use super::{
+    parse_number, parse_time, RecordedTrack, TrackBuilder, TrackPoint,
+};
+use crate::error::{Error, ErrorType, Result};
+
+const LAT_COLUMNS: &[&str] = &["lat", "latitude"];
+const LON_COLUMNS: &[&str] = &["lon", "lng", "long", "longitude"];
+const TIME_COLUMNS: &[&str] = &["time", "timestamp", "date_time", "datetime"];
+const ELEVATION_COLUMNS: &[&str] =
+    &["ele", "elevation", "alt", "altitude", "altitude_m"];
+const SPEED_COLUMNS: &[&str] = &["speed", "speed_ms"];
+const HEART_RATE_COLUMNS: &[&str] = &["hr", "heart_rate", "heartrate"];
+
+struct Columns {
+    lat: usize,
+    lon: usize,
+    time: Option<usize>,
+    elevation: Option<usize>,
+    speed: Option<usize>,
+    heart_rate: Option<usize>,
+}
+
+impl Columns {
+    fn new(headers: &StringRecord) -> Result<Self> {
+        let find = |names: &[&str]| {
+            headers.iter().position(|h| {
+                let h = h.trim().to_lowercase();
+                names.contains(&h.as_str())
+            })
+        };
+        let required = |names: &[&str]| {
+            find(names).ok_or_else(|| {
+                Error::new(
+                    ErrorType::FormatError,
+                    format!("missing CSV column: {}", names[0]),
+                )
+            })
+        };
+        Ok(Columns {
+            lat: required(LAT_COLUMNS)?,
+            lon: required(LON_COLUMNS)?,
+            time: find(TIME_COLUMNS),
+            elevation: find(ELEVATION_COLUMNS),
+            speed: find(SPEED_COLUMNS),
+            heart_rate: find(HEART_RATE_COLUMNS),
+        })
+    }
+}
+
+fn get(record: &StringRecord, column: Option<usize>) -> Option<&str> {
+    column
+        .and_then(|c| record.get(c))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+}
+
+/// Times are either ISO 8601 or seconds since the Unix epoch.
+fn parse_csv_time(value: &str) -> Result<OffsetDateTime> {
+    match value.parse::<f64>() {
+        Ok(seconds) => {
+            Ok(OffsetDateTime::UNIX_EPOCH + Duration::seconds_f64(seconds))
+        }
+        Err(_) => parse_time(value),
+    }
+}
+
+fn detect_delimiter(data: &[u8]) -> u8 {
+    let header = data.split(|b| *b == b'\n').next().unwrap_or(data);
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| header.iter().filter(|b| *b == d).count())
+        .unwrap()
+}
+
+/// Reads a CSV file with a header row. Latitude and longitude columns are
+/// required, time, elevation, speed and heart rate are optional. The
+/// delimiter can be comma, semicolon or tab.
+pub fn read(data: &[u8]) -> Result<RecordedTrack> {
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+    let mut reader = ReaderBuilder::new()
+        .delimiter(detect_delimiter(data))
+        .flexible(true)
+        .from_reader(data);
+    let columns = Columns::new(reader.headers()?)?;
+    let mut track = TrackBuilder::default();
+
+    for record in reader.records() {
+        let record = record?;
+        let number = |name, column| {
+            get(&record, column)
+                .map(|v| parse_number(name, v))
+                .transpose()
+        };
+        track.add_point(TrackPoint {
+            lat: number("latitude", Some(columns.lat))?,
+            lon: number("longitude", Some(columns.lon))?,
+            time: get(&record, columns.time).map(parse_csv_time).transpose()?,
+            elevation: number("elevation", columns.elevation)?,
+            speed: number("speed", columns.speed)?,
+            heart_rate: get(&record, columns.heart_rate)
+                .map(|v| parse_number("heart rate", v))
+                .transpose()?,
+            ..Default::default()
+        });
+    }
+
+    Ok(track.finish())
+}
@@ -0,0 +1,132 @@
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use time::OffsetDateTime;
+use zip::ZipArchive;
+
+use std::io::{Cursor, Read};
+use std::mem::take;
+
+use super::{
+    parse_number, parse_time, RecordedTrack, TrackBuilder, TrackPoint,
+};
+use crate::error::{Error, ErrorType, Result};
+
+enum Field {
+    When,
+    Coord,
+}
+
+/// Reads the `gx:Track` elements of a KML file. Every track is a separate
+/// segment, including the tracks of a `gx:MultiTrack`. `when` elements
+/// outside of tracks, such as the time stamps of placemarks, are ignored.
+pub fn read(data: &[u8]) -> Result<RecordedTrack> {
+    let mut reader = Reader::from_reader(data);
+    let mut buf = Vec::new();
+    let mut field = None;
+    let mut in_track = false;
+    let mut track = TrackBuilder::default();
+    let mut times = Vec::new();
+    let mut coords = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                field = match e.local_name().as_ref() {
+                    b"Track" => {
+                        in_track = true;
+                        None
+                    }
+                    b"when" if in_track => Some(Field::When),
+                    b"coord" if in_track => Some(Field::Coord),
+                    _ => None,
+                }
+            }
+            Event::End(e) => {
+                field = None;
+                if e.local_name().as_ref() == b"Track" {
+                    in_track = false;
+                    add_track(&mut track, take(&mut times), take(&mut coords))?;
+                }
+            }
+            Event::Text(e) => match field {
+                Some(Field::When) => times.push(parse_time(&e.unescape()?)?),
+                Some(Field::Coord) => coords.push(parse_coord(&e.unescape()?)?),
+                None => (),
+            },
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(track.finish())
+}
+
+/// Reads the main KML document of a KMZ archive.
+pub fn read_kmz(data: &[u8]) -> Result<RecordedTrack> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let name = archive
+        .file_names()
+        .filter(|n| n.to_lowercase().ends_with(".kml"))
+        // By convention the main document is doc.kml, but any name is valid.
+        .min_by_key(|n| *n != "doc.kml")
+        .ok_or_else(|| {
+            Error::new_s(ErrorType::FormatError, "no KML document in KMZ")
+        })?
+        .to_string();
+    let mut kml = Vec::new();
+    archive.by_name(&name)?.read_to_end(&mut kml)?;
+    read(&kml)
+}
+
+/// Parses a `gx:coord` value: longitude, latitude and optional altitude,
+/// separated by spaces.
+fn parse_coord(value: &str) -> Result<(f64, f64, Option<f64>)> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    match parts.as_slice() {
+        [lon, lat] => Ok((
+            parse_number("longitude", lon)?,
+            parse_number("latitude", lat)?,
+            None,
+        )),
+        [lon, lat, alt] => Ok((
+            parse_number("longitude", lon)?,
+            parse_number("latitude", lat)?,
+            Some(parse_number("altitude", alt)?),
+        )),
+        _ => Err(Error::new(
+            ErrorType::FormatError,
+            format!("invalid coordinate: {}", value),
+        )),
+    }
+}
+
+fn add_track(
+    track: &mut TrackBuilder,
+    times: Vec<OffsetDateTime>,
+    coords: Vec<(f64, f64, Option<f64>)>,
+) -> Result<()> {
+    // Tracks without time information have no when elements.
+    if !times.is_empty() && times.len() != coords.len() {
+        return Err(Error::new(
+            ErrorType::FormatError,
+            format!(
+                "number of times ({}) and coordinates ({}) differ in track",
+                times.len(),
+                coords.len()
+            ),
+        ));
+    }
+    let mut times = times.into_iter();
+    for (lon, lat, elevation) in coords {
+        track.add_point(TrackPoint {
+            lon: Some(lon),
+            lat: Some(lat),
+            time: times.next(),
+            elevation,
+            ..Default::default()
+        });
+    }
+    track.end_segment();
+    Ok(())
+}
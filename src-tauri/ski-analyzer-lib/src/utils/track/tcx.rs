@@ -0,0 +1,72 @@
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+use std::mem::take;
+
+use super::{
+    parse_number, parse_time, RecordedTrack, TrackBuilder, TrackPoint,
+};
+use crate::error::Result;
+
+/// Reads a Garmin Training Center file. Every `Track` element is a separate
+/// segment.
+pub fn read(data: &[u8]) -> Result<RecordedTrack> {
+    let mut reader = Reader::from_reader(data);
+    let mut buf = Vec::new();
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut track = TrackBuilder::default();
+    let mut point = TrackPoint::default();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => path.push(e.local_name().as_ref().to_vec()),
+            Event::End(e) => {
+                path.pop();
+                match e.local_name().as_ref() {
+                    b"Trackpoint" => track.add_point(take(&mut point)),
+                    b"Track" => track.end_segment(),
+                    _ => (),
+                }
+            }
+            Event::Text(e) => {
+                let text = e.unescape()?;
+                let text = text.as_ref();
+                if let [.., parent, name] = path.as_slice() {
+                    read_field(&mut point, parent, name, text)?;
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(track.finish())
+}
+
+fn read_field(
+    point: &mut TrackPoint,
+    parent: &[u8],
+    name: &[u8],
+    text: &str,
+) -> Result<()> {
+    match (parent, name) {
+        (b"Trackpoint", b"Time") => point.time = Some(parse_time(text)?),
+        (b"Position", b"LatitudeDegrees") => {
+            point.lat = Some(parse_number("latitude", text)?)
+        }
+        (b"Position", b"LongitudeDegrees") => {
+            point.lon = Some(parse_number("longitude", text)?)
+        }
+        (b"Trackpoint", b"AltitudeMeters") => {
+            point.elevation = Some(parse_number("altitude", text)?)
+        }
+        (b"HeartRateBpm", b"Value") => {
+            point.heart_rate = Some(parse_number("heart rate", text)?)
+        }
+        // From the ActivityExtension schema.
+        (b"TPX", b"Speed") => point.speed = Some(parse_number("speed", text)?),
+        _ => (),
+    }
+    Ok(())
+}
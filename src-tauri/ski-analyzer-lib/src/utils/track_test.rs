@@ -0,0 +1,251 @@
+use super::track::{load_from_file, read, RecordedTrack, TrackFormat};
+use crate::error::ErrorType;
+use crate::utils::test_util::{init, Init};
+
+use gpx::Waypoint;
+use rstest::rstest;
+use time::format_description::well_known::Iso8601;
+use time::OffsetDateTime;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use std::fs;
+use std::io::{Cursor, Write};
+
+const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk><trkseg>
+    <trkpt lat="45.0" lon="6.0"><ele>1000</ele></trkpt>
+  </trkseg></trk>
+</gpx>
+"#;
+
+const TCX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase
+    xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"
+    xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
+  <Activities>
+    <Activity Sport="Other">
+      <Id>2024-01-01T09:00:00Z</Id>
+      <Lap StartTime="2024-01-01T09:00:00Z">
+        <Track>
+          <Trackpoint>
+            <Time>2024-01-01T09:00:00Z</Time>
+            <Position>
+              <LatitudeDegrees>45.1</LatitudeDegrees>
+              <LongitudeDegrees>6.1</LongitudeDegrees>
+            </Position>
+            <AltitudeMeters>1500.5</AltitudeMeters>
+            <HeartRateBpm><Value>110</Value></HeartRateBpm>
+            <Extensions>
+              <ns3:TPX><ns3:Speed>4.5</ns3:Speed></ns3:TPX>
+            </Extensions>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-01-01T09:00:05Z</Time>
+            <HeartRateBpm><Value>112</Value></HeartRateBpm>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-01-01T09:00:10Z</Time>
+            <Position>
+              <LatitudeDegrees>45.2</LatitudeDegrees>
+              <LongitudeDegrees>6.2</LongitudeDegrees>
+            </Position>
+          </Trackpoint>
+        </Track>
+        <Track>
+          <Trackpoint>
+            <Time>2024-01-01T10:00:00Z</Time>
+            <Position>
+              <LatitudeDegrees>45.3</LatitudeDegrees>
+              <LongitudeDegrees>6.3</LongitudeDegrees>
+            </Position>
+          </Trackpoint>
+        </Track>
+      </Lap>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>
+"#;
+
+const KML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2"
+    xmlns:gx="http://www.google.com/kml/ext/2.2">
+  <Document>
+    <Placemark>
+      <TimeStamp><when>2024-01-01T08:00:00Z</when></TimeStamp>
+      <gx:MultiTrack>
+        <gx:Track>
+          <when>2024-01-01T09:00:00Z</when>
+          <when>2024-01-01T09:00:05Z</when>
+          <gx:coord>6.1 45.1 1500</gx:coord>
+          <gx:coord>6.2 45.2 1400</gx:coord>
+        </gx:Track>
+        <gx:Track>
+          <gx:coord>6.3 45.3</gx:coord>
+        </gx:Track>
+      </gx:MultiTrack>
+    </Placemark>
+  </Document>
+</kml>
+"#;
+
+fn time(value: &str) -> gpx::Time {
+    OffsetDateTime::parse(value, &Iso8601::DEFAULT)
+        .unwrap()
+        .into()
+}
+
+fn points(track: &RecordedTrack) -> Vec<Vec<&Waypoint>> {
+    track
+        .gpx
+        .tracks
+        .iter()
+        .flat_map(|t| t.segments.iter())
+        .map(|s| s.points.iter().collect())
+        .collect()
+}
+
+fn coords(track: &RecordedTrack) -> Vec<Vec<(f64, f64)>> {
+    points(track)
+        .iter()
+        .map(|s| s.iter().map(|wp| wp.point().x_y()).collect())
+        .collect()
+}
+
+fn kmz(name: &str, content: &str) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored);
+    writer.start_file("files/icon.png", options).unwrap();
+    writer.write_all(b"not really an image").unwrap();
+    writer.start_file(name, options).unwrap();
+    writer.write_all(content.as_bytes()).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+#[rstest]
+#[case::gpx(GPX.as_bytes().to_vec(), TrackFormat::Gpx)]
+#[case::gpx_bom([b"\xef\xbb\xbf".as_slice(), GPX.as_bytes()].concat(), TrackFormat::Gpx)]
+#[case::tcx(TCX.as_bytes().to_vec(), TrackFormat::Tcx)]
+#[case::kml(KML.as_bytes().to_vec(), TrackFormat::Kml)]
+#[case::kmz(kmz("doc.kml", KML), TrackFormat::Kmz)]
+#[case::csv(b"lat,lon\n45,6\n".to_vec(), TrackFormat::Csv)]
+#[case::fit(b"\x0e\x20\x08\x08\0\0\0\0.FIT\0\0\0\0".to_vec(), TrackFormat::Fit)]
+fn detect(_init: Init, #[case] data: Vec<u8>, #[case] expected: TrackFormat) {
+    assert_eq!(TrackFormat::detect(&data).unwrap(), expected);
+}
+
+#[rstest]
+#[case::empty(b" \n".to_vec())]
+#[case::unknown_xml(b"<osm version=\"0.6\"></osm>".to_vec())]
+fn detect_error(_init: Init, #[case] data: Vec<u8>) {
+    let err = TrackFormat::detect(&data).unwrap_err();
+    assert_eq!(err.get_type(), ErrorType::FormatError);
+}
+
+#[rstest]
+fn tcx(_init: Init) {
+    let track = read(TrackFormat::Tcx, TCX.as_bytes()).unwrap();
+    assert_eq!(
+        coords(&track),
+        vec![vec![(6.1, 45.1), (6.2, 45.2)], vec![(6.3, 45.3)]]
+    );
+    let wp = points(&track)[0][0];
+    assert_eq!(wp.time, Some(time("2024-01-01T09:00:00Z")));
+    assert_eq!(wp.elevation, Some(1500.5));
+    assert_eq!(wp.speed, Some(4.5));
+    assert_eq!(track.sensor_data[0].heart_rate, Some(110));
+
+    let wp = points(&track)[0][1];
+    assert_eq!(wp.time, Some(time("2024-01-01T09:00:10Z")));
+    assert_eq!(wp.elevation, None);
+    assert_eq!(track.sensor_data[1].heart_rate, None);
+}
+
+#[rstest]
+fn kml(_init: Init) {
+    let track = read(TrackFormat::Kml, KML.as_bytes()).unwrap();
+    assert_eq!(
+        coords(&track),
+        vec![vec![(6.1, 45.1), (6.2, 45.2)], vec![(6.3, 45.3)]]
+    );
+    let points = points(&track);
+    assert_eq!(points[0][1].time, Some(time("2024-01-01T09:00:05Z")));
+    assert_eq!(points[0][1].elevation, Some(1400.0));
+    assert_eq!(points[1][0].time, None);
+    assert_eq!(points[1][0].elevation, None);
+}
+
+#[rstest]
+fn kml_time_mismatch(_init: Init) {
+    let kml = KML.replace("<when>2024-01-01T09:00:05Z</when>", "");
+    let err = read(TrackFormat::Kml, kml.as_bytes()).unwrap_err();
+    assert_eq!(err.get_type(), ErrorType::FormatError);
+}
+
+#[rstest]
+#[case::doc("doc.kml")]
+#[case::other_name("track.KML")]
+fn kmz_archive(_init: Init, #[case] name: &str) {
+    let data = kmz(name, KML);
+    let track = read(TrackFormat::Kmz, &data).unwrap();
+    assert_eq!(
+        coords(&track),
+        vec![vec![(6.1, 45.1), (6.2, 45.2)], vec![(6.3, 45.3)]]
+    );
+}
+
+#[rstest]
+fn kmz_without_kml(_init: Init) {
+    let data = kmz("doc.txt", KML);
+    let err = read(TrackFormat::Kmz, &data).unwrap_err();
+    assert_eq!(err.get_type(), ErrorType::FormatError);
+}
+
+#[rstest]
+fn csv(_init: Init) {
+    let data = "Time,Latitude,Longitude,Ele,HR\n\
+        2024-01-01T09:00:00Z,45.1,6.1,1500,120\n\
+        2024-01-01T09:00:05Z,45.2,6.2,,\n\
+        2024-01-01T09:00:10Z,,,1400,130\n";
+    let track = read(TrackFormat::Csv, data.as_bytes()).unwrap();
+    assert_eq!(coords(&track), vec![vec![(6.1, 45.1), (6.2, 45.2)]]);
+    let points = points(&track);
+    assert_eq!(points[0][0].time, Some(time("2024-01-01T09:00:00Z")));
+    assert_eq!(points[0][0].elevation, Some(1500.0));
+    assert_eq!(track.sensor_data[0].heart_rate, Some(120));
+    assert_eq!(points[0][1].elevation, None);
+    assert_eq!(track.sensor_data[1].heart_rate, None);
+}
+
+#[rstest]
+fn csv_semicolon_and_unix_time(_init: Init) {
+    let data = "lon;lat;timestamp;speed\n6.1;45.1;1704099600;3.5\n";
+    let track = read(TrackFormat::Csv, data.as_bytes()).unwrap();
+    let wp = points(&track)[0][0];
+    assert_eq!(wp.point().x_y(), (6.1, 45.1));
+    assert_eq!(wp.time, Some(time("2024-01-01T09:00:00Z")));
+    assert_eq!(wp.speed, Some(3.5));
+}
+
+#[rstest]
+#[case::missing_column("lat,time\n45.1,2024-01-01T09:00:00Z\n")]
+#[case::invalid_number("lat,lon\n45.1,east\n")]
+#[case::invalid_time("lat,lon,time\n45.1,6.1,yesterday\n")]
+fn csv_error(_init: Init, #[case] data: &str) {
+    let err = read(TrackFormat::Csv, data.as_bytes()).unwrap_err();
+    assert_eq!(err.get_type(), ErrorType::FormatError);
+}
+
+#[rstest]
+#[case::gpx("track.gpx", GPX.as_bytes().to_vec())]
+#[case::kmz("track.kmz", kmz("doc.kml", KML))]
+fn load(_init: Init, #[case] name: &str, #[case] data: Vec<u8>) {
+    let dir = "test_output/track_test";
+    fs::create_dir_all(dir).unwrap();
+    let path = format!("{}/{}", dir, name);
+    fs::write(&path, data).unwrap();
+    let track = load_from_file(&path).unwrap();
+    assert!(!points(&track).is_empty());
+}
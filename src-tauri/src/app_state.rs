@@ -6,6 +6,7 @@ use std::time::Duration;
 
 use ski_analyzer_lib::error::{Error, ErrorType, Result};
 use ski_analyzer_lib::gpx_analyzer::AnalyzedRoute;
+use ski_analyzer_lib::graph::Graph;
 use ski_analyzer_lib::ski_area::SkiArea;
 use ski_analyzer_lib::utils::json::{
     load_from_file, load_from_file_if_exists, save_to_file,
@@ -30,6 +31,8 @@ pub struct AppState<E: EventEmitter> {
     window_initialized: bool,
     window_saver: DelayedAction,
     ski_area: Option<Arc<(Uuid, SkiArea)>>,
+    /// The routing graph of the current ski area, built on first use.
+    graph: Option<Arc<Graph>>,
     analyzed_route: Option<AnalyzedRoute>,
 }
 
@@ -50,6 +53,7 @@ impl<E: EventEmitter> AppState<E> {
             window_initialized: false,
             window_saver: DelayedAction::new(Duration::from_secs(2)),
             ski_area: None,
+            graph: None,
             analyzed_route: None,
         }
     }
@@ -120,8 +124,25 @@ impl<E: EventEmitter> AppState<E> {
         self.ski_area.as_ref().map(|s| &**s)
     }
 
+    pub fn get_shared_ski_area(&self) -> Option<Arc<(Uuid, SkiArea)>> {
+        self.ski_area.clone()
+    }
+
+    pub fn get_graph(&self) -> Option<Arc<Graph>> {
+        self.graph.clone()
+    }
+
+    /// Caches the graph of the ski area with the given UUID. Does nothing if
+    /// another ski area was loaded while the graph was being built.
+    pub fn set_graph(&mut self, uuid: &Uuid, graph: Arc<Graph>) {
+        if self.ski_area.as_ref().map_or(false, |s| s.0 == *uuid) {
+            self.graph = Some(graph);
+        }
+    }
+
     fn set_ski_area_inner(&mut self, ski_area: SkiArea, uuid: Uuid) {
         self.ski_area = Some(Arc::new((uuid, ski_area)));
+        self.graph = None;
         let value = serde_json::to_value(&self.ski_area.as_ref().unwrap().1)
             .unwrap_or(Value::Null);
         self.emit_event("active_ski_area_changed", &value);
@@ -249,6 +270,7 @@ impl<E: EventEmitter> AppState<E> {
         if should_clear {
             config.current_ski_area = None;
             self.ski_area = None;
+            self.graph = None;
             let value = serde_json::to_value(&Option::<SkiArea>::None)
                 .unwrap_or(Value::Null);
             self.emit_event("active_ski_area_changed", &value);
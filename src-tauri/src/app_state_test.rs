@@ -1,10 +1,14 @@
 use geo::coord;
 use rstest::{fixture, rstest};
-use ski_analyzer_lib::ski_area::{SkiArea, SkiAreaMetadata};
+use ski_analyzer_lib::graph::Graph;
+use ski_analyzer_lib::ski_area::{
+    Lift, LiftAttributes, SkiArea, SkiAreaMetadata,
+};
 use ski_analyzer_lib::utils::bounded_geometry::BoundedGeometry;
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::app_state::AppState;
@@ -47,10 +51,26 @@ fn ski_area_b() -> SkiArea {
 }
 
 fn create_ski_area(name: String) -> SkiArea {
-    let bounding_rect =
-        geo::Rect::new(coord! { x: 0.0, y: 0.0 }, coord! { x: 1.0, y: 1.0 });
-    SkiArea {
-        metadata: SkiAreaMetadata {
+    let line = BoundedGeometry::new(geo::LineString::new(vec![
+        coord! { x: 0.0, y: 0.0 },
+        coord! { x: 1.0, y: 1.0 },
+    ]))
+    .unwrap();
+    let bounding_rect = line.bounding_rect;
+    let lift = Lift {
+        ref_: String::new(),
+        name: "Lift".to_string(),
+        type_: "chair_lift".to_string(),
+        line,
+        stations: Vec::new(),
+        can_go_reverse: false,
+        can_disembark: false,
+        lengths: Vec::new(),
+        underground: false,
+        attributes: LiftAttributes::default(),
+    };
+    SkiArea::new(
+        SkiAreaMetadata {
             name: name.clone(),
             id: 0,
             outline: BoundedGeometry {
@@ -67,11 +87,11 @@ fn create_ski_area(name: String) -> SkiArea {
                 bounding_rect,
             },
         },
-        lifts: HashMap::new(),
-        pistes: HashMap::new(),
-        bounding_rect,
-        date: time::OffsetDateTime::now_utc(),
-    }
+        HashMap::from([("lift".to_string(), lift)]),
+        HashMap::new(),
+        time::OffsetDateTime::now_utc(),
+    )
+    .unwrap()
 }
 
 fn get_app_state(
@@ -235,3 +255,38 @@ fn test_ski_area_events_on_load_from_file_and_cache(
         );
     }
 }
+
+#[rstest]
+fn test_graph_cache(
+    temp_dir: TempDir,
+    ski_area_a: SkiArea,
+    ski_area_b: SkiArea,
+) {
+    let (mut app_state, _) = get_app_state(temp_dir.path());
+    assert!(app_state.get_graph().is_none());
+
+    app_state.set_ski_area(ski_area_a);
+    let uuid_a = app_state.get_ski_area().unwrap().0;
+    app_state.set_graph(&uuid_a, Arc::new(Graph::default()));
+    assert!(app_state.get_graph().is_some(), "Graph should be cached");
+
+    app_state.set_ski_area(ski_area_b);
+    assert!(
+        app_state.get_graph().is_none(),
+        "Graph should be dropped when the ski area changes"
+    );
+
+    app_state.set_graph(&uuid_a, Arc::new(Graph::default()));
+    assert!(
+        app_state.get_graph().is_none(),
+        "Graph of another ski area should not be cached"
+    );
+
+    let uuid_b = app_state.get_ski_area().unwrap().0;
+    app_state.set_graph(&uuid_b, Arc::new(Graph::default()));
+    app_state.remove_cached_ski_area(&uuid_b);
+    assert!(
+        app_state.get_graph().is_none(),
+        "Graph should be dropped with the ski area"
+    );
+}
@@ -2,26 +2,37 @@ use crate::app_state::AppStateType;
 use crate::config::{CachedSkiArea, MapConfig};
 use crate::task_manager::{do_with_task, TaskHandle, TaskManagerType};
 
-use geo::{Intersects, Point, Rect};
+use geo::{Intersects, MultiLineString, Point, Rect};
 use gpx::Waypoint;
 use serde::{Deserialize, Deserializer, Serialize};
-use ski_analyzer_lib::gpx_analyzer::{analyze_route, get_lines, DerivedData};
+use ski_analyzer_lib::dem::{Dem, ElevationCorrection};
+use ski_analyzer_lib::geojson_export::{route_to_geojson, ski_area_to_geojson};
+use ski_analyzer_lib::gpx_analyzer::{
+    analyze_routes, export_gpx, get_lines, AnalysisMode, DerivedData,
+    MoveProfile, RouteStatistics, TripStatistics,
+};
+use ski_analyzer_lib::graph::{
+    plan_route as plan_route_in_graph, reachable_from_lift, way_to_valley,
+    CostModel, Graph, PlannedRoute, Reachable,
+};
 use ski_analyzer_lib::osm_query::{
     query_ski_area_details_by_id, query_ski_areas_by_coords,
     query_ski_areas_by_name,
 };
 use ski_analyzer_lib::osm_reader::Document;
-use ski_analyzer_lib::ski_area::{SkiArea, SkiAreaMetadata};
+use ski_analyzer_lib::ski_area::{Difficulty, SkiArea, SkiAreaMetadata};
 use ski_analyzer_lib::utils::bounded_geometry::BoundedGeometry;
-use ski_analyzer_lib::utils::gpx::load_from_file as load_gpx_from_file;
+use ski_analyzer_lib::utils::gpx::save_to_file as save_gpx_to_file;
 use ski_analyzer_lib::utils::json::{load_from_file, save_to_file};
+use ski_analyzer_lib::utils::track::load_from_file as load_track_from_file;
 use tauri::Manager;
 use time::format_description::well_known::Rfc3339;
-use time::OffsetDateTime;
+use time::{OffsetDateTime, UtcOffset};
 use uuid::Uuid;
 
 use core::str;
 use std::error::Error;
+use std::sync::Arc;
 
 #[derive(Serialize)]
 pub struct CachedSkiAreaWithUuid {
@@ -138,6 +149,16 @@ pub fn find_ski_areas_by_coords(
     })
 }
 
+fn open_dem(
+    app_handle: &tauri::AppHandle,
+) -> ski_analyzer_lib::error::Result<Option<(Dem, ElevationCorrection)>> {
+    let state = app_handle.state::<AppStateType>();
+    let config = state.inner().lock().unwrap().get_config().dem.clone();
+    config
+        .map(|c| Ok((Dem::open(&c.path)?, c.correction)))
+        .transpose()
+}
+
 async fn load_ski_area_from_id_inner(
     task: TaskHandle,
     id: u64,
@@ -147,7 +168,10 @@ async fn load_ski_area_from_id_inner(
         .add_async_task(query_ski_area_details_by_id(id))
         .await?;
     let doc = Document::parse(&json)?;
-    let ski_area = task.add_sync_task(|cancel| SkiArea::parse(cancel, &doc))?;
+    let dem = open_dem(&app_handle)?;
+    let ski_area = task.add_sync_task(|cancel| {
+        SkiArea::parse(cancel, &doc, dem.as_ref().map(|(d, _)| d))
+    })?;
     let state = app_handle.state::<AppStateType>();
     let mut app_state = state.inner().lock().map_err(|e| e.to_string())?;
     app_state.set_ski_area(ski_area);
@@ -182,16 +206,36 @@ pub fn load_cached_ski_area(
 
 fn load_gpx_inner(
     task: TaskHandle,
-    path: String,
+    paths: Vec<String>,
+    profile: String,
+    mode: AnalysisMode,
     app_handle: tauri::AppHandle,
 ) -> Result<(), ski_analyzer_lib::error::Error> {
-    let gpx = load_gpx_from_file(path)?;
+    let mut tracks = paths
+        .iter()
+        .map(load_track_from_file)
+        .collect::<ski_analyzer_lib::error::Result<Vec<_>>>()?;
+    if let Some((dem, correction)) = open_dem(&app_handle)? {
+        for track in &mut tracks {
+            dem.correct_elevations(&mut track.gpx, correction);
+        }
+    }
+    let profile = MoveProfile::find(&profile)?;
 
     let state = app_handle.state::<AppStateType>();
+    let filter = state
+        .inner()
+        .lock()
+        .unwrap()
+        .get_config()
+        .noise_filter
+        .clone();
 
     let (uuid, ski_area) = {
         let mut lock = state.inner().lock().unwrap();
-        let line = BoundedGeometry::new(get_lines(&gpx))?;
+        let line = BoundedGeometry::new(MultiLineString::new(
+            tracks.iter().flat_map(|t| get_lines(&t.gpx).0).collect(),
+        ))?;
         let cached = lock.get_current_cached_ski_area().ok_or_else(|| {
             ski_analyzer_lib::error::Error::new_s(
                 ski_analyzer_lib::error::ErrorType::NoSkiAreaAtLocation(
@@ -211,8 +255,9 @@ fn load_gpx_inner(
         lock.get_clipped_ski_area().unwrap().clone()
     };
 
-    let route =
-        task.add_sync_task(|cancel| analyze_route(cancel, &ski_area, gpx))?;
+    let route = task.add_sync_task(|cancel| {
+        analyze_routes(cancel, &ski_area, mode, &profile, &filter, tracks)
+    })?;
 
     let mut lock = state.inner().lock().unwrap();
     if !lock.get_ski_area().map_or(false, |(u, _)| *u == uuid) {
@@ -227,12 +272,25 @@ fn load_gpx_inner(
 }
 
 #[tauri::command]
-pub fn load_gpx(path: String, app_handle: tauri::AppHandle) -> u64 {
+pub fn load_gpx(
+    paths: Vec<String>,
+    profile: String,
+    mode: AnalysisMode,
+    app_handle: tauri::AppHandle,
+) -> u64 {
     do_with_task(app_handle.clone(), move |task| async move {
-        load_gpx_inner(task, path, app_handle)
+        load_gpx_inner(task, paths, profile, mode, app_handle)
     })
 }
 
+#[tauri::command]
+pub fn get_move_profiles() -> Vec<String> {
+    MoveProfile::builtin_names()
+        .into_iter()
+        .map(|n| n.to_string())
+        .collect()
+}
+
 fn load_route_inner(
     path: String,
     state: tauri::State<AppStateType>,
@@ -279,6 +337,92 @@ pub fn save_current_route_to_file(
     save_current_route_to_file_inner(path, state).map_err(|e| e.to_string())
 }
 
+fn export_current_route_to_gpx_inner(
+    path: String,
+    state: tauri::State<AppStateType>,
+) -> Result<(), Box<dyn Error>> {
+    let gpx = {
+        let app_state = state.inner().lock().map_err(|e| e.to_string())?;
+        match (app_state.get_ski_area(), app_state.get_route()) {
+            (Some((_, ski_area)), Some(route)) => export_gpx(ski_area, route),
+            _ => {
+                return Err(Box::new(ski_analyzer_lib::error::Error::new_s(
+                    ski_analyzer_lib::error::ErrorType::InputError,
+                    "No active route",
+                )))
+            }
+        }
+    };
+    save_gpx_to_file(&gpx, &path)?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub fn export_current_route_to_gpx(
+    path: String,
+    state: tauri::State<AppStateType>,
+) -> Result<(), String> {
+    export_current_route_to_gpx_inner(path, state).map_err(|e| e.to_string())
+}
+
+fn export_current_ski_area_to_geojson_inner(
+    path: String,
+    state: tauri::State<AppStateType>,
+) -> Result<(), Box<dyn Error>> {
+    let geojson = {
+        let app_state = state.inner().lock().map_err(|e| e.to_string())?;
+        match app_state.get_ski_area() {
+            None => {
+                return Err(Box::new(ski_analyzer_lib::error::Error::new_s(
+                    ski_analyzer_lib::error::ErrorType::InputError,
+                    "No active ski area",
+                )))
+            }
+            Some((_, ski_area)) => ski_area_to_geojson(ski_area),
+        }
+    };
+    save_to_file(&geojson, &path)?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub fn export_current_ski_area_to_geojson(
+    path: String,
+    state: tauri::State<AppStateType>,
+) -> Result<(), String> {
+    export_current_ski_area_to_geojson_inner(path, state)
+        .map_err(|e| e.to_string())
+}
+
+fn export_current_route_to_geojson_inner(
+    path: String,
+    state: tauri::State<AppStateType>,
+) -> Result<(), Box<dyn Error>> {
+    let geojson = {
+        let app_state = state.inner().lock().map_err(|e| e.to_string())?;
+        match app_state.get_route() {
+            None => {
+                return Err(Box::new(ski_analyzer_lib::error::Error::new_s(
+                    ski_analyzer_lib::error::ErrorType::InputError,
+                    "No active route",
+                )))
+            }
+            Some(route) => route_to_geojson(route),
+        }
+    };
+    save_to_file(&geojson, &path)?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub fn export_current_route_to_geojson(
+    path: String,
+    state: tauri::State<AppStateType>,
+) -> Result<(), String> {
+    export_current_route_to_geojson_inner(path, state)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_active_ski_area(
     state: tauri::State<AppStateType>,
@@ -306,6 +450,33 @@ pub fn get_active_route(
         .transpose()
 }
 
+#[tauri::command]
+pub fn get_route_statistics(
+    state: tauri::State<AppStateType>,
+) -> Result<Option<RouteStatistics>, String> {
+    let app_state = state.inner().lock().map_err(|e| e.to_string())?;
+    Ok((|| {
+        let ski_area = &app_state.get_ski_area()?.1;
+        let route = app_state.get_route()?;
+        Some(RouteStatistics::calculate(ski_area, route))
+    })())
+}
+
+#[tauri::command]
+pub fn get_trip_statistics(
+    state: tauri::State<AppStateType>,
+    utc_offset: i32,
+) -> Result<Option<TripStatistics>, String> {
+    let offset =
+        UtcOffset::from_whole_seconds(utc_offset).map_err(|e| e.to_string())?;
+    let app_state = state.inner().lock().map_err(|e| e.to_string())?;
+    Ok((|| {
+        let ski_area = &app_state.get_ski_area()?.1;
+        let route = app_state.get_route()?;
+        Some(TripStatistics::calculate(ski_area, route, offset))
+    })())
+}
+
 #[derive(Deserialize, Debug)]
 pub struct WaypointIn {
     point: Point,
@@ -364,6 +535,135 @@ pub fn get_closest_lift(
     })())
 }
 
+type SkiAreaWithGraph = (Arc<(Uuid, SkiArea)>, Arc<Graph>);
+
+/// Returns the current ski area and its routing graph. The graph is built on
+/// first use and cached until the ski area changes.
+fn get_graph(
+    task: &TaskHandle,
+    app_handle: &tauri::AppHandle,
+) -> ski_analyzer_lib::error::Result<Option<SkiAreaWithGraph>> {
+    let state = app_handle.state::<AppStateType>();
+    let (ski_area, graph) = {
+        let lock = state.inner().lock().unwrap();
+        (lock.get_shared_ski_area(), lock.get_graph())
+    };
+    let Some(ski_area) = ski_area else {
+        return Ok(None);
+    };
+    if let Some(graph) = graph {
+        return Ok(Some((ski_area, graph)));
+    }
+
+    let dem = open_dem(app_handle)?;
+    let graph = Arc::new(task.add_sync_task(|cancel| {
+        Graph::new(cancel, &ski_area.1, dem.as_ref().map(|(d, _)| d))
+    })?);
+    state
+        .inner()
+        .lock()
+        .unwrap()
+        .set_graph(&ski_area.0, graph.clone());
+    Ok(Some((ski_area, graph)))
+}
+
+fn plan_route_inner(
+    task: TaskHandle,
+    app_handle: tauri::AppHandle,
+    from: Point,
+    to: Point,
+    difficulties: Vec<Difficulty>,
+    cost_model: Option<CostModel>,
+) -> Result<Option<PlannedRoute>, ski_analyzer_lib::error::Error> {
+    let Some((ski_area, graph)) = get_graph(&task, &app_handle)? else {
+        return Ok(None);
+    };
+    Ok(plan_route_in_graph(
+        &ski_area.1,
+        &graph,
+        from,
+        to,
+        &difficulties,
+        &cost_model.unwrap_or_default(),
+    ))
+}
+
+#[tauri::command]
+pub fn plan_route(
+    app_handle: tauri::AppHandle,
+    from: Point,
+    to: Point,
+    difficulties: Vec<Difficulty>,
+    cost_model: Option<CostModel>,
+) -> u64 {
+    do_with_task(app_handle.clone(), move |task| async move {
+        plan_route_inner(task, app_handle, from, to, difficulties, cost_model)
+    })
+}
+
+fn get_reachable_pistes_inner(
+    task: TaskHandle,
+    app_handle: tauri::AppHandle,
+    p: Point,
+    limit: f64,
+    max_difficulty: Difficulty,
+) -> Result<Option<Reachable>, ski_analyzer_lib::error::Error> {
+    let Some((ski_area, graph)) = get_graph(&task, &app_handle)? else {
+        return Ok(None);
+    };
+    let Some((lift_id, _)) = ski_area.1.get_closest_lift(p, limit) else {
+        return Ok(None);
+    };
+    Ok(reachable_from_lift(
+        &ski_area.1,
+        &graph,
+        lift_id,
+        max_difficulty,
+        &CostModel::default(),
+    ))
+}
+
+#[tauri::command]
+pub fn get_reachable_pistes(
+    app_handle: tauri::AppHandle,
+    p: Point,
+    limit: f64,
+    max_difficulty: Difficulty,
+) -> u64 {
+    do_with_task(app_handle.clone(), move |task| async move {
+        get_reachable_pistes_inner(task, app_handle, p, limit, max_difficulty)
+    })
+}
+
+fn get_way_to_valley_inner(
+    task: TaskHandle,
+    app_handle: tauri::AppHandle,
+    p: Point,
+    limit: f64,
+) -> Result<Option<PlannedRoute>, ski_analyzer_lib::error::Error> {
+    let Some((ski_area, graph)) = get_graph(&task, &app_handle)? else {
+        return Ok(None);
+    };
+    Ok(way_to_valley(
+        &ski_area.1,
+        &graph,
+        p,
+        limit,
+        &CostModel::default(),
+    ))
+}
+
+#[tauri::command]
+pub fn get_way_to_valley(
+    app_handle: tauri::AppHandle,
+    p: Point,
+    limit: f64,
+) -> u64 {
+    do_with_task(app_handle.clone(), move |task| async move {
+        get_way_to_valley_inner(task, app_handle, p, limit)
+    })
+}
+
 #[tauri::command]
 pub fn save_map_config(
     state: tauri::State<AppStateType>,
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use geo::Point;
 use serde::{Deserialize, Serialize};
@@ -6,6 +7,8 @@ use tauri::{PhysicalSize, Window};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+use ski_analyzer_lib::dem::ElevationCorrection;
+use ski_analyzer_lib::gpx_analyzer::NoiseFilter;
 use ski_analyzer_lib::ski_area::{SkiArea, SkiAreaMetadata};
 use ski_analyzer_lib::utils::time_ser;
 
@@ -73,6 +76,15 @@ pub struct CachedSkiArea {
     pub clipped_uuid: Option<Uuid>,
 }
 
+/// Elevation model used to correct loaded tracks and lift stations.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DemConfig {
+    /// SRTM .hgt or GeoTIFF file, or a directory of them.
+    pub path: PathBuf,
+    #[serde(default)]
+    pub correction: ElevationCorrection,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
@@ -85,6 +97,11 @@ pub struct Config {
     pub current_ski_area: Option<Uuid>,
     #[serde(default)]
     pub ui_config: String,
+    #[serde(default)]
+    pub dem: Option<DemConfig>,
+    /// Preprocessing of loaded tracks. Disabled by default.
+    #[serde(default)]
+    pub noise_filter: NoiseFilter,
 }
 
 impl Config {
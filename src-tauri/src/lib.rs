@@ -24,7 +24,7 @@ pub fn run() {
         std::process::exit(1);
     }));
 
-    set_config(Config { verbose: 0 }).unwrap();
+    set_config(Config::default()).unwrap();
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .manage(Arc::new(Mutex::new(AppState::<TauriEventEmitter>::new())))
@@ -59,13 +59,22 @@ pub fn run() {
             commands::find_ski_areas_by_coords,
             commands::load_ski_area_from_id,
             commands::load_gpx,
+            commands::get_move_profiles,
             commands::load_route,
             commands::save_current_route_to_file,
+            commands::export_current_route_to_gpx,
+            commands::export_current_ski_area_to_geojson,
+            commands::export_current_route_to_geojson,
             commands::get_active_ski_area,
             commands::has_active_ski_area,
             commands::get_active_route,
+            commands::get_route_statistics,
+            commands::get_trip_statistics,
             commands::get_derived_data,
             commands::get_closest_lift,
+            commands::plan_route,
+            commands::get_reachable_pistes,
+            commands::get_way_to_valley,
             commands::save_map_config,
             commands::get_map_config,
             commands::load_cached_ski_area,
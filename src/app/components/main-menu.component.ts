@@ -57,6 +57,15 @@ export class MainMenuComponent {
     }
   }
 
+  public async exportSkiAreaToGeoJson(): Promise<void> {
+    const path = await save({
+      filters: [{ name: "GeoJSON", extensions: ["geojson"] }],
+    });
+    if (!!path) {
+      await this.actionsService.exportSkiAreaToGeoJson(path);
+    }
+  }
+
   public async findSkiArea(): Promise<void> {
     const dialogRef = this.dialog.open<
       NameInputDialogComponent,
@@ -82,11 +91,21 @@ export class MainMenuComponent {
   }
 
   public async loadGpx(): Promise<void> {
-    const path = await open({
-      filters: [{ name: "GPX", extensions: ["gpx"] }],
+    const paths = await open({
+      filters: [
+        {
+          name: "Tracks",
+          extensions: ["gpx", "fit", "tcx", "kml", "kmz", "csv"],
+        },
+      ],
+      multiple: true,
     });
-    if (!!path) {
-      this.actionsService.loadGpx(path);
+    if (!!paths && paths.length !== 0) {
+      this.actionsService.loadGpx(
+        paths,
+        this.configService.getConfig().moveProfile,
+        this.configService.getConfig().analysisMode,
+      );
     }
   }
 
@@ -108,6 +127,24 @@ export class MainMenuComponent {
     }
   }
 
+  public async exportRouteToGpx(): Promise<void> {
+    const path = await save({
+      filters: [{ name: "GPX", extensions: ["gpx"] }],
+    });
+    if (!!path) {
+      await this.actionsService.exportRouteToGpx(path);
+    }
+  }
+
+  public async exportRouteToGeoJson(): Promise<void> {
+    const path = await save({
+      filters: [{ name: "GeoJSON", extensions: ["geojson"] }],
+    });
+    if (!!path) {
+      await this.actionsService.exportRouteToGeoJson(path);
+    }
+  }
+
   public async cancelAllTasks(): Promise<void> {
     await this.actionsService.cancelAllTasks();
   }
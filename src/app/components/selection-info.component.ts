@@ -27,6 +27,9 @@ const liftTypes: { [type: string]: string } = {
   rope_tow: "Rope tow",
   magic_carpet: "Magic carpet",
   zip_line: "Zipline",
+  funicular: "Funicular",
+  rack: "Rack railway",
+  subway: "Underground railway",
 };
 
 const liftIcons: { [type: string]: string } = {
@@ -51,11 +54,31 @@ const difficultyColors: { [type: string]: string } = {
   Unknown: "#888",
 };
 
+const groomings: { [type: string]: string } = {
+  Classic: "Classic",
+  Skating: "Skating",
+  ClassicAndSkating: "Classic and skating",
+  Mogul: "Moguls",
+  Scooter: "Scooter",
+  Backcountry: "Not groomed",
+};
+
 const activityTypes: { [type: string]: string } = {
   Unknown: "unknown",
   UseLift: "Lift",
   EnterLift: "Enter Lift",
   ExitLift: "Exit Lift",
+  Trail: "Nordic trail",
+};
+
+const terrains: { [type: string]: string } = {
+  Piste: "Piste",
+  Freeride: "Freeride",
+  SledRun: "Sled run",
+  SkiTour: "Ski touring route",
+  BikeTrail: "Bike trail",
+  OffPiste: "Off-piste",
+  Backcountry: "Backcountry",
 };
 
 @Component({
@@ -116,6 +139,25 @@ export class SelectionInfoComponent {
     () => this.selectedPiste() || this.selectedActivity()?.moving?.piste,
   );
 
+  public pisteGrooming = computed(
+    () => groomings[this.piste()?.attributes.grooming ?? ""] ?? "",
+  );
+
+  public pisteStatus = computed(() => {
+    const attributes = this.piste()?.attributes;
+    const result: string[] = [];
+    if (!!attributes?.status) {
+      result.push(attributes.status);
+    }
+    if (attributes?.lit) {
+      result.push("Lit");
+    }
+    if (attributes?.snowmaking) {
+      result.push("Snowmaking");
+    }
+    return result;
+  });
+
   public liftName = computed(() => this.getName(this.lift()));
   public liftType = computed(() => liftTypes[this.lift()?.type ?? ""]);
 
@@ -127,7 +169,7 @@ export class SelectionInfoComponent {
   public stations = computed(() => {
     const stations = this.lift()?.stations ?? [];
     return stations.map((s) =>
-      s.elevation === 0 ? "?" : this.meters(s.elevation),
+      s.elevation == null ? "?" : this.meters(s.elevation),
     );
   });
 
@@ -136,6 +178,20 @@ export class SelectionInfoComponent {
     return lengths.map((l) => this.meters(l));
   });
 
+  public liftCapacity = computed(() => {
+    const capacity = this.lift()?.attributes.capacity;
+    return capacity != null ? capacity + " persons/h" : "";
+  });
+
+  public liftDuration = computed(() => {
+    const duration = this.lift()?.attributes.duration;
+    return duration != null ? this.minutes(duration) : "";
+  });
+
+  public liftOpeningHours = computed(
+    () => this.lift()?.attributes.opening_hours ?? "",
+  );
+
   public activityType = computed(() => {
     const activity = this.selectedActivity();
     if (activity?.type !== "Moving") {
@@ -143,6 +199,15 @@ export class SelectionInfoComponent {
     }
     return this.selectedActivity()!.moving!.move_type;
   });
+  public activityTerrain = computed(() => {
+    const terrain = this.selectedActivity()?.moving?.terrain;
+    return terrains[terrain ?? ""] ?? "";
+  });
+  public activityTrail = computed(() =>
+    this.getName(
+      this.selectedActivity()?.trail ?? this.selectedActivity()?.moving?.trail,
+    ),
+  );
   public activityLength = computed(() =>
     this.meters(this.selectedActivity()?.length ?? 0),
   );
@@ -179,7 +244,7 @@ export class SelectionInfoComponent {
 
   public elevation = computed(() => {
     const elevation = this.selectedWaypoint()?.elevation;
-    return !!elevation ? this.meters(elevation) : "";
+    return elevation != null ? this.meters(elevation) : "";
   });
 
   public inclination = computed(() => {
@@ -196,6 +261,11 @@ export class SelectionInfoComponent {
     return Math.round(len) + " m";
   }
 
+  private minutes(seconds: number) {
+    const s = Math.round(seconds);
+    return Math.floor(s / 60) + ":" + String(s % 60).padStart(2, "0");
+  }
+
   private metersPerSecond(speed: number) {
     return speed.toFixed(1) + " m/s";
   }
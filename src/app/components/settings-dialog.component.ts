@@ -20,11 +20,14 @@ import { MatIconModule } from "@angular/material/icon";
 import { MatButtonToggleModule } from "@angular/material/button-toggle";
 import { MatFormFieldModule } from "@angular/material/form-field";
 import { MapTileType, UiConfig } from "@/types/config";
+import { AnalysisMode } from "@/types/generated/generated";
 import { MatInputModule } from "@angular/material/input";
 import { MatMenuModule } from "@angular/material/menu";
+import { MatSelectModule } from "@angular/material/select";
 
 export type SettingsDialogData = {
   config: UiConfig;
+  moveProfiles: string[];
 };
 
 @Component({
@@ -40,6 +43,7 @@ export type SettingsDialogData = {
     MatIconModule,
     MatInputModule,
     MatMenuModule,
+    MatSelectModule,
     FormsModule,
     ReactiveFormsModule,
   ],
@@ -50,6 +54,8 @@ export class SettingsDialogComponent {
   public readonly formGroup = new FormGroup({
     mapTileType: new FormControl<MapTileType>("OpenStreetMap"),
     mapTileUrl: new FormControl<string>(""),
+    moveProfile: new FormControl<string>(""),
+    analysisMode: new FormControl<AnalysisMode>("Alpine"),
   });
 
   constructor(
@@ -58,6 +64,10 @@ export class SettingsDialogComponent {
   ) {
     this.formGroup.controls.mapTileType.setValue(this.data.config.mapTileType);
     this.formGroup.controls.mapTileUrl.setValue(this.data.config.mapTileUrl);
+    this.formGroup.controls.moveProfile.setValue(this.data.config.moveProfile);
+    this.formGroup.controls.analysisMode.setValue(
+      this.data.config.analysisMode,
+    );
   }
 
   @HostListener("window:keyup.enter")
@@ -12,6 +12,11 @@ import {
   CachedSkiArea,
   UiConfig,
 } from "@/types/config";
+import {
+  AnalysisMode,
+  RouteStatistics,
+  TripStatistics,
+} from "@/types/generated/generated";
 import { TasksService } from "./tasks.service";
 
 @Injectable({ providedIn: "root" })
@@ -79,24 +84,34 @@ export class ActionsService {
     await this.skiAreaChooserService.selectSkiAreas(cached, loaded, undefined);
   }
 
-  public async loadGpx(path: string): Promise<void> {
+  public async loadGpx(
+    paths: string[],
+    profile: string,
+    mode: AnalysisMode,
+  ): Promise<void> {
     try {
-      await this.tasksService.addTask(await invoke("load_gpx", { path }));
+      await this.tasksService.addTask(
+        await invoke("load_gpx", { paths, profile, mode }),
+      );
     } catch (e) {
       const err = e as Error;
       if (err.type === "NoSkiAreaAtLocation") {
         this.skiAreaChooserService.actionOnSelect = () => {
-          return this.loadGpx(path);
+          return this.loadGpx(paths, profile, mode);
         };
 
         if (await this.findSkiAreasByCoords(err.details!, true)) {
           this.skiAreaChooserService.actionOnSelect = null;
-          return this.loadGpx(path);
+          return this.loadGpx(paths, profile, mode);
         }
       }
     }
   }
 
+  public async getMoveProfiles(): Promise<string[]> {
+    return (await invoke("get_move_profiles", {})) as string[];
+  }
+
   public async loadRoute(path: string): Promise<void> {
     await invoke("load_route", { path });
   }
@@ -105,6 +120,18 @@ export class ActionsService {
     await invoke("save_current_route_to_file", { path });
   }
 
+  public async exportRouteToGpx(path: string): Promise<void> {
+    await invoke("export_current_route_to_gpx", { path });
+  }
+
+  public async exportRouteToGeoJson(path: string): Promise<void> {
+    await invoke("export_current_route_to_geojson", { path });
+  }
+
+  public async exportSkiAreaToGeoJson(path: string): Promise<void> {
+    await invoke("export_current_ski_area_to_geojson", { path });
+  }
+
   public getDerivedData(wp1: Waypoint, wp2: Waypoint): Promise<DerivedData> {
     return invoke("get_derived_data", { wp1, wp2 });
   }
@@ -126,6 +153,15 @@ export class ActionsService {
     return !!data ? JSON.parse(data as string) : undefined;
   }
 
+  public getRouteStatistics(): Promise<RouteStatistics | null> {
+    return invoke("get_route_statistics", {});
+  }
+
+  public getTripStatistics(): Promise<TripStatistics | null> {
+    const utcOffset = -new Date().getTimezoneOffset() * 60;
+    return invoke("get_trip_statistics", { utcOffset });
+  }
+
   public async loadCachedSkiArea(uuid: string): Promise<void> {
     await invoke("load_cached_ski_area", { uuid });
   }
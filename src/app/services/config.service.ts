@@ -37,13 +37,14 @@ export class ConfigService {
 
   public async openSettings() {
     const config = this.getConfig();
+    const moveProfiles = await this.actionsService.getMoveProfiles();
     this.unAutoFill(config);
     const dialogRef = this.dialog.open<
       SettingsDialogComponent,
       SettingsDialogData,
       UiConfig
     >(SettingsDialogComponent, {
-      data: { config },
+      data: { config, moveProfiles },
     });
     const result = await lastValueFrom(dialogRef.afterClosed());
     if (result) {
@@ -91,10 +92,20 @@ export class ConfigService {
         mapTileType: "OpenStreetMap",
         mapTileUrl: "",
         savedMapTiles: [],
+        moveProfile: "alpine_ski",
+        analysisMode: "Alpine",
       };
       this.autoFill(config);
-    } else if (!config.savedMapTiles) {
-      config.savedMapTiles = [];
+    } else {
+      if (!config.savedMapTiles) {
+        config.savedMapTiles = [];
+      }
+      if (!config.moveProfile) {
+        config.moveProfile = "alpine_ski";
+      }
+      if (!config.analysisMode) {
+        config.analysisMode = "Alpine";
+      }
     }
 
     this.setConfig(config);
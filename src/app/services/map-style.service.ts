@@ -121,6 +121,7 @@ export class MapStyleService {
       Moving_Unknown: "#0aa",
       Moving_Ski: "#0ff",
       Moving_Wait: "#f7b32c",
+      Trail: "#3c3",
     };
     const dashes: { [type: string]: number[] } = {
       EnterLift: [6, 4, 2, 4],
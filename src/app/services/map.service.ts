@@ -360,6 +360,23 @@ export class MapService {
         features.push(areas, lines);
       }
 
+      const trails = [
+        ...skiArea.nordic_trails.values(),
+        ...skiArea.sled_runs.values(),
+        ...skiArea.skitour_routes.values(),
+        ...skiArea.bike_trails.values(),
+      ];
+      for (const trail of trails) {
+        const style = pisteStyles[trail.difficulty] ?? pisteStyles["Unknown"];
+        const lines = new Feature(
+          new OlMultiLineString(
+            trail.line.item.map((line) => this.createLineString(line)),
+          ),
+        );
+        lines.setStyle(style.line.unselected);
+        features.push(lines);
+      }
+
       const minCoord = this.pointToCoordinate(skiArea.bounding_rect.min);
       const maxCoord = this.pointToCoordinate(skiArea.bounding_rect.max);
 
@@ -1,5 +1,6 @@
 import { Point } from "./geo";
 import { SkiAreaMetadata } from "./skiArea";
+import { AnalysisMode } from "./generated/generated";
 import { Dayjs } from "dayjs";
 import dayjs from "dayjs";
 
@@ -31,6 +32,8 @@ export type UiConfig = {
   mapTileType: MapTileType;
   mapTileUrl: string;
   savedMapTiles: SavedMapTile[];
+  moveProfile: string;
+  analysisMode: AnalysisMode;
 };
 
 export function convertCachedSkiAreas(
@@ -43,6 +43,17 @@ describe("indexSkiArea", () => {
           can_go_reverse: false,
           can_disembark: false,
           lengths: [],
+          underground: false,
+          attributes: {
+            capacity: null,
+            occupancy: null,
+            duration: null,
+            heating: null,
+            bubble: null,
+            detachable: null,
+            opening_hours: null,
+            summer: null,
+          },
         },
       },
       pistes: {
@@ -56,8 +67,21 @@ describe("indexSkiArea", () => {
           },
           areas: [],
           lines: [],
+          attributes: {
+            piste_type: "Downhill",
+            grooming: null,
+            oneway: null,
+            lit: null,
+            status: null,
+            snowmaking: null,
+            names: {},
+          },
         },
       },
+      nordic_trails: {},
+      sled_runs: {},
+      skitour_routes: {},
+      bike_trails: {},
       bounding_rect: {
         min: { x: 0, y: 0 },
         max: { x: 2, y: 2 },
@@ -98,6 +122,10 @@ describe("indexSkiArea", () => {
       },
       lifts: {},
       pistes: {},
+      nordic_trails: {},
+      sled_runs: {},
+      skitour_routes: {},
+      bike_trails: {},
       bounding_rect: {
         min: { x: 0, y: 0 },
         max: { x: 1, y: 1 },
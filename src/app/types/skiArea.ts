@@ -1,5 +1,10 @@
 import { Rect } from "./geo";
-import type { Lift, Piste, SkiAreaMetadata } from "./generated/generated";
+import type {
+  Lift,
+  Piste,
+  SkiAreaMetadata,
+  Trail,
+} from "./generated/generated";
 import { indexData } from "@/utils/data";
 import { SkiArea as RawSkiArea } from "./generated/generated";
 
@@ -11,6 +16,7 @@ export {
   PisteData,
   Piste,
   SkiAreaMetadata,
+  Trail,
 } from "./generated/generated";
 
 export { RawSkiArea };
@@ -19,6 +25,10 @@ export type SkiArea = {
   metadata: SkiAreaMetadata;
   lifts: Map<string, Lift>;
   pistes: Map<string, Piste>;
+  nordic_trails: Map<string, Trail>;
+  sled_runs: Map<string, Trail>;
+  skitour_routes: Map<string, Trail>;
+  bike_trails: Map<string, Trail>;
   bounding_rect: Rect;
   date: string;
 };
@@ -28,6 +38,10 @@ export function indexSkiArea(ski_area: RawSkiArea): SkiArea {
     metadata: ski_area.metadata,
     lifts: indexData<Lift>(ski_area.lifts),
     pistes: indexData<Piste>(ski_area.pistes),
+    nordic_trails: indexData<Trail>(ski_area.nordic_trails),
+    sled_runs: indexData<Trail>(ski_area.sled_runs),
+    skitour_routes: indexData<Trail>(ski_area.skitour_routes),
+    bike_trails: indexData<Trail>(ski_area.bike_trails),
     bounding_rect: ski_area.bounding_rect,
     date: ski_area.date,
   };
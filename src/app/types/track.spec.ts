@@ -42,6 +42,17 @@ describe("TrackConverter", () => {
           can_go_reverse: false,
           can_disembark: false,
           lengths: [],
+          underground: false,
+          attributes: {
+            capacity: null,
+            occupancy: null,
+            duration: null,
+            heating: null,
+            bubble: null,
+            detachable: null,
+            opening_hours: null,
+            summer: null,
+          },
         },
       ],
     ]),
@@ -58,9 +69,22 @@ describe("TrackConverter", () => {
           },
           areas: [],
           lines: [],
+          attributes: {
+            piste_type: "Downhill",
+            grooming: null,
+            oneway: null,
+            lit: null,
+            status: null,
+            snowmaking: null,
+            names: {},
+          },
         },
       ],
     ]),
+    nordic_trails: new Map(),
+    sled_runs: new Map(),
+    skitour_routes: new Map(),
+    bike_trails: new Map(),
     bounding_rect: {
       min: { x: 0, y: 0 },
       max: { x: 2, y: 2 },
@@ -77,7 +101,13 @@ describe("TrackConverter", () => {
         route: {
           item: [
             {
-              type: { Moving: { move_type: "Ski", piste_id: "piste1" } },
+              type: {
+                Moving: {
+                  move_type: "Ski",
+                  piste_id: "piste1",
+                  terrain: "Piste",
+                },
+              },
               route: [
                 [
                   {
@@ -110,6 +140,8 @@ describe("TrackConverter", () => {
             max: { x: 1, y: 1 },
           },
         },
+        removed_points: [],
+        heart_rate: [],
       };
 
       const result = converter.convertTrack(rawTrack);
@@ -153,6 +185,8 @@ describe("TrackConverter", () => {
             max: { x: 1, y: 1 },
           },
         },
+        removed_points: [],
+        heart_rate: [],
       };
 
       const result = converter.convertTrack(rawTrack);
@@ -183,6 +217,8 @@ describe("TrackConverter", () => {
             max: { x: 1, y: 1 },
           },
         },
+        removed_points: [],
+        heart_rate: [],
       };
 
       const result = converter.convertTrack(rawTrack);
@@ -198,7 +234,13 @@ describe("TrackConverter", () => {
         route: {
           item: [
             {
-              type: { Moving: { move_type: "Ski", piste_id: "" } },
+              type: {
+                Moving: {
+                  move_type: "Ski",
+                  piste_id: "",
+                  terrain: "OffPiste",
+                },
+              },
               route: [],
               begin_time: null,
               end_time: null,
@@ -210,6 +252,8 @@ describe("TrackConverter", () => {
             max: { x: 1, y: 1 },
           },
         },
+        removed_points: [],
+        heart_rate: [],
       };
 
       const result = converter.convertTrack(rawTrack);
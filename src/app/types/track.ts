@@ -1,7 +1,7 @@
 import dayjs from "dayjs";
 import { Dayjs } from "dayjs";
 import { Point, Rect } from "./geo";
-import { Lift, Piste, SkiArea } from "./skiArea";
+import { Lift, Piste, SkiArea, Trail } from "./skiArea";
 import {
   AnalyzedRoute,
   Moving,
@@ -40,6 +40,8 @@ export type ProcessedUseLift = {
 export type ProcessedMoving = {
   move_type: string;
   piste?: Piste;
+  trail?: Trail;
+  terrain: string;
 };
 
 export type ActivityType =
@@ -47,7 +49,8 @@ export type ActivityType =
   | "UseLift"
   | "EnterLift"
   | "ExitLift"
-  | "Moving";
+  | "Moving"
+  | "Trail";
 
 export type Activity = {
   type: ActivityType;
@@ -55,6 +58,7 @@ export type Activity = {
   enterLift?: Lift;
   exitLift?: Lift;
   moving?: ProcessedMoving;
+  trail?: Trail;
   route: Segments;
   begin_time: Dayjs | null;
   end_time: Dayjs | null;
@@ -78,6 +82,7 @@ export class TrackConverter {
         let enterLiftData: string | undefined;
         let exitLiftData: string | undefined;
         let movingData: Moving | undefined;
+        let trailData: string | undefined;
 
         if ("Unknown" in typeInfo) {
           activityType = "Unknown";
@@ -93,6 +98,9 @@ export class TrackConverter {
         } else if ("Moving" in typeInfo) {
           activityType = "Moving";
           movingData = typeInfo.Moving;
+        } else if ("Trail" in typeInfo) {
+          activityType = "Trail";
+          trailData = typeInfo.Trail;
         } else {
           activityType = "Unknown";
         }
@@ -103,6 +111,7 @@ export class TrackConverter {
           enterLift: this.getLift(enterLiftData),
           exitLift: this.getLift(exitLiftData),
           moving: this.convertMoving(movingData),
+          trail: this.getTrail(trailData),
           route: this.convertRoute(activity.route),
           begin_time: dayjs(activity.begin_time),
           end_time: dayjs(activity.end_time),
@@ -129,6 +138,14 @@ export class TrackConverter {
     return this.skiArea.pistes.get(pisteId);
   }
 
+  private getTrail(trailId: string | undefined): Trail | undefined {
+    if (!trailId) {
+      return undefined;
+    }
+
+    return this.skiArea.nordic_trails.get(trailId);
+  }
+
   private convertUseLift(input?: UseLift): ProcessedUseLift | undefined {
     if (!input) {
       return;
@@ -152,6 +169,25 @@ export class TrackConverter {
       return;
     }
 
+    const trailsByTerrain: { [terrain: string]: Map<string, Trail> } = {
+      SledRun: this.skiArea.sled_runs,
+      SkiTour: this.skiArea.skitour_routes,
+      BikeTrail: this.skiArea.bike_trails,
+    };
+    const trails = trailsByTerrain[input.terrain];
+    if (trails) {
+      const trail = trails.get(input.piste_id);
+      if (!trail) {
+        console.warn(`Trail not found with id: ${input.piste_id}`);
+      }
+
+      return {
+        move_type: input.move_type,
+        trail,
+        terrain: input.terrain,
+      };
+    }
+
     const piste = this.getPiste(input.piste_id);
     if (input.piste_id !== "" && !piste) {
       console.warn(`Piste not found with id: ${input.piste_id}`);
@@ -160,6 +196,7 @@ export class TrackConverter {
     return {
       move_type: input.move_type,
       piste,
+      terrain: input.terrain,
     };
   }
 